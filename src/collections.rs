@@ -31,9 +31,22 @@ impl<T> LimitedQueue<T> {
         self.data.back()
     }
 
+    pub fn front(&self) -> Option<&T> {
+        self.data.front()
+    }
+
     pub fn is_empty(&self) -> bool {
         self.data.is_empty()
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.data.iter()
+    }
+
+    pub fn remove_first<F: Fn(&T) -> bool>(&mut self, predicate: F) -> Option<T> {
+        let index = self.data.iter().position(predicate)?;
+        self.data.remove(index)
+    }
 }
 
 pub struct LimitedStack<T> {
@@ -72,6 +85,14 @@ impl<T> LimitedStack<T> {
     pub fn is_empty(&self) -> bool {
         self.data.is_empty()
     }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.data.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.data.iter_mut()
+    }
 }
 
 #[cfg(test)]
@@ -384,4 +405,45 @@ mod tests {
         // Теперь снова можно добавлять
         assert!(stack.push(5).is_ok());
     }
+
+    #[test]
+    fn test_queue_iter_order() {
+        let mut queue: LimitedQueue<i8> = LimitedQueue::new(3);
+
+        queue.enqueue(1).unwrap();
+        queue.enqueue(2).unwrap();
+        queue.enqueue(3).unwrap();
+
+        // Итерация от первого к последнему, без извлечения
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(queue.len(), 3);
+    }
+
+    #[test]
+    fn test_stack_iter_order() {
+        let mut stack: LimitedStack<i8> = LimitedStack::new(3);
+
+        stack.push(1).unwrap();
+        stack.push(2).unwrap();
+
+        // Итерация от дна к вершине
+        assert_eq!(stack.iter().copied().collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(stack.len(), 2);
+    }
+
+    #[test]
+    fn test_queue_remove_first() {
+        let mut queue: LimitedQueue<i8> = LimitedQueue::new(4);
+
+        queue.enqueue(1).unwrap();
+        queue.enqueue(2).unwrap();
+        queue.enqueue(3).unwrap();
+        queue.enqueue(4).unwrap();
+
+        // Удаляем первый подходящий, порядок остальных сохраняется
+        assert_eq!(queue.front(), Some(&1));
+        assert_eq!(queue.remove_first(|x| x % 2 == 0), Some(2));
+        assert_eq!(queue.remove_first(|x| *x > 10), None);
+        assert_eq!(queue.iter().copied().collect::<Vec<_>>(), vec![1, 3, 4]);
+    }
 }
@@ -0,0 +1,267 @@
+use crate::collections::LimitedQueue;
+use crate::layout::BinId;
+use crate::models::{Product, Truck, Warehouse};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchRule {
+    Any,
+    Sku(String),
+    Order(u64),
+}
+
+impl MatchRule {
+    pub fn matches(&self, product: &Product) -> bool {
+        match self {
+            MatchRule::Any => true,
+            MatchRule::Sku(sku) => product.sku == *sku,
+            MatchRule::Order(id) => product.order == Some(*id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutboundLoad {
+    pub truck: usize,
+    pub rule: MatchRule,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flow {
+    Staged,
+    Stored(BinId),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CrossDockStats {
+    pub received: usize,
+    pub cross_docked: usize,
+    pub stored: usize,
+    pub expired: usize,
+    pub total_dwell_hours: f64,
+}
+
+impl CrossDockStats {
+    // Доля входящего потока, ушедшая на отгрузку без хранения
+    pub fn cross_dock_share(&self) -> f64 {
+        if self.received == 0 {
+            return 0.0;
+        }
+        self.cross_docked as f64 / self.received as f64
+    }
+
+    pub fn average_dwell_hours(&self) -> f64 {
+        if self.cross_docked == 0 {
+            return 0.0;
+        }
+        self.total_dwell_hours / self.cross_docked as f64
+    }
+}
+
+struct Staged {
+    product: Product,
+    since: f64,
+}
+
+pub struct CrossDock {
+    staging: LimitedQueue<Staged>,
+    max_dwell_hours: f64,
+    loads: Vec<OutboundLoad>,
+    stats: CrossDockStats,
+}
+
+impl CrossDock {
+    pub fn new(staging_capacity: usize, max_dwell_hours: f64) -> Self {
+        Self {
+            staging: LimitedQueue::new(staging_capacity),
+            max_dwell_hours,
+            loads: Vec::new(),
+            stats: CrossDockStats::default(),
+        }
+    }
+
+    pub fn open_load(&mut self, truck: usize, rule: MatchRule) {
+        self.close_load(truck);
+        self.loads.push(OutboundLoad { truck, rule });
+    }
+
+    pub fn close_load(&mut self, truck: usize) {
+        self.loads.retain(|load| load.truck != truck);
+    }
+
+    pub fn loads(&self) -> &[OutboundLoad] {
+        &self.loads
+    }
+
+    pub fn is_outbound(&self, truck: usize) -> bool {
+        self.loads.iter().any(|load| load.truck == truck)
+    }
+
+    pub fn staged(&self) -> usize {
+        self.staging.len()
+    }
+
+    pub fn stats(&self) -> &CrossDockStats {
+        &self.stats
+    }
+
+    // Товар под открытую отгрузку ждёт в зоне комплектации, остальное идёт на хранение
+    pub fn receive(
+        &mut self,
+        product: Product,
+        now: f64,
+        warehouse: &mut Warehouse,
+    ) -> Result<Flow, &'static str> {
+        let wanted = self.loads.iter().any(|load| load.rule.matches(&product));
+        if wanted && self.staging.len() < self.staging.max_size {
+            self.staging.enqueue(Staged {
+                product,
+                since: now,
+            })?;
+            self.stats.received += 1;
+            return Ok(Flow::Staged);
+        }
+        let bin = warehouse.load(product)?;
+        self.stats.received += 1;
+        self.stats.stored += 1;
+        Ok(Flow::Stored(bin))
+    }
+
+    pub fn unload_inbound(
+        &mut self,
+        inbound: &mut Truck,
+        now: f64,
+        warehouse: &mut Warehouse,
+    ) -> usize {
+        let mut unloaded = 0;
+        while let Some(product) = inbound.cargo().last() {
+            let wanted = self.loads.iter().any(|load| load.rule.matches(product));
+            let fits = wanted && self.staging.len() < self.staging.max_size;
+            if !fits && !warehouse.can_accept(product) {
+                break;
+            }
+            let Ok(product) = inbound.unload() else { break };
+            self.receive(product, now, warehouse)
+                .expect("staging or storage has room");
+            unloaded += 1;
+        }
+        unloaded
+    }
+
+    pub fn load_outbound(&mut self, truck: usize, outbound: &mut Truck, now: f64) -> usize {
+        let Some(load) = self.loads.iter().find(|load| load.truck == truck) else {
+            return 0;
+        };
+        let mut loaded = 0;
+        // Берём только то, что машина точно примет, и переносим без копий
+        while let Some(staged) = self.staging.remove_first(|staged| {
+            load.rule.matches(&staged.product) && outbound.check_load(&staged.product).is_ok()
+        }) {
+            outbound.load(staged.product).expect("load was checked");
+            self.stats.cross_docked += 1;
+            self.stats.total_dwell_hours += now - staged.since;
+            loaded += 1;
+        }
+        loaded
+    }
+
+    pub fn max_dwell_hours(&self) -> f64 {
+        self.max_dwell_hours
+    }
+
+    // Всё, что пролежало допустимое время, уходит на хранение
+    pub fn expire(&mut self, now: f64, warehouse: &mut Warehouse) -> usize {
+        let mut moved = 0;
+        let max_dwell = self.max_dwell_hours;
+        while let Some(staged) = self.staging.remove_first(|staged| {
+            now - staged.since >= max_dwell && warehouse.can_accept(&staged.product)
+        }) {
+            warehouse.load(staged.product).expect("warehouse has room");
+            self.stats.expired += 1;
+            self.stats.stored += 1;
+            moved += 1;
+        }
+        moved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Factory;
+
+    fn inbound(skus: &[&str]) -> Truck {
+        let mut factory = Factory::new("CrossFactory".to_string());
+        let mut truck = Truck::new("Inbound".to_string(), 10);
+        for sku in skus {
+            truck.load(factory.create_sku(sku).unwrap()).unwrap();
+        }
+        truck
+    }
+
+    #[test]
+    fn test_matching_products_bypass_storage() {
+        let mut cross_dock = CrossDock::new(10, 4.0);
+        let mut warehouse = Warehouse::new(10);
+        let mut truck = inbound(&["bolt", "nut", "bolt"]);
+        let mut outbound = Truck::new("Outbound".to_string(), 10);
+        cross_dock.open_load(1, MatchRule::Sku("bolt".to_string()));
+
+        assert_eq!(
+            cross_dock.unload_inbound(&mut truck, 0.0, &mut warehouse),
+            3
+        );
+        assert_eq!(cross_dock.staged(), 2);
+        assert_eq!(warehouse.count_sku("nut"), 1);
+
+        assert_eq!(cross_dock.load_outbound(1, &mut outbound, 1.5), 2);
+        assert_eq!(cross_dock.load_outbound(2, &mut outbound, 1.5), 0);
+        assert!(outbound.cargo().all(|p| p.sku == "bolt"));
+        let stats = cross_dock.stats();
+        assert_eq!(
+            (stats.received, stats.cross_docked, stats.stored),
+            (3, 2, 1)
+        );
+        assert_eq!(stats.cross_dock_share(), 2.0 / 3.0);
+        assert_eq!(stats.average_dwell_hours(), 1.5);
+    }
+
+    #[test]
+    fn test_order_rule_and_full_staging_fall_back_to_storage() {
+        let mut cross_dock = CrossDock::new(1, 4.0);
+        let mut warehouse = Warehouse::new(10);
+        let mut factory = Factory::new("CrossFactory".to_string());
+        cross_dock.open_load(0, MatchRule::Order(7));
+
+        let mut first = factory.create().unwrap();
+        first.order = Some(7);
+        let second = first.clone();
+        assert_eq!(
+            cross_dock.receive(first, 0.0, &mut warehouse),
+            Ok(Flow::Staged)
+        );
+        assert!(matches!(
+            cross_dock.receive(second, 0.0, &mut warehouse),
+            Ok(Flow::Stored(_))
+        ));
+        assert!(matches!(
+            cross_dock.receive(factory.create().unwrap(), 0.0, &mut warehouse),
+            Ok(Flow::Stored(_))
+        ));
+        assert_eq!(warehouse.loaded(), 2);
+    }
+
+    #[test]
+    fn test_dwell_limit_moves_stage_to_storage() {
+        let mut cross_dock = CrossDock::new(5, 4.0);
+        let mut warehouse = Warehouse::new(10);
+        let mut truck = inbound(&["bolt", "bolt"]);
+        cross_dock.open_load(1, MatchRule::Any);
+        cross_dock.unload_inbound(&mut truck, 0.0, &mut warehouse);
+
+        assert_eq!(cross_dock.expire(3.5, &mut warehouse), 0);
+        assert_eq!(cross_dock.expire(4.0, &mut warehouse), 2);
+        assert_eq!(cross_dock.staged(), 0);
+        assert_eq!(warehouse.count_sku("bolt"), 2);
+        assert_eq!(cross_dock.stats().expired, 2);
+    }
+}
@@ -0,0 +1,319 @@
+use crate::collections::LimitedQueue;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Appointment {
+    pub truck: usize,
+    pub door: usize,
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Appointment {
+    fn overlaps(&self, start: f64, end: f64) -> bool {
+        start < self.end && self.start < end
+    }
+
+    fn covers(&self, time: f64) -> bool {
+        self.start <= time && time < self.end
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DockStatus {
+    AtDoor(usize),
+    Queued(usize),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DockStats {
+    pub arrivals: usize,
+    pub served: usize,
+    pub turned_away: usize,
+    pub total_wait_hours: f64,
+    pub max_wait_hours: f64,
+    pub max_yard: usize,
+}
+
+impl DockStats {
+    pub fn average_wait_hours(&self) -> f64 {
+        if self.served == 0 {
+            return 0.0;
+        }
+        self.total_wait_hours / self.served as f64
+    }
+}
+
+struct Door {
+    truck: Option<usize>,
+    busy_hours: f64,
+}
+
+// Ворота площадки: грузовики в воротах, очередь во дворе и записи на разгрузку
+pub struct Docks {
+    doors: Vec<Door>,
+    unload_per_hour: f64,
+    load_per_hour: f64,
+    yard: LimitedQueue<(usize, f64)>,
+    appointments: Vec<Appointment>,
+    stats: DockStats,
+}
+
+impl Docks {
+    pub fn new(doors: usize, yard_capacity: usize) -> Self {
+        Self {
+            doors: (0..doors)
+                .map(|_| Door {
+                    truck: None,
+                    busy_hours: 0.0,
+                })
+                .collect(),
+            unload_per_hour: 20.0,
+            load_per_hour: 20.0,
+            yard: LimitedQueue::new(yard_capacity),
+            appointments: Vec::new(),
+            stats: DockStats::default(),
+        }
+    }
+
+    pub fn with_rates(mut self, unload_per_hour: f64, load_per_hour: f64) -> Self {
+        self.unload_per_hour = unload_per_hour;
+        self.load_per_hour = load_per_hour;
+        self
+    }
+
+    pub fn doors(&self) -> usize {
+        self.doors.len()
+    }
+
+    pub fn stats(&self) -> &DockStats {
+        &self.stats
+    }
+
+    pub fn appointments(&self) -> &[Appointment] {
+        &self.appointments
+    }
+
+    pub fn yard(&self) -> impl Iterator<Item = usize> + '_ {
+        self.yard.iter().map(|(truck, _)| *truck)
+    }
+
+    pub fn door_of(&self, truck: usize) -> Option<usize> {
+        self.doors.iter().position(|door| door.truck == Some(truck))
+    }
+
+    pub fn holds(&self, truck: usize) -> bool {
+        self.door_of(truck).is_some() || self.yard().any(|queued| queued == truck)
+    }
+
+    pub fn service_hours(&self, unloaded: usize, loaded: usize) -> f64 {
+        unloaded as f64 / self.unload_per_hour + loaded as f64 / self.load_per_hour
+    }
+
+    // Самый ранний слот нужной длины на любых воротах
+    pub fn book(
+        &mut self,
+        truck: usize,
+        earliest: f64,
+        hours: f64,
+    ) -> Result<Appointment, &'static str> {
+        if hours <= 0.0 {
+            return Err("Slot length must be positive");
+        }
+        if self.doors.is_empty() {
+            return Err("No dock doors");
+        }
+        let mut best: Option<Appointment> = None;
+        for door in 0..self.doors.len() {
+            let booked: Vec<&Appointment> = self
+                .appointments
+                .iter()
+                .filter(|a| a.door == door)
+                .collect();
+            let mut starts: Vec<f64> = booked
+                .iter()
+                .map(|a| a.end)
+                .filter(|end| *end > earliest)
+                .collect();
+            starts.push(earliest);
+            starts.sort_by(f64::total_cmp);
+            let start = starts
+                .into_iter()
+                .find(|start| !booked.iter().any(|a| a.overlaps(*start, start + hours)))
+                .unwrap_or(earliest);
+            if best.is_none_or(|best| start < best.start) {
+                best = Some(Appointment {
+                    truck,
+                    door,
+                    start,
+                    end: start + hours,
+                });
+            }
+        }
+        let appointment = best.ok_or("No dock doors")?;
+        self.appointments.push(appointment);
+        Ok(appointment)
+    }
+
+    fn can_use(&self, door: usize, truck: usize, now: f64) -> bool {
+        self.doors[door].truck.is_none()
+            && !self
+                .appointments
+                .iter()
+                .any(|a| a.door == door && a.truck != truck && a.covers(now))
+    }
+
+    pub fn arrive(&mut self, truck: usize, now: f64) -> Result<DockStatus, &'static str> {
+        self.stats.arrivals += 1;
+        // Записанный грузовик сначала пробует свои ворота
+        let booked = self
+            .appointments
+            .iter()
+            .find(|a| a.truck == truck && a.end > now)
+            .map(|a| a.door)
+            .filter(|door| self.can_use(*door, truck, now));
+        let door =
+            booked.or_else(|| (0..self.doors.len()).find(|door| self.can_use(*door, truck, now)));
+        if let Some(door) = door {
+            self.start(door, truck, 0.0);
+            return Ok(DockStatus::AtDoor(door));
+        }
+        if self.yard.enqueue((truck, now)).is_err() {
+            self.stats.turned_away += 1;
+            return Err("Yard is full");
+        }
+        self.stats.max_yard = self.stats.max_yard.max(self.yard.len());
+        Ok(DockStatus::Queued(self.yard.len()))
+    }
+
+    fn start(&mut self, door: usize, truck: usize, waited: f64) {
+        self.doors[door].truck = Some(truck);
+        self.appointments.retain(|a| a.truck != truck);
+        self.stats.served += 1;
+        self.stats.total_wait_hours += waited;
+        self.stats.max_wait_hours = self.stats.max_wait_hours.max(waited);
+    }
+
+    pub fn occupy(&mut self, door: usize, hours: f64) {
+        self.doors[door].busy_hours += hours;
+    }
+
+    // Освобождает ворота и ставит в них следующий грузовик из двора
+    pub fn release(&mut self, truck: usize, now: f64) -> Option<(usize, usize)> {
+        let door = self.door_of(truck)?;
+        self.doors[door].truck = None;
+        self.admit_at(door, now)
+    }
+
+    // После окончания слота ворота, придержанные для неприехавшего грузовика, отдаются двору
+    pub fn admit(&mut self, now: f64) -> Vec<(usize, usize)> {
+        let mut admitted = Vec::new();
+        for door in 0..self.doors.len() {
+            if self.doors[door].truck.is_some() {
+                continue;
+            }
+            if let Some(next) = self.admit_at(door, now) {
+                admitted.push(next);
+            }
+        }
+        admitted
+    }
+
+    fn admit_at(&mut self, door: usize, now: f64) -> Option<(usize, usize)> {
+        let appointments = &self.appointments;
+        let next = self
+            .yard
+            .remove_first(|(queued, _)| {
+                appointments
+                    .iter()
+                    .any(|a| a.truck == *queued && a.door == door && a.start <= now)
+            })
+            .or_else(|| {
+                let reserved = appointments.iter().any(|a| a.door == door && a.covers(now));
+                if reserved { None } else { self.yard.dequeue() }
+            });
+        let (next, arrived) = next?;
+        self.start(door, next, now - arrived);
+        Some((next, door))
+    }
+
+    pub fn utilization(&self, now: f64) -> f64 {
+        if now <= 0.0 || self.doors.is_empty() {
+            return 0.0;
+        }
+        let busy: f64 = self.doors.iter().map(|door| door.busy_hours.min(now)).sum();
+        busy / (self.doors.len() as f64 * now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trucks_queue_when_doors_busy() {
+        let mut docks = Docks::new(1, 2);
+
+        assert_eq!(docks.arrive(0, 0.0), Ok(DockStatus::AtDoor(0)));
+        assert_eq!(docks.arrive(1, 0.5), Ok(DockStatus::Queued(1)));
+        assert_eq!(docks.arrive(2, 1.0), Ok(DockStatus::Queued(2)));
+        assert_eq!(docks.arrive(3, 1.0), Err("Yard is full"));
+        assert!(docks.holds(2));
+
+        assert_eq!(docks.release(0, 2.0), Some((1, 0)));
+        assert_eq!(docks.release(1, 3.0), Some((2, 0)));
+        assert_eq!(docks.release(2, 4.0), None);
+
+        let stats = docks.stats();
+        assert_eq!(stats.arrivals, 4);
+        assert_eq!(stats.served, 3);
+        assert_eq!(stats.turned_away, 1);
+        assert_eq!(stats.max_yard, 2);
+        assert_eq!(stats.max_wait_hours, 2.0);
+        assert_eq!(stats.average_wait_hours(), 3.5 / 3.0);
+    }
+
+    #[test]
+    fn test_booking_finds_free_slot() {
+        let mut docks = Docks::new(2, 5);
+
+        assert_eq!(docks.book(0, 8.0, 2.0).unwrap().door, 0);
+        assert_eq!(docks.book(1, 8.0, 2.0).unwrap().door, 1);
+        let third = docks.book(2, 9.0, 1.0).unwrap();
+        assert_eq!((third.door, third.start, third.end), (0, 10.0, 11.0));
+        assert_eq!(docks.book(3, 0.0, 0.0), Err("Slot length must be positive"));
+    }
+
+    #[test]
+    fn test_appointment_reserves_door() {
+        let mut docks = Docks::new(1, 5);
+        docks.book(7, 1.0, 2.0).unwrap();
+
+        // Во время чужого слота ворота закрыты для остальных
+        assert_eq!(docks.arrive(0, 1.5), Ok(DockStatus::Queued(1)));
+        assert_eq!(docks.arrive(7, 1.5), Ok(DockStatus::AtDoor(0)));
+        assert!(docks.appointments().is_empty());
+        assert_eq!(docks.release(7, 2.0), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_admit_after_missed_slot() {
+        let mut docks = Docks::new(1, 5);
+        docks.book(7, 0.0, 2.0).unwrap();
+        assert_eq!(docks.arrive(0, 1.0), Ok(DockStatus::Queued(1)));
+
+        assert!(docks.admit(1.5).is_empty());
+        assert_eq!(docks.admit(2.0), vec![(0, 0)]);
+        assert_eq!(docks.stats().max_wait_hours, 1.0);
+    }
+
+    #[test]
+    fn test_service_time_and_utilization() {
+        let mut docks = Docks::new(2, 1).with_rates(10.0, 5.0);
+
+        assert_eq!(docks.service_hours(10, 5), 2.0);
+        docks.arrive(0, 0.0).unwrap();
+        docks.occupy(0, 2.0);
+        assert_eq!(docks.utilization(4.0), 0.25);
+        assert_eq!(docks.utilization(0.0), 0.0);
+    }
+}
@@ -0,0 +1,608 @@
+use crate::routing::Route;
+
+// Упрощённые правила режима труда и отдыха водителей (как в ЕС)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HoursRules {
+    max_continuous_driving: f64,
+    break_hours: f64,
+    max_daily_driving: f64,
+    daily_rest_hours: f64,
+    max_weekly_driving: f64,
+    weekly_rest_hours: f64,
+}
+
+impl HoursRules {
+    pub fn new(
+        max_continuous_driving: f64,
+        max_daily_driving: f64,
+        max_weekly_driving: f64,
+    ) -> Result<Self, &'static str> {
+        // С нулевым лимитом план не продвинется ни на километр
+        let limits = [
+            max_continuous_driving,
+            max_daily_driving,
+            max_weekly_driving,
+        ];
+        if limits.iter().any(|limit| limit.is_nan() || *limit <= 0.0) {
+            return Err("Driving limits must be positive");
+        }
+        Ok(Self {
+            max_continuous_driving,
+            max_daily_driving,
+            max_weekly_driving,
+            ..Self::default()
+        })
+    }
+
+    pub fn with_rest(
+        mut self,
+        break_hours: f64,
+        daily_rest_hours: f64,
+        weekly_rest_hours: f64,
+    ) -> Result<Self, &'static str> {
+        let rests = [break_hours, daily_rest_hours, weekly_rest_hours];
+        if rests.iter().any(|rest| rest.is_nan() || *rest < 0.0) {
+            return Err("Rest periods must not be negative");
+        }
+        self.break_hours = break_hours;
+        self.daily_rest_hours = daily_rest_hours;
+        self.weekly_rest_hours = weekly_rest_hours;
+        Ok(self)
+    }
+}
+
+impl Default for HoursRules {
+    fn default() -> Self {
+        Self {
+            max_continuous_driving: 4.5,
+            break_hours: 0.75,
+            max_daily_driving: 9.0,
+            daily_rest_hours: 11.0,
+            max_weekly_driving: 56.0,
+            weekly_rest_hours: 45.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    Driving,
+    Break,
+    DailyRest,
+    WeeklyRest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Duty {
+    pub activity: Activity,
+    pub start: f64,
+    pub end: f64,
+    // Сколько километров маршрута пройдено к началу записи
+    pub km: f64,
+}
+
+impl Duty {
+    pub fn new(activity: Activity, start: f64, end: f64) -> Self {
+        Self {
+            activity,
+            start,
+            end,
+            km: 0.0,
+        }
+    }
+
+    pub fn hours(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    ContinuousDriving,
+    DailyDriving,
+    WeeklyDriving,
+    DailyRest,
+    WeeklyRest,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Violation {
+    pub rule: Rule,
+    pub at: f64,
+    pub excess_hours: f64,
+}
+
+// Накопленное время за рулём с последнего перерыва, суточного и недельного отдыха
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Counters {
+    continuous: f64,
+    daily: f64,
+    weekly: f64,
+}
+
+impl Counters {
+    fn apply(&mut self, duty: &Duty, rules: &HoursRules, violations: &mut Vec<Violation>) {
+        let hours = duty.hours();
+        match duty.activity {
+            Activity::Driving => {
+                let limits = [
+                    (
+                        Rule::ContinuousDriving,
+                        self.continuous,
+                        rules.max_continuous_driving,
+                    ),
+                    (Rule::DailyDriving, self.daily, rules.max_daily_driving),
+                    (Rule::WeeklyDriving, self.weekly, rules.max_weekly_driving),
+                ];
+                for (rule, driven, limit) in limits {
+                    let total = driven + hours;
+                    if total <= limit + 1e-9 {
+                        continue;
+                    }
+                    // Одно нарушение на каждый непрерывный отрезок, дальше растёт превышение
+                    match violations
+                        .iter_mut()
+                        .rev()
+                        .find(|violation| violation.rule == rule)
+                    {
+                        Some(violation) if driven > limit + 1e-9 => {
+                            violation.excess_hours = total - limit;
+                        }
+                        _ => violations.push(Violation {
+                            rule,
+                            at: duty.start + (limit - driven).max(0.0),
+                            excess_hours: total - limit,
+                        }),
+                    }
+                }
+                self.continuous += hours;
+                self.daily += hours;
+                self.weekly += hours;
+            }
+            Activity::Break => {
+                if hours + 1e-9 >= rules.break_hours {
+                    self.continuous = 0.0;
+                }
+            }
+            Activity::DailyRest => {
+                if hours + 1e-9 < rules.daily_rest_hours {
+                    violations.push(Violation {
+                        rule: Rule::DailyRest,
+                        at: duty.start,
+                        excess_hours: rules.daily_rest_hours - hours,
+                    });
+                }
+                self.continuous = 0.0;
+                self.daily = 0.0;
+            }
+            Activity::WeeklyRest => {
+                if hours + 1e-9 < rules.weekly_rest_hours {
+                    violations.push(Violation {
+                        rule: Rule::WeeklyRest,
+                        at: duty.start,
+                        excess_hours: rules.weekly_rest_hours - hours,
+                    });
+                }
+                *self = Counters::default();
+            }
+        }
+    }
+}
+
+pub fn check(duties: &[Duty], rules: &HoursRules) -> Vec<Violation> {
+    let mut counters = Counters::default();
+    let mut violations = Vec::new();
+    for duty in duties {
+        counters.apply(duty, rules, &mut violations);
+    }
+    violations
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    pub duties: Vec<Duty>,
+    pub start: f64,
+    pub end: f64,
+}
+
+impl Schedule {
+    pub fn hours_of(&self, activity: Activity) -> f64 {
+        self.duties
+            .iter()
+            .filter(|duty| duty.activity == activity)
+            .map(Duty::hours)
+            .sum()
+    }
+
+    pub fn breaks(&self) -> usize {
+        self.duties
+            .iter()
+            .filter(|duty| duty.activity != Activity::Driving)
+            .count()
+    }
+}
+
+pub struct Driver {
+    name: String,
+    rules: HoursRules,
+    log: Vec<Duty>,
+    available_from: f64,
+}
+
+impl Driver {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            rules: HoursRules::default(),
+            log: Vec::new(),
+            available_from: 0.0,
+        }
+    }
+
+    pub fn with_rules(mut self, rules: HoursRules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn rules(&self) -> &HoursRules {
+        &self.rules
+    }
+
+    pub fn log(&self) -> &[Duty] {
+        &self.log
+    }
+
+    pub fn available_from(&self) -> f64 {
+        self.available_from
+    }
+
+    pub fn record(&mut self, duty: Duty) {
+        self.available_from = self.available_from.max(duty.end);
+        self.log.push(duty);
+    }
+
+    pub fn violations(&self) -> Vec<Violation> {
+        check(&self.log, &self.rules)
+    }
+
+    fn counters(&self) -> Counters {
+        let mut counters = Counters::default();
+        let mut ignored = Vec::new();
+        for duty in &self.log {
+            counters.apply(duty, &self.rules, &mut ignored);
+        }
+        counters
+    }
+
+    // Простой между рейсами засчитывается как отдых подходящей длины
+    fn idle_rest(&self, start: f64) -> Option<Duty> {
+        let last = self.log.last()?.end;
+        let idle = start - last;
+        let activity = if idle + 1e-9 >= self.rules.weekly_rest_hours {
+            Activity::WeeklyRest
+        } else if idle + 1e-9 >= self.rules.daily_rest_hours {
+            Activity::DailyRest
+        } else if idle + 1e-9 >= self.rules.break_hours {
+            Activity::Break
+        } else {
+            return None;
+        };
+        Some(Duty::new(activity, last, start))
+    }
+
+    // Расписание рейса с перерывами и отдыхом там, где упирается лимит
+    pub fn plan(&self, route: &Route, start: f64) -> Schedule {
+        let rules = &self.rules;
+        let mut counters = self.counters();
+        let mut now = start.max(self.available_from);
+        let start = now;
+        let mut km = 0.0;
+        let mut duties: Vec<Duty> = Vec::new();
+        let mut ignored = Vec::new();
+        if let Some(rest) = self.idle_rest(start) {
+            counters.apply(&rest, rules, &mut ignored);
+        }
+        let mut push = |duty: Duty, counters: &mut Counters, duties: &mut Vec<Duty>| {
+            counters.apply(&duty, rules, &mut ignored);
+            match duties.last_mut() {
+                Some(last) if last.activity == duty.activity && last.end == duty.start => {
+                    last.end = duty.end;
+                }
+                _ => duties.push(duty),
+            }
+        };
+
+        for leg in &route.legs {
+            let mut left = leg.travel_hours;
+            while left > 1e-9 {
+                let rest = if counters.weekly + 1e-9 >= rules.max_weekly_driving {
+                    Some((Activity::WeeklyRest, rules.weekly_rest_hours))
+                } else if counters.daily + 1e-9 >= rules.max_daily_driving {
+                    Some((Activity::DailyRest, rules.daily_rest_hours))
+                } else if counters.continuous + 1e-9 >= rules.max_continuous_driving {
+                    Some((Activity::Break, rules.break_hours))
+                } else {
+                    None
+                };
+                if let Some((activity, hours)) = rest {
+                    let duty = Duty {
+                        activity,
+                        start: now,
+                        end: now + hours,
+                        km,
+                    };
+                    push(duty, &mut counters, &mut duties);
+                    now += hours;
+                    continue;
+                }
+                let chunk = left
+                    .min(rules.max_continuous_driving - counters.continuous)
+                    .min(rules.max_daily_driving - counters.daily)
+                    .min(rules.max_weekly_driving - counters.weekly);
+                let duty = Duty {
+                    activity: Activity::Driving,
+                    start: now,
+                    end: now + chunk,
+                    km,
+                };
+                push(duty, &mut counters, &mut duties);
+                if leg.travel_hours > 0.0 {
+                    km += leg.distance_km * chunk / leg.travel_hours;
+                }
+                now += chunk;
+                left -= chunk;
+            }
+        }
+        Schedule {
+            duties,
+            start,
+            end: now,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DriverAssignment {
+    pub driver: usize,
+    pub truck: usize,
+    pub schedule: Schedule,
+}
+
+pub struct Crew {
+    drivers: Vec<Driver>,
+    assignments: Vec<DriverAssignment>,
+}
+
+impl Crew {
+    pub fn new() -> Self {
+        Self {
+            drivers: Vec::new(),
+            assignments: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, driver: Driver) -> usize {
+        self.drivers.push(driver);
+        self.drivers.len() - 1
+    }
+
+    pub fn driver(&self, id: usize) -> Option<&Driver> {
+        self.drivers.get(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.drivers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.drivers.is_empty()
+    }
+
+    pub fn assignments(&self, driver: usize) -> impl Iterator<Item = &DriverAssignment> {
+        self.assignments.iter().filter(move |a| a.driver == driver)
+    }
+
+    pub fn truck_driver(&self, truck: usize, at: f64) -> Option<usize> {
+        self.assignments
+            .iter()
+            .find(|a| a.truck == truck && a.schedule.start <= at && at < a.schedule.end)
+            .map(|a| a.driver)
+    }
+
+    pub fn assign(
+        &mut self,
+        driver: usize,
+        truck: usize,
+        route: &Route,
+        start: f64,
+    ) -> Result<&DriverAssignment, &'static str> {
+        let person = self.drivers.get_mut(driver).ok_or("Unknown driver")?;
+        if person.available_from > start + 1e-9 {
+            return Err("Driver is already assigned");
+        }
+        let schedule = person.plan(route, start);
+        if let Some(rest) = person.idle_rest(schedule.start) {
+            person.record(rest);
+        }
+        for duty in &schedule.duties {
+            person.record(*duty);
+        }
+        self.assignments.push(DriverAssignment {
+            driver,
+            truck,
+            schedule,
+        });
+        Ok(self.assignments.last().expect("just pushed"))
+    }
+
+    // Свободный водитель, который закончит рейс раньше остальных
+    pub fn assign_first_free(
+        &mut self,
+        truck: usize,
+        route: &Route,
+        start: f64,
+    ) -> Result<usize, &'static str> {
+        let driver = self
+            .drivers
+            .iter()
+            .enumerate()
+            .filter(|(_, driver)| driver.available_from <= start + 1e-9)
+            .map(|(id, driver)| (id, driver.plan(route, start).end))
+            .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+            .map(|(id, _)| id)
+            .ok_or("No driver available")?;
+        self.assign(driver, truck, route, start)?;
+        Ok(driver)
+    }
+
+    pub fn violations(&self) -> Vec<(usize, Violation)> {
+        self.drivers
+            .iter()
+            .enumerate()
+            .flat_map(|(id, driver)| {
+                driver
+                    .violations()
+                    .into_iter()
+                    .map(move |violation| (id, violation))
+            })
+            .collect()
+    }
+}
+
+impl Default for Crew {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Location, NodeId, RoadNetwork, SiteKind};
+
+    // Длинный перегон: 12 часов езды по 80 км/ч
+    fn long_haul() -> (RoadNetwork, NodeId, NodeId) {
+        let mut network = RoadNetwork::new();
+        let depot = network.add_location(Location::new("depot", SiteKind::Warehouse, 0.0, 0.0));
+        let port = network.add_location(Location::new("port", SiteKind::Market, 960.0, 0.0));
+        network.add_two_way_road(depot, port, 960.0, 12.0).unwrap();
+        (network, depot, port)
+    }
+
+    #[test]
+    fn test_plan_inserts_breaks_and_daily_rest() {
+        let (network, depot, port) = long_haul();
+        let route = network.route(depot, port).unwrap();
+        let driver = Driver::new("Ivan");
+
+        let schedule = driver.plan(&route, 6.0);
+        let activities: Vec<Activity> = schedule.duties.iter().map(|d| d.activity).collect();
+        assert_eq!(
+            activities,
+            vec![
+                Activity::Driving,
+                Activity::Break,
+                Activity::Driving,
+                Activity::DailyRest,
+                Activity::Driving,
+            ]
+        );
+        assert_eq!(schedule.hours_of(Activity::Driving), 12.0);
+        assert_eq!(schedule.end, 6.0 + 12.0 + 0.75 + 11.0);
+        // Перерыв после 4.5 часов, то есть на 360-м километре
+        assert_eq!(schedule.duties[1].km, 360.0);
+        assert!(check(&schedule.duties, driver.rules()).is_empty());
+    }
+
+    #[test]
+    fn test_rules_reject_degenerate_limits() {
+        assert_eq!(
+            HoursRules::new(0.0, 9.0, 56.0).err(),
+            Some("Driving limits must be positive")
+        );
+        assert!(HoursRules::new(4.5, f64::NAN, 56.0).is_err());
+        assert_eq!(
+            HoursRules::default().with_rest(-0.5, 11.0, 45.0).err(),
+            Some("Rest periods must not be negative")
+        );
+
+        // Короткие смены без перерывов: по два часа, потом суточный отдых
+        let (network, depot, port) = long_haul();
+        let route = network.route(depot, port).unwrap();
+        let rules = HoursRules::new(2.0, 2.0, 56.0)
+            .and_then(|rules| rules.with_rest(0.0, 1.0, 45.0))
+            .unwrap();
+        let schedule = Driver::new("Petr").with_rules(rules).plan(&route, 0.0);
+        assert_eq!(schedule.hours_of(Activity::Driving), 12.0);
+        assert!(check(&schedule.duties, &rules).is_empty());
+    }
+
+    #[test]
+    fn test_check_reports_violations() {
+        let rules = HoursRules::default();
+        let duties = vec![
+            Duty::new(Activity::Driving, 0.0, 6.0),
+            Duty::new(Activity::Break, 6.0, 6.25),
+            Duty::new(Activity::Driving, 6.25, 10.25),
+            Duty::new(Activity::DailyRest, 10.25, 18.25),
+        ];
+
+        let violations = check(&duties, &rules);
+        assert_eq!(violations.len(), 3);
+        assert_eq!(violations[0].rule, Rule::ContinuousDriving);
+        assert_eq!(violations[0].at, 4.5);
+        // Короткий перерыв не обнуляет непрерывное вождение
+        assert_eq!(violations[0].excess_hours, 5.5);
+        assert_eq!(violations[1].rule, Rule::DailyDriving);
+        assert_eq!(violations[1].excess_hours, 1.0);
+        assert_eq!(violations[2].rule, Rule::DailyRest);
+        assert_eq!(violations[2].excess_hours, 3.0);
+    }
+
+    #[test]
+    fn test_crew_assigns_rested_drivers() {
+        let (network, depot, port) = long_haul();
+        let there = network.route(depot, port).unwrap();
+        let mut crew = Crew::new();
+        let tired = crew.add(Driver::new("Tired"));
+        let fresh = crew.add(Driver::new("Fresh"));
+
+        // Уставший водитель уже отъездил почти всю смену
+        crew.drivers[tired].record(Duty::new(Activity::Driving, 0.0, 4.0));
+        crew.drivers[tired].record(Duty::new(Activity::Break, 4.0, 5.0));
+        crew.drivers[tired].record(Duty::new(Activity::Driving, 5.0, 8.0));
+
+        assert_eq!(crew.assign_first_free(0, &there, 8.0), Ok(fresh));
+        assert_eq!(crew.truck_driver(0, 10.0), Some(fresh));
+        assert_eq!(
+            crew.assign(fresh, 1, &there, 12.0).err(),
+            Some("Driver is already assigned")
+        );
+        assert_eq!(crew.assign_first_free(1, &there, 8.0), Ok(tired));
+        assert_eq!(
+            crew.assign_first_free(2, &there, 8.0),
+            Err("No driver available")
+        );
+        assert!(crew.violations().is_empty());
+
+        let tired_plan = &crew.assignments(tired).next().unwrap().schedule;
+        // Ему хватает времени только до перерыва и получаса после него
+        assert_eq!(tired_plan.duties[1].activity, Activity::Break);
+        assert_eq!(tired_plan.duties[2].hours(), 0.5);
+        assert_eq!(tired_plan.duties[3].activity, Activity::DailyRest);
+        assert_eq!(
+            crew.driver(fresh).unwrap().available_from(),
+            8.0 + 12.0 + 0.75 + 11.0
+        );
+
+        // После ночёвки в порту обратный рейс начинается с чистой смены
+        let back = network.route(port, depot).unwrap();
+        let returning = crew.assign(fresh, 0, &back, 50.0).unwrap();
+        assert_eq!(returning.schedule.duties[0].hours(), 4.5);
+        assert!(crew.violations().is_empty());
+    }
+}
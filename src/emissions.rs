@@ -0,0 +1,377 @@
+use std::collections::BTreeMap;
+
+use crate::energy::{Consumption, Powertrain};
+use crate::fleet::VehicleType;
+use crate::models::{Product, Truck};
+use crate::network::NodeId;
+use crate::orders::OrderBook;
+use crate::routing::Route;
+
+// Коэффициенты well-to-wheel в духе GLEC
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EmissionFactors {
+    pub diesel_kg_per_l: f64,
+    pub electricity_kg_per_kwh: f64,
+}
+
+impl Default for EmissionFactors {
+    fn default() -> Self {
+        Self {
+            diesel_kg_per_l: 3.24,
+            electricity_kg_per_kwh: 0.35,
+        }
+    }
+}
+
+impl EmissionFactors {
+    pub fn per_unit(&self, powertrain: Powertrain) -> f64 {
+        match powertrain {
+            Powertrain::Diesel => self.diesel_kg_per_l,
+            Powertrain::Electric => self.electricity_kg_per_kwh,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Production,
+    Transport,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Allocation {
+    pub source: Source,
+    pub sku: String,
+    pub order: Option<u64>,
+    pub lane: Option<(NodeId, NodeId)>,
+    pub co2e_kg: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TripEmissions {
+    pub truck: String,
+    pub lane: (NodeId, NodeId),
+    pub distance_km: f64,
+    pub powertrain: Powertrain,
+    pub energy_used: f64,
+    pub co2e_kg: f64,
+    pub payload_kg: f64,
+    pub load_factor: f64,
+}
+
+impl TripEmissions {
+    pub fn tonne_km(&self) -> f64 {
+        self.payload_kg / 1000.0 * self.distance_km
+    }
+
+    pub fn intensity_g_per_tkm(&self) -> Option<f64> {
+        let tonne_km = self.tonne_km();
+        if tonne_km <= 0.0 {
+            return None;
+        }
+        Some(self.co2e_kg * 1000.0 / tonne_km)
+    }
+}
+
+fn totals<K: Ord>(items: impl Iterator<Item = (K, f64)>) -> Vec<(K, f64)> {
+    let mut grouped: BTreeMap<K, f64> = BTreeMap::new();
+    for (key, kg) in items {
+        *grouped.entry(key).or_default() += kg;
+    }
+    let mut totals: Vec<(K, f64)> = grouped.into_iter().collect();
+    totals.sort_by(|a, b| b.1.total_cmp(&a.1));
+    totals
+}
+
+pub struct Emissions {
+    factors: EmissionFactors,
+    fallback: Consumption,
+    trips: Vec<TripEmissions>,
+    allocations: Vec<Allocation>,
+}
+
+impl Emissions {
+    pub fn new() -> Self {
+        Self {
+            factors: EmissionFactors::default(),
+            fallback: VehicleType::Rigid.fuel_consumption(),
+            trips: Vec::new(),
+            allocations: Vec::new(),
+        }
+    }
+
+    pub fn with_factors(mut self, factors: EmissionFactors) -> Self {
+        self.factors = factors;
+        self
+    }
+
+    pub fn with_fallback(mut self, kind: VehicleType) -> Self {
+        self.fallback = kind.fuel_consumption();
+        self
+    }
+
+    pub fn factors(&self) -> &EmissionFactors {
+        &self.factors
+    }
+
+    pub fn trips(&self) -> &[TripEmissions] {
+        &self.trips
+    }
+
+    pub fn allocations(&self) -> &[Allocation] {
+        &self.allocations
+    }
+
+    // Все выбросы, включая пустые пробеги, которые не на кого распределить
+    pub fn total_kg(&self) -> f64 {
+        let production: f64 = self
+            .allocations
+            .iter()
+            .filter(|allocation| allocation.source == Source::Production)
+            .map(|allocation| allocation.co2e_kg)
+            .sum();
+        production + self.trips.iter().map(|trip| trip.co2e_kg).sum::<f64>()
+    }
+
+    pub fn record_production(&mut self, sku: &str, units: usize, kg_per_unit: f64) {
+        if units == 0 || kg_per_unit <= 0.0 {
+            return;
+        }
+        self.allocations.push(Allocation {
+            source: Source::Production,
+            sku: sku.to_string(),
+            order: None,
+            lane: None,
+            co2e_kg: units as f64 * kg_per_unit,
+        });
+    }
+
+    pub fn record_product(&mut self, product: &Product) {
+        if product.co2e_kg <= 0.0 {
+            return;
+        }
+        self.allocations.push(Allocation {
+            source: Source::Production,
+            sku: product.sku.clone(),
+            order: product.order,
+            lane: None,
+            co2e_kg: product.co2e_kg,
+        });
+    }
+
+    // Выбросы рейса делятся между товарами на борту пропорционально массе,
+    // то есть по тонно-километрам
+    pub fn record_trip(&mut self, truck: &mut Truck, route: &Route) -> TripEmissions {
+        let distance_km = route.distance_km;
+        let payload_kg = truck.payload_kg();
+        let (powertrain, energy_used) = match truck.energy() {
+            Some(energy) => (energy.powertrain(), energy.needed(distance_km, payload_kg)),
+            // Без своей модели топлива — расход по типу машины, если он известен
+            None => {
+                let consumption = truck
+                    .kind()
+                    .map_or(self.fallback, |kind| kind.fuel_consumption());
+                (
+                    Powertrain::Diesel,
+                    distance_km * consumption.per_km_at(payload_kg),
+                )
+            }
+        };
+        let co2e_kg = energy_used * self.factors.per_unit(powertrain);
+        let load_factor = if truck.max_payload_kg().is_finite() && truck.max_payload_kg() > 0.0 {
+            payload_kg / truck.max_payload_kg()
+        } else if truck.capacity() > 0 {
+            truck.loaded() as f64 / truck.capacity() as f64
+        } else {
+            0.0
+        };
+        let lane = (route.origin(), route.destination());
+
+        if payload_kg > 0.0 {
+            for product in truck.cargo_mut() {
+                let share = co2e_kg * product.weight_kg / payload_kg;
+                product.co2e_kg += share;
+                self.allocations.push(Allocation {
+                    source: Source::Transport,
+                    sku: product.sku.clone(),
+                    order: product.order,
+                    lane: Some(lane),
+                    co2e_kg: share,
+                });
+            }
+        }
+
+        let trip = TripEmissions {
+            truck: truck.name().to_string(),
+            lane,
+            distance_km,
+            powertrain,
+            energy_used,
+            co2e_kg,
+            payload_kg,
+            load_factor,
+        };
+        self.trips.push(trip.clone());
+        trip
+    }
+
+    pub fn by_lane(&self) -> Vec<((NodeId, NodeId), f64)> {
+        totals(self.trips.iter().map(|trip| (trip.lane, trip.co2e_kg)))
+    }
+
+    pub fn by_sku(&self) -> Vec<(String, f64)> {
+        totals(
+            self.allocations
+                .iter()
+                .map(|allocation| (allocation.sku.clone(), allocation.co2e_kg)),
+        )
+    }
+
+    pub fn by_order(&self) -> Vec<(u64, f64)> {
+        totals(
+            self.allocations
+                .iter()
+                .filter_map(|allocation| allocation.order.map(|id| (id, allocation.co2e_kg))),
+        )
+    }
+
+    pub fn by_customer(&self, orders: &OrderBook) -> Vec<(String, f64)> {
+        totals(self.allocations.iter().filter_map(|allocation| {
+            let order = orders.order(allocation.order?)?;
+            Some((order.customer.clone(), allocation.co2e_kg))
+        }))
+    }
+
+    pub fn order_footprint(&self, id: u64) -> f64 {
+        self.allocations
+            .iter()
+            .filter(|allocation| allocation.order == Some(id))
+            .map(|allocation| allocation.co2e_kg)
+            .sum()
+    }
+}
+
+impl Default for Emissions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::energy::{ChargingCurve, Energy};
+    use crate::models::Factory;
+    use crate::network::{Location, RoadNetwork, SiteKind};
+    use crate::orders::OrderLine;
+
+    fn lane() -> (RoadNetwork, NodeId, NodeId) {
+        let mut network = RoadNetwork::new();
+        let dc = network.add_location(Location::new("dc", SiteKind::Warehouse, 0.0, 0.0));
+        let city = network.add_location(Location::new("city", SiteKind::Market, 100.0, 0.0));
+        network.add_two_way_road(dc, city, 100.0, 1.25).unwrap();
+        (network, dc, city)
+    }
+
+    fn factory() -> Factory {
+        let mut factory = Factory::new("GreenFactory".to_string()).with_energy_use(2.0, 0.5);
+        factory.set_weight("steel", 3000.0);
+        factory.set_weight("foam", 1000.0);
+        factory
+    }
+
+    #[test]
+    fn test_trip_emissions_are_allocated_by_weight() {
+        let (network, dc, city) = lane();
+        let mut factory = factory();
+        let mut truck = Truck::new("Rigid".to_string(), 4)
+            .with_location(dc)
+            .with_max_payload(8000.0);
+        let mut steel = factory.create_sku("steel").unwrap();
+        steel.order = Some(1);
+        let mut foam = factory.create_sku("foam").unwrap();
+        foam.order = Some(2);
+        assert_eq!(steel.co2e_kg, 1.0);
+        truck.load(steel).unwrap();
+        truck.load(foam).unwrap();
+
+        let mut emissions = Emissions::new();
+        let trip = emissions.record_trip(&mut truck, &network.route(dc, city).unwrap());
+
+        // 100 км по 0.22 + 0.02 * 4 т = 30 л дизеля
+        assert!((trip.energy_used - 30.0).abs() < 1e-9);
+        assert!((trip.co2e_kg - 97.2).abs() < 1e-9);
+        assert_eq!(trip.load_factor, 0.5);
+        assert_eq!(trip.tonne_km(), 400.0);
+        assert!((trip.intensity_g_per_tkm().unwrap() - 243.0).abs() < 1e-9);
+
+        // Сталь весит втрое больше пенопласта и несёт три четверти выбросов
+        let shares: Vec<f64> = truck.cargo().map(|p| p.co2e_kg).collect();
+        assert!((shares[0] - (1.0 + 72.9)).abs() < 1e-9);
+        assert!((shares[1] - (1.0 + 24.3)).abs() < 1e-9);
+        assert!((emissions.order_footprint(1) - 72.9).abs() < 1e-9);
+        assert_eq!(emissions.by_lane(), vec![((dc, city), trip.co2e_kg)]);
+    }
+
+    #[test]
+    fn test_diesel_consumption_follows_vehicle_type() {
+        let (network, dc, city) = lane();
+        let route = network.route(dc, city).unwrap();
+        let mut factory = factory();
+        let mut emissions = Emissions::new();
+        let mut trip = |kind: VehicleType| {
+            let mut truck = kind.truck("Typed").with_location(dc);
+            truck.load(factory.create_sku("foam").unwrap()).unwrap();
+            emissions.record_trip(&mut truck, &route).energy_used
+        };
+
+        // 100 км с тонной груза: фургон 0.09 + 0.04, седельный тягач 0.26 + 0.012 л/км
+        assert!((trip(VehicleType::Van) - 13.0).abs() < 1e-9);
+        assert!((trip(VehicleType::Articulated) - 27.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_reports_by_sku_and_customer() {
+        let (network, dc, city) = lane();
+        let mut factory = factory();
+        let mut book = OrderBook::new();
+        let acme = book.place("Acme", vec![OrderLine::new("steel", 1)], 10.0, 1, city);
+        let mut emissions = Emissions::new();
+        let mut truck = Truck::new("Van".to_string(), 2).with_location(dc);
+        let mut steel = factory.create_sku("steel").unwrap();
+        steel.order = Some(acme);
+        emissions.record_product(&steel);
+        truck.load(steel).unwrap();
+        emissions.record_production("foam", 3, 1.0);
+
+        emissions.record_trip(&mut truck, &network.route(dc, city).unwrap());
+        // Обратный порожний рейс попадает в отчёт по плечам, но не в товары
+        let mut empty = Truck::new("Van".to_string(), 2).with_location(city);
+        let back = emissions.record_trip(&mut empty, &network.route(city, dc).unwrap());
+        assert_eq!(back.load_factor, 0.0);
+
+        let by_sku = emissions.by_sku();
+        assert_eq!(by_sku[0].0, "steel");
+        assert_eq!(by_sku[1], ("foam".to_string(), 3.0));
+        assert_eq!(emissions.by_customer(&book)[0].0, "Acme");
+        assert_eq!(emissions.by_lane()[0].0, (dc, city));
+        let allocated: f64 = emissions.allocations().iter().map(|a| a.co2e_kg).sum();
+        assert!((emissions.total_kg() - allocated - back.co2e_kg).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_electric_truck_uses_grid_factor() {
+        let (network, dc, city) = lane();
+        let mut truck = Truck::new("EV".to_string(), 1)
+            .with_location(dc)
+            .with_energy(Energy::electric(
+                300.0,
+                Consumption::new(1.0, 0.0),
+                ChargingCurve::flat(150.0),
+            ));
+
+        let trip = Emissions::new().record_trip(&mut truck, &network.route(dc, city).unwrap());
+        assert_eq!(trip.powertrain, Powertrain::Electric);
+        assert!((trip.co2e_kg - 35.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,538 @@
+use crate::models::Truck;
+use crate::network::{NodeId, RoadNetwork, SiteKind};
+use crate::routing::{Metric, Route};
+
+// Производительность колонки, л/ч
+const DIESEL_PUMP_L_PER_HOUR: f64 = 2400.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Powertrain {
+    Diesel,
+    Electric,
+}
+
+impl Powertrain {
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Powertrain::Diesel => "l",
+            Powertrain::Electric => "kWh",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChargingCurve {
+    // Мощность действует от своего уровня заряда до следующей точки
+    points: Vec<(f64, f64)>,
+}
+
+impl ChargingCurve {
+    pub fn new(mut points: Vec<(f64, f64)>) -> Self {
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        if points.first().is_none_or(|(soc, _)| *soc > 0.0) {
+            let power = points.first().map_or(0.0, |(_, power)| *power);
+            points.insert(0, (0.0, power));
+        }
+        Self { points }
+    }
+
+    pub fn flat(power: f64) -> Self {
+        Self::new(vec![(0.0, power)])
+    }
+
+    // Быстрая зарядка до 80%, дальше мощность падает
+    pub fn fast(peak_kw: f64) -> Self {
+        Self::new(vec![
+            (0.0, peak_kw),
+            (0.8, peak_kw * 0.4),
+            (0.9, peak_kw * 0.15),
+        ])
+    }
+
+    pub fn power_at(&self, soc: f64) -> f64 {
+        self.points
+            .iter()
+            .rev()
+            .find(|(from, _)| *from <= soc)
+            .map_or(0.0, |(_, power)| *power)
+    }
+
+    fn segments(&self) -> impl Iterator<Item = (f64, f64, f64)> + '_ {
+        self.points.iter().enumerate().map(|(i, (from, power))| {
+            let to = self.points.get(i + 1).map_or(1.0, |(next, _)| *next);
+            (*from, to, *power)
+        })
+    }
+
+    pub fn hours(&self, from_soc: f64, to_soc: f64, capacity: f64) -> f64 {
+        let mut hours = 0.0;
+        for (start, end, power) in self.segments() {
+            let span = end.min(to_soc) - start.max(from_soc);
+            if span <= 0.0 {
+                continue;
+            }
+            if power <= 0.0 {
+                return f64::INFINITY;
+            }
+            hours += span * capacity / power;
+        }
+        hours
+    }
+
+    // Уровень заряда после зарядки в течение hours
+    pub fn charge(&self, soc: f64, hours: f64, capacity: f64) -> f64 {
+        let mut soc = soc;
+        let mut left = hours;
+        for (start, end, power) in self.segments() {
+            if end <= soc || power <= 0.0 {
+                continue;
+            }
+            let from = soc.max(start);
+            let needed = (end - from) * capacity / power;
+            if left < needed {
+                return from + left * power / capacity;
+            }
+            left -= needed;
+            soc = end;
+        }
+        soc.min(1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Consumption {
+    pub per_km: f64,
+    pub per_tonne_km: f64,
+}
+
+impl Consumption {
+    pub fn new(per_km: f64, per_tonne_km: f64) -> Self {
+        Self {
+            per_km,
+            per_tonne_km,
+        }
+    }
+
+    pub fn per_km_at(&self, payload_kg: f64) -> f64 {
+        self.per_km + self.per_tonne_km * payload_kg / 1000.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Energy {
+    powertrain: Powertrain,
+    capacity: f64,
+    level: f64,
+    consumption: Consumption,
+    curve: ChargingCurve,
+    price: f64,
+}
+
+impl Energy {
+    pub fn diesel(tank_l: f64, consumption: Consumption) -> Self {
+        Self {
+            powertrain: Powertrain::Diesel,
+            capacity: tank_l,
+            level: tank_l,
+            consumption,
+            curve: ChargingCurve::flat(DIESEL_PUMP_L_PER_HOUR),
+            price: 1.6,
+        }
+    }
+
+    pub fn electric(battery_kwh: f64, consumption: Consumption, curve: ChargingCurve) -> Self {
+        Self {
+            powertrain: Powertrain::Electric,
+            capacity: battery_kwh,
+            level: battery_kwh,
+            consumption,
+            curve,
+            price: 0.3,
+        }
+    }
+
+    pub fn with_level(mut self, level: f64) -> Self {
+        self.level = level.clamp(0.0, self.capacity);
+        self
+    }
+
+    pub fn with_price(mut self, per_unit: f64) -> Self {
+        self.price = per_unit;
+        self
+    }
+
+    pub fn powertrain(&self) -> Powertrain {
+        self.powertrain
+    }
+
+    pub fn capacity(&self) -> f64 {
+        self.capacity
+    }
+
+    pub fn level(&self) -> f64 {
+        self.level
+    }
+
+    pub fn soc(&self) -> f64 {
+        if self.capacity <= 0.0 {
+            return 0.0;
+        }
+        self.level / self.capacity
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.level <= 0.0
+    }
+
+    pub fn consumption(&self) -> &Consumption {
+        &self.consumption
+    }
+
+    pub fn curve(&self) -> &ChargingCurve {
+        &self.curve
+    }
+
+    pub fn needed(&self, km: f64, payload_kg: f64) -> f64 {
+        km * self.consumption.per_km_at(payload_kg)
+    }
+
+    pub fn range_km(&self, payload_kg: f64) -> f64 {
+        self.range_from(self.level, payload_kg)
+    }
+
+    fn range_from(&self, level: f64, payload_kg: f64) -> f64 {
+        let per_km = self.consumption.per_km_at(payload_kg);
+        if per_km <= 0.0 {
+            return f64::INFINITY;
+        }
+        level / per_km
+    }
+
+    pub fn cost(&self, km: f64, payload_kg: f64) -> f64 {
+        self.needed(km, payload_kg) * self.price
+    }
+
+    pub fn consume(&mut self, km: f64, payload_kg: f64) -> Result<(), &'static str> {
+        let needed = self.needed(km, payload_kg);
+        if needed > self.level + 1e-9 {
+            self.level = 0.0;
+            return Err("Ran out of energy");
+        }
+        self.level = (self.level - needed).max(0.0);
+        Ok(())
+    }
+
+    pub fn refill_hours(&self, to_soc: f64) -> f64 {
+        self.curve.hours(self.soc(), to_soc, self.capacity)
+    }
+
+    fn refill_hours_from(&self, level: f64) -> f64 {
+        if self.capacity <= 0.0 {
+            return 0.0;
+        }
+        self.curve.hours(level / self.capacity, 1.0, self.capacity)
+    }
+
+    pub fn refill(&mut self, hours: f64) {
+        self.level = self.curve.charge(self.soc(), hours, self.capacity) * self.capacity;
+    }
+
+    pub fn top_up(&mut self, amount: f64) {
+        self.level = (self.level + amount).min(self.capacity);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnergyStop {
+    pub node: NodeId,
+    pub arrival_level: f64,
+    pub hours: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnergyPlan {
+    // Участки между заправками, каждый отправляется отдельным рейсом
+    pub segments: Vec<Route>,
+    pub stops: Vec<EnergyStop>,
+    pub arrival_level: f64,
+    pub cost: f64,
+}
+
+impl EnergyPlan {
+    pub fn distance_km(&self) -> f64 {
+        self.segments.iter().map(|route| route.distance_km).sum()
+    }
+
+    pub fn stop_hours(&self) -> f64 {
+        self.stops.iter().map(|stop| stop.hours).sum()
+    }
+
+    pub fn hours(&self) -> f64 {
+        self.segments
+            .iter()
+            .map(|route| route.eta_hours)
+            .sum::<f64>()
+            + self.stop_hours()
+    }
+}
+
+#[derive(Clone)]
+struct Label {
+    hours: f64,
+    level: f64,
+    via: Option<(usize, Route)>,
+}
+
+impl RoadNetwork {
+    // Маршрут с остановками на заправках: Дейкстра по опорным точкам,
+    // на каждой станции бак заполняется полностью
+    pub fn energy_plan(&self, truck: &Truck, to: NodeId) -> Result<EnergyPlan, &'static str> {
+        let from = truck.location().ok_or("Truck has no location")?;
+        let profile = Some(truck.profile());
+        let direct = self
+            .route_with(from, to, Metric::Time, profile)
+            .ok_or("No route")?;
+        let Some(energy) = truck.energy() else {
+            return Ok(EnergyPlan {
+                segments: vec![direct],
+                stops: Vec::new(),
+                arrival_level: 0.0,
+                cost: 0.0,
+            });
+        };
+        let payload = truck.payload_kg();
+
+        let mut points = vec![from];
+        points.extend(
+            self.nodes()
+                .filter(|(node, location)| {
+                    location.kind == SiteKind::Station && *node != from && *node != to
+                })
+                .map(|(node, _)| node),
+        );
+        points.push(to);
+        let target = points.len() - 1;
+
+        let mut best: Vec<Option<Label>> = vec![None; points.len()];
+        let mut done = vec![false; points.len()];
+        best[0] = Some(Label {
+            hours: 0.0,
+            level: energy.level(),
+            via: None,
+        });
+        while let Some((current, label)) = best
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !done[*i])
+            .filter_map(|(i, label)| label.clone().map(|label| (i, label)))
+            .min_by(|a, b| a.1.hours.total_cmp(&b.1.hours))
+        {
+            done[current] = true;
+            if current == target {
+                break;
+            }
+            let (start_level, wait) = if current == 0 {
+                (label.level, 0.0)
+            } else {
+                (energy.capacity(), energy.refill_hours_from(label.level))
+            };
+            let range = energy.range_from(start_level, payload);
+            for next in 1..points.len() {
+                if done[next] {
+                    continue;
+                }
+                let Some(route) =
+                    self.route_with(points[current], points[next], Metric::Time, profile)
+                else {
+                    continue;
+                };
+                if route.distance_km > range + 1e-9 {
+                    continue;
+                }
+                let hours = label.hours + wait + route.eta_hours;
+                if best[next].as_ref().is_none_or(|known| hours < known.hours) {
+                    best[next] = Some(Label {
+                        hours,
+                        level: start_level - energy.needed(route.distance_km, payload),
+                        via: Some((current, route)),
+                    });
+                }
+            }
+        }
+
+        let arrival_level = best[target]
+            .as_ref()
+            .ok_or("No station within range")?
+            .level;
+        let mut segments = Vec::new();
+        let mut stops = Vec::new();
+        let mut node = target;
+        while let Some((previous, route)) = best[node].as_ref().and_then(|label| label.via.clone())
+        {
+            segments.push(route);
+            if previous != 0 {
+                let level = best[previous].as_ref().map_or(0.0, |label| label.level);
+                stops.push(EnergyStop {
+                    node: points[previous],
+                    arrival_level: level,
+                    hours: energy.refill_hours_from(level),
+                });
+            }
+            node = previous;
+        }
+        segments.reverse();
+        stops.reverse();
+
+        let distance: f64 = segments.iter().map(|route| route.distance_km).sum();
+        Ok(EnergyPlan {
+            segments,
+            stops,
+            arrival_level,
+            cost: energy.cost(distance, payload),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Factory;
+    use crate::network::Location;
+    use crate::trip::TruckState;
+
+    fn diesel() -> Energy {
+        Energy::diesel(300.0, Consumption::new(0.3, 0.02))
+    }
+
+    fn electric() -> Energy {
+        Energy::electric(
+            400.0,
+            Consumption::new(1.2, 0.08),
+            ChargingCurve::fast(350.0),
+        )
+    }
+
+    // depot — 200 км — station — 200 км — city
+    fn corridor() -> (RoadNetwork, NodeId, NodeId, NodeId) {
+        let mut network = RoadNetwork::new();
+        let depot = network.add_location(Location::new("depot", SiteKind::Warehouse, 0.0, 0.0));
+        let station = network.add_location(Location::new("station", SiteKind::Station, 200.0, 0.0));
+        let city = network.add_location(Location::new("city", SiteKind::Market, 400.0, 0.0));
+        network
+            .add_two_way_road(depot, station, 200.0, 2.5)
+            .unwrap();
+        network.add_two_way_road(station, city, 200.0, 2.5).unwrap();
+        (network, depot, station, city)
+    }
+
+    #[test]
+    fn test_charging_curve_tapers_near_full() {
+        let curve = ChargingCurve::fast(100.0);
+        assert_eq!(curve.power_at(0.5), 100.0);
+        assert_eq!(curve.power_at(0.85), 40.0);
+
+        // 0→80% на полной мощности быстрее, чем 80→100%
+        let bulk = curve.hours(0.0, 0.8, 100.0);
+        let tail = curve.hours(0.8, 1.0, 100.0);
+        assert!((bulk - 0.8).abs() < 1e-9);
+        assert!(tail > bulk);
+
+        let soc = curve.charge(0.0, bulk + 0.1, 100.0);
+        assert!((soc - 0.84).abs() < 1e-9);
+        assert_eq!(curve.charge(0.0, 100.0, 100.0), 1.0);
+    }
+
+    #[test]
+    fn test_consumption_grows_with_payload() {
+        let mut energy = diesel();
+        assert_eq!(energy.range_km(0.0), 1000.0);
+        assert!(energy.range_km(10_000.0) < 1000.0);
+
+        energy.consume(100.0, 5000.0).unwrap();
+        assert!((energy.level() - 260.0).abs() < 1e-9);
+        assert_eq!(energy.consume(10_000.0, 0.0), Err("Ran out of energy"));
+        assert!(energy.is_empty());
+    }
+
+    #[test]
+    fn test_plan_inserts_station_stop_when_range_is_short() {
+        let (network, depot, station, city) = corridor();
+        let truck = Truck::new("Diesel".to_string(), 1)
+            .with_location(depot)
+            .with_energy(diesel().with_level(90.0));
+
+        let plan = network.energy_plan(&truck, city).unwrap();
+        assert_eq!(plan.stops.len(), 1);
+        assert_eq!(plan.stops[0].node, station);
+        assert!((plan.stops[0].arrival_level - 30.0).abs() < 1e-9);
+        assert_eq!(plan.segments.len(), 2);
+        assert_eq!(plan.distance_km(), 400.0);
+        assert!((plan.arrival_level - 240.0).abs() < 1e-9);
+
+        let stranded = Truck::new("Stranded".to_string(), 1)
+            .with_location(depot)
+            .with_energy(diesel().with_level(30.0));
+        assert_eq!(
+            network.energy_plan(&stranded, city).err(),
+            Some("No station within range")
+        );
+    }
+
+    #[test]
+    fn test_truck_runs_dry_and_is_rescued() {
+        let (network, depot, station, city) = corridor();
+        let mut truck = Truck::new("Dry".to_string(), 1)
+            .with_location(depot)
+            .with_energy(diesel().with_level(30.0));
+
+        truck.dispatch(network.route(depot, city).unwrap()).unwrap();
+        truck.advance(2.0);
+        assert_eq!(truck.state(), TruckState::Stranded);
+        assert!((truck.odometer_km() - 100.0).abs() < 1e-9);
+        assert_eq!(truck.location(), Some(depot));
+        assert_eq!(truck.begin_refuelling(1.0), Err("Truck has run dry"));
+
+        // Подвезли топливо на дорогу — машина продолжает рейс
+        truck.top_up(100.0);
+        assert_eq!(truck.state(), TruckState::EnRoute);
+        truck.advance(10.0);
+        assert_eq!(truck.location(), Some(city));
+        assert_eq!(truck.state(), TruckState::Idle);
+        assert!((truck.energy().unwrap().level() - 10.0).abs() < 1e-9);
+
+        assert_eq!(truck.refuel(&network), Err("Truck is not at a station"));
+        let mut back = Truck::new("Back".to_string(), 1)
+            .with_location(station)
+            .with_energy(diesel().with_level(0.0));
+        back.refuel(&network).unwrap();
+        assert_eq!(back.state(), TruckState::Refuelling);
+        back.advance(1.0);
+        assert_eq!(back.state(), TruckState::Idle);
+        assert_eq!(back.energy().unwrap().level(), 300.0);
+    }
+
+    #[test]
+    fn test_electric_fleet_against_diesel() {
+        let (network, depot, _, city) = corridor();
+        let mut factory = Factory::new("EnergyFactory".to_string());
+        factory.set_weight("pallet", 2000.0);
+        let mut build = |energy: Energy| {
+            let mut truck = Truck::new("Truck".to_string(), 5)
+                .with_location(depot)
+                .with_energy(energy);
+            for _ in 0..5 {
+                truck.load(factory.create_sku("pallet").unwrap()).unwrap();
+            }
+            truck
+        };
+        let diesel = network.energy_plan(&build(diesel()), city).unwrap();
+        let electric = network.energy_plan(&build(electric()), city).unwrap();
+
+        // Дизель доезжает без остановок, электричке нужна зарядка,
+        // зато рейс обходится дешевле
+        assert!(diesel.stops.is_empty());
+        assert_eq!(electric.stops.len(), 1);
+        assert!(electric.hours() > diesel.hours());
+        assert!(electric.cost < diesel.cost);
+    }
+}
@@ -0,0 +1,542 @@
+use crate::energy::Consumption;
+use crate::models::Truck;
+use crate::network::{NodeId, RoadNetwork};
+use crate::reliability::Condition;
+use crate::routing::{Route, VehicleProfile};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum VehicleType {
+    Van,
+    Rigid,
+    Articulated,
+}
+
+impl VehicleType {
+    pub fn capacity(&self) -> usize {
+        match self {
+            VehicleType::Van => 3,
+            VehicleType::Rigid => 12,
+            VehicleType::Articulated => 33,
+        }
+    }
+
+    pub fn profile(&self) -> VehicleProfile {
+        match self {
+            VehicleType::Van => VehicleProfile {
+                height_m: 2.7,
+                gross_weight_t: 3.5,
+                hazmat: false,
+            },
+            VehicleType::Rigid => VehicleProfile::default(),
+            VehicleType::Articulated => VehicleProfile {
+                height_m: 4.0,
+                gross_weight_t: 40.0,
+                hazmat: false,
+            },
+        }
+    }
+
+    pub fn default_cost(&self) -> OperatingCost {
+        match self {
+            VehicleType::Van => OperatingCost::new(0.35, 25.0, 40.0),
+            VehicleType::Rigid => OperatingCost::new(0.8, 30.0, 90.0),
+            VehicleType::Articulated => OperatingCost::new(1.2, 35.0, 150.0),
+        }
+    }
+
+    // Расход дизеля по умолчанию, если у машины нет своей модели топлива
+    pub fn fuel_consumption(&self) -> Consumption {
+        match self {
+            VehicleType::Van => Consumption::new(0.09, 0.04),
+            VehicleType::Rigid => Consumption::new(0.22, 0.02),
+            VehicleType::Articulated => Consumption::new(0.26, 0.012),
+        }
+    }
+
+    pub fn truck(&self, name: &str) -> Truck {
+        Truck::new(name.to_string(), self.capacity())
+            .with_kind(*self)
+            .with_profile(self.profile())
+            .with_cost(self.default_cost())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OperatingCost {
+    pub per_km: f64,
+    pub per_hour: f64,
+    pub per_day: f64,
+}
+
+impl OperatingCost {
+    pub fn new(per_km: f64, per_hour: f64, per_day: f64) -> Self {
+        Self {
+            per_km,
+            per_hour,
+            per_day,
+        }
+    }
+
+    pub fn trip(&self, distance_km: f64, hours: f64) -> f64 {
+        distance_km * self.per_km + hours * self.per_hour
+    }
+}
+
+// Рабочая смена внутри суток и периоды, когда машина недоступна
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calendar {
+    shift: Option<(f64, f64)>,
+    blocked: Vec<(f64, f64)>,
+}
+
+impl Calendar {
+    pub fn always() -> Self {
+        Self {
+            shift: None,
+            blocked: Vec::new(),
+        }
+    }
+
+    pub fn with_shift(mut self, from_hour: f64, to_hour: f64) -> Self {
+        self.shift = Some((from_hour, to_hour));
+        self
+    }
+
+    pub fn block(mut self, start: f64, end: f64) -> Self {
+        self.blocked.push((start, end));
+        self
+    }
+
+    pub fn is_available(&self, start: f64, end: f64) -> bool {
+        if self
+            .blocked
+            .iter()
+            .any(|&(from, to)| start < to && from < end)
+        {
+            return false;
+        }
+        match self.shift {
+            None => true,
+            Some((from, to)) => {
+                // Ночная смена (22 → 6) переходит через полночь
+                let length = if to > from {
+                    to - from
+                } else {
+                    to + 24.0 - from
+                };
+                let day = (start / 24.0).floor() * 24.0;
+                let begin = if start < day + from {
+                    day - 24.0 + from
+                } else {
+                    day + from
+                };
+                start >= begin && end <= begin + length
+            }
+        }
+    }
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Self::always()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub vehicle: usize,
+    pub start: f64,
+    pub end: f64,
+    pub route: Route,
+}
+
+pub struct FleetVehicle {
+    truck: Truck,
+    depot: NodeId,
+    calendar: Calendar,
+}
+
+impl FleetVehicle {
+    pub fn new(truck: Truck, kind: VehicleType, depot: NodeId) -> Self {
+        let truck = if truck.location().is_none() {
+            truck.with_location(depot)
+        } else {
+            truck
+        };
+        Self {
+            truck: truck.with_kind(kind),
+            depot,
+            calendar: Calendar::always(),
+        }
+    }
+
+    pub fn with_cost(mut self, cost: OperatingCost) -> Self {
+        self.truck = self.truck.with_cost(cost);
+        self
+    }
+
+    pub fn with_calendar(mut self, calendar: Calendar) -> Self {
+        self.calendar = calendar;
+        self
+    }
+
+    pub fn truck(&self) -> &Truck {
+        &self.truck
+    }
+
+    pub fn kind(&self) -> VehicleType {
+        self.truck.kind().expect("fleet trucks have a type")
+    }
+
+    pub fn depot(&self) -> NodeId {
+        self.depot
+    }
+
+    pub fn cost(&self) -> &OperatingCost {
+        self.truck.cost()
+    }
+
+    pub fn calendar(&self) -> &Calendar {
+        &self.calendar
+    }
+
+    pub fn location(&self) -> NodeId {
+        self.truck.location().unwrap_or(self.depot)
+    }
+}
+
+pub struct Fleet {
+    vehicles: Vec<FleetVehicle>,
+    assignments: Vec<Assignment>,
+}
+
+impl Fleet {
+    pub fn new() -> Self {
+        Self {
+            vehicles: Vec::new(),
+            assignments: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, vehicle: FleetVehicle) -> usize {
+        self.vehicles.push(vehicle);
+        self.vehicles.len() - 1
+    }
+
+    pub fn add_new(&mut self, name: &str, kind: VehicleType, depot: NodeId) -> usize {
+        self.add(FleetVehicle::new(kind.truck(name), kind, depot))
+    }
+
+    pub fn vehicle(&self, id: usize) -> Option<&FleetVehicle> {
+        self.vehicles.get(id)
+    }
+
+    pub fn truck_mut(&mut self, id: usize) -> Option<&mut Truck> {
+        self.vehicles.get_mut(id).map(|vehicle| &mut vehicle.truck)
+    }
+
+    pub fn len(&self) -> usize {
+        self.vehicles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vehicles.is_empty()
+    }
+
+    pub fn of_kind(&self, kind: VehicleType) -> impl Iterator<Item = usize> + '_ {
+        self.vehicles
+            .iter()
+            .enumerate()
+            .filter(move |(_, vehicle)| vehicle.kind() == kind)
+            .map(|(id, _)| id)
+    }
+
+    pub fn assignments(&self, id: usize) -> impl Iterator<Item = &Assignment> {
+        self.assignments.iter().filter(move |a| a.vehicle == id)
+    }
+
+    pub fn is_free(&self, id: usize, start: f64, end: f64) -> bool {
+        let Some(vehicle) = self.vehicles.get(id) else {
+            return false;
+        };
+        vehicle.truck.reliability().condition() == Condition::Operational
+            && vehicle.calendar.is_available(start, end)
+            && !self.assignments(id).any(|a| start < a.end && a.start < end)
+    }
+
+    // Где машина окажется к этому времени: конец последнего рейса или стоянка
+    pub fn position_at(&self, id: usize, time: f64) -> Option<NodeId> {
+        let vehicle = self.vehicles.get(id)?;
+        Some(
+            self.assignments(id)
+                .filter(|a| a.end <= time)
+                .max_by(|a, b| a.end.total_cmp(&b.end))
+                .map_or(vehicle.location(), |a| a.route.destination()),
+        )
+    }
+
+    // Свободные машины нужной вместимости, от ближней к дальней
+    pub fn free_near(
+        &self,
+        network: &RoadNetwork,
+        node: NodeId,
+        min_capacity: usize,
+        start: f64,
+        end: f64,
+    ) -> Vec<(usize, f64)> {
+        let mut found: Vec<(usize, f64)> = self
+            .vehicles
+            .iter()
+            .enumerate()
+            .filter(|(id, vehicle)| {
+                vehicle.truck.capacity() >= min_capacity && self.is_free(*id, start, end)
+            })
+            .filter_map(|(id, vehicle)| {
+                let matrix = network.distance_matrix(Some(vehicle.truck.profile()));
+                let position = self.position_at(id, start)?;
+                matrix.distance_km(position, node).map(|km| (id, km))
+            })
+            .collect();
+        found.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        found
+    }
+
+    pub fn assign(
+        &mut self,
+        id: usize,
+        route: Route,
+        start: f64,
+    ) -> Result<&Assignment, &'static str> {
+        let vehicle = self.vehicles.get(id).ok_or("Unknown truck")?;
+        if self.position_at(id, start) != Some(route.origin()) {
+            return Err("Route does not start at truck location");
+        }
+        let end = start + route.eta_hours;
+        // Следующий запланированный рейс должен начинаться там, где закончится этот
+        let next = self
+            .assignments(id)
+            .filter(|a| a.start >= end)
+            .min_by(|a, b| a.start.total_cmp(&b.start));
+        if next.is_some_and(|a| a.route.origin() != route.destination()) {
+            return Err("Route does not lead to the next trip");
+        }
+        if !vehicle.calendar.is_available(start, end) {
+            return Err("Truck is not available");
+        }
+        if !self.is_free(id, start, end) {
+            return Err("Truck is already assigned");
+        }
+        self.assignments.push(Assignment {
+            vehicle: id,
+            start,
+            end,
+            route,
+        });
+        Ok(self.assignments.last().expect("just pushed"))
+    }
+
+    // Ближайшая свободная машина подъезжает к месту загрузки к началу рейса и выполняет его
+    pub fn assign_nearest(
+        &mut self,
+        network: &RoadNetwork,
+        route: Route,
+        capacity: usize,
+        start: f64,
+    ) -> Result<usize, &'static str> {
+        let end = start + route.eta_hours;
+        let candidates = self.free_near(network, route.origin(), capacity, start, end);
+        for (id, _) in candidates {
+            let Some(position) = self.position_at(id, start) else {
+                continue;
+            };
+            let empty_run = if position == route.origin() {
+                None
+            } else {
+                let truck = &self.vehicles[id].truck;
+                let Some(empty_run) = network.route_for(truck, position, route.origin()) else {
+                    continue;
+                };
+                if !self.is_free(id, start - empty_run.eta_hours, start) {
+                    continue;
+                }
+                Some(empty_run)
+            };
+            let departure = empty_run.as_ref().map(|run| start - run.eta_hours);
+            if let Some((empty_run, departure)) = empty_run.zip(departure) {
+                self.assign(id, empty_run, departure)?;
+            }
+            if let Err(error) = self.assign(id, route, start) {
+                if let Some(departure) = departure {
+                    self.unassign(id, departure);
+                }
+                return Err(error);
+            }
+            return Ok(id);
+        }
+        Err("No free truck can reach the trip origin")
+    }
+
+    pub fn unassign(&mut self, id: usize, start: f64) -> Option<Assignment> {
+        let index = self
+            .assignments
+            .iter()
+            .position(|a| a.vehicle == id && a.start == start)?;
+        Some(self.assignments.remove(index))
+    }
+
+    pub fn trip_cost(&self, id: usize, route: &Route) -> Option<f64> {
+        let vehicle = self.vehicles.get(id)?;
+        Some(vehicle.cost().trip(route.distance_km, route.eta_hours))
+    }
+}
+
+impl Default for Fleet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Location, SiteKind};
+
+    fn network() -> (RoadNetwork, NodeId, NodeId, NodeId) {
+        let mut network = RoadNetwork::new();
+        let north = network.add_location(Location::new("north", SiteKind::Warehouse, 0.0, 50.0));
+        let south = network.add_location(Location::new("south", SiteKind::Warehouse, 0.0, -50.0));
+        let city = network.add_location(Location::new("city", SiteKind::Market, 0.0, 10.0));
+        network.add_two_way_road(north, city, 40.0, 0.5).unwrap();
+        network.add_two_way_road(south, city, 60.0, 0.8).unwrap();
+        (network, north, south, city)
+    }
+
+    #[test]
+    fn test_free_near_filters_and_sorts() {
+        let (network, north, south, city) = network();
+        let mut fleet = Fleet::new();
+        let van = fleet.add_new("van", VehicleType::Van, north);
+        let rigid = fleet.add_new("rigid", VehicleType::Rigid, south);
+        let artic = fleet.add_new("artic", VehicleType::Articulated, north);
+
+        assert_eq!(
+            fleet.free_near(&network, city, 1, 0.0, 1.0),
+            vec![(van, 40.0), (artic, 40.0), (rigid, 60.0)]
+        );
+        assert_eq!(
+            fleet.free_near(&network, city, 10, 0.0, 1.0),
+            vec![(artic, 40.0), (rigid, 60.0)]
+        );
+        assert_eq!(
+            fleet.of_kind(VehicleType::Van).collect::<Vec<_>>(),
+            vec![van]
+        );
+        assert_eq!(fleet.len(), 3);
+    }
+
+    #[test]
+    fn test_calendar_limits_availability() {
+        let calendar = Calendar::always().with_shift(6.0, 18.0).block(30.0, 40.0);
+
+        assert!(calendar.is_available(8.0, 12.0));
+        assert!(!calendar.is_available(17.0, 19.0));
+        assert!(!calendar.is_available(32.0, 34.0));
+        assert!(calendar.is_available(54.0, 56.0));
+
+        // Смена с 22 до 6 утра следующего дня
+        let night = Calendar::always().with_shift(22.0, 6.0);
+        assert!(night.is_available(23.0, 27.0));
+        assert!(night.is_available(2.0, 5.0));
+        assert!(!night.is_available(5.0, 7.0));
+        assert!(!night.is_available(12.0, 13.0));
+    }
+
+    #[test]
+    fn test_assignments_do_not_overlap() {
+        let (network, north, _, city) = network();
+        let mut fleet = Fleet::new();
+        let van = fleet.add(
+            FleetVehicle::new(VehicleType::Van.truck("van"), VehicleType::Van, north)
+                .with_calendar(Calendar::always().with_shift(6.0, 18.0)),
+        );
+        let route = network.route(north, city).unwrap();
+
+        assert_eq!(
+            fleet.assign(van, route.clone(), 4.0).err(),
+            Some("Truck is not available")
+        );
+        assert_eq!(fleet.assign(van, route.clone(), 8.0).unwrap().end, 8.5);
+        assert_eq!(
+            fleet.assign(van, route.clone(), 8.25).err(),
+            Some("Truck is already assigned")
+        );
+        assert!(fleet.free_near(&network, city, 1, 8.0, 9.0).is_empty());
+        assert!(fleet.unassign(van, 8.0).is_some());
+        assert_eq!(
+            fleet.assign_nearest(&network, route.clone(), 1, 8.25),
+            Ok(van)
+        );
+        assert_eq!(
+            fleet.assign_nearest(&network, route.clone(), 5, 10.0),
+            Err("No free truck can reach the trip origin")
+        );
+        assert_eq!(fleet.trip_cost(van, &route), Some(40.0 * 0.35 + 0.5 * 25.0));
+    }
+
+    #[test]
+    fn test_broken_trucks_are_not_free() {
+        let (_, north, _, _) = network();
+        let mut fleet = Fleet::new();
+        let id = fleet.add_new("rigid", VehicleType::Rigid, north);
+
+        fleet.truck_mut(id).unwrap().reliability_mut().break_down();
+        assert!(!fleet.is_free(id, 0.0, 1.0));
+    }
+
+    #[test]
+    fn test_trips_chain_from_previous_destination() {
+        let (network, north, south, city) = network();
+        let mut fleet = Fleet::new();
+        let van = fleet.add_new("van", VehicleType::Van, north);
+        let there = network.route(north, city).unwrap();
+        let onward = network.route(city, south).unwrap();
+
+        // Второй рейс планируется заранее: машина будет в городе к его началу
+        assert!(fleet.assign(van, there.clone(), 1.0).is_ok());
+        assert_eq!(
+            fleet.assign(van, there.clone(), 2.0).err(),
+            Some("Route does not start at truck location")
+        );
+        assert_eq!(fleet.assign(van, onward.clone(), 2.0).unwrap().end, 2.8);
+        assert_eq!(fleet.position_at(van, 3.0), Some(south));
+        // Рейс перед уже запланированным обязан привести машину к его началу
+        assert!(fleet.unassign(van, 1.0).is_some());
+        assert_eq!(
+            fleet
+                .assign(van, network.route(north, south).unwrap(), 0.0)
+                .err(),
+            Some("Route does not lead to the next trip")
+        );
+    }
+
+    #[test]
+    fn test_nearest_truck_drives_empty_to_origin() {
+        let (network, north, south, city) = network();
+        let mut fleet = Fleet::new();
+        let rigid = fleet.add_new("rigid", VehicleType::Rigid, south);
+        let route = network.route(north, city).unwrap();
+
+        assert_eq!(
+            fleet.assign_nearest(&network, route.clone(), 1, 5.0),
+            Ok(rigid)
+        );
+        let trips: Vec<&Assignment> = fleet.assignments(rigid).collect();
+        assert_eq!(trips.len(), 2);
+        // Порожний пробег юг → север заканчивается к началу погрузки
+        assert_eq!(trips[0].route.origin(), south);
+        assert_eq!(trips[0].route.destination(), north);
+        assert!((trips[0].end - 5.0).abs() < 1e-9);
+        assert_eq!(trips[1].route, route);
+        assert_eq!(fleet.position_at(rigid, 6.0), Some(city));
+    }
+}
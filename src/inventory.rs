@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StockLevel {
+    pub on_hand: usize,
+    pub reserved: usize,
+    pub in_transit: usize,
+    pub on_order: usize,
+}
+
+impl StockLevel {
+    pub fn available(&self) -> usize {
+        self.on_hand.saturating_sub(self.reserved)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Discrepancy {
+    pub sku: String,
+    pub recorded: StockLevel,
+    pub counted: usize,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Inventory {
+    levels: BTreeMap<String, StockLevel>,
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self {
+            levels: BTreeMap::new(),
+        }
+    }
+
+    pub fn level(&self, sku: &str) -> StockLevel {
+        self.levels.get(sku).copied().unwrap_or_default()
+    }
+
+    pub fn skus(&self) -> impl Iterator<Item = &str> {
+        self.levels.keys().map(|sku| sku.as_str())
+    }
+
+    fn entry(&mut self, sku: &str) -> &mut StockLevel {
+        self.levels.entry(sku.to_string()).or_default()
+    }
+
+    pub fn receive(&mut self, sku: &str) {
+        self.entry(sku).on_hand += 1;
+    }
+
+    // Приёмка ожидаемой поставки списывает единицу из товара в пути
+    pub fn receive_expected(&mut self, sku: &str) {
+        let level = self.entry(sku);
+        level.on_hand += 1;
+        level.in_transit = level.in_transit.saturating_sub(1);
+    }
+
+    // Прямая поставка с завода минует путь и закрывает заказ при приёмке
+    pub fn receive_ordered(&mut self, sku: &str) {
+        let level = self.entry(sku);
+        level.on_hand += 1;
+        level.on_order = level.on_order.saturating_sub(1);
+    }
+
+    pub fn issue(&mut self, sku: &str) {
+        let level = self.entry(sku);
+        level.on_hand = level.on_hand.saturating_sub(1);
+    }
+
+    pub fn issue_reserved(&mut self, sku: &str) -> Result<(), &'static str> {
+        let level = self.entry(sku);
+        if level.reserved == 0 {
+            return Err("Nothing reserved");
+        }
+        level.reserved -= 1;
+        level.on_hand = level.on_hand.saturating_sub(1);
+        Ok(())
+    }
+
+    pub fn order(&mut self, sku: &str, quantity: usize) {
+        self.entry(sku).on_order += quantity;
+    }
+
+    pub fn cancel_order(&mut self, sku: &str, quantity: usize) {
+        let level = self.entry(sku);
+        level.on_order = level.on_order.saturating_sub(quantity);
+    }
+
+    // Отгруженное по заказу переходит из заказанного в товар в пути
+    pub fn expect(&mut self, sku: &str, quantity: usize) {
+        let level = self.entry(sku);
+        level.on_order = level.on_order.saturating_sub(quantity);
+        level.in_transit += quantity;
+    }
+
+    pub fn cancel_expected(&mut self, sku: &str, quantity: usize) {
+        let level = self.entry(sku);
+        level.in_transit = level.in_transit.saturating_sub(quantity);
+    }
+
+    pub fn reserve(&mut self, sku: &str, quantity: usize) -> Result<(), &'static str> {
+        let level = self.entry(sku);
+        if level.available() < quantity {
+            return Err("Not enough available stock");
+        }
+        level.reserved += quantity;
+        Ok(())
+    }
+
+    pub fn release(&mut self, sku: &str, quantity: usize) -> Result<(), &'static str> {
+        let level = self.entry(sku);
+        if level.reserved < quantity {
+            return Err("Cannot release more than reserved");
+        }
+        level.reserved -= quantity;
+        Ok(())
+    }
+
+    pub fn reconcile<'a, I>(&self, physical: I) -> Vec<Discrepancy>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut counted: BTreeMap<&str, usize> = BTreeMap::new();
+        for sku in physical {
+            *counted.entry(sku).or_default() += 1;
+        }
+        let mut skus: Vec<&str> = self.skus().chain(counted.keys().copied()).collect();
+        skus.sort();
+        skus.dedup();
+
+        skus.into_iter()
+            .filter_map(|sku| {
+                let recorded = self.level(sku);
+                let counted = counted.get(sku).copied().unwrap_or(0);
+                if recorded.on_hand != counted || recorded.reserved > counted {
+                    Some(Discrepancy {
+                        sku: sku.to_string(),
+                        recorded,
+                        counted,
+                    })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_receive_issue_and_available() {
+        let mut inventory = Inventory::new();
+        inventory.receive("bolt");
+        inventory.receive("bolt");
+        inventory.receive("bolt");
+        inventory.reserve("bolt", 2).unwrap();
+
+        let level = inventory.level("bolt");
+        assert_eq!(level.on_hand, 3);
+        assert_eq!(level.reserved, 2);
+        assert_eq!(level.available(), 1);
+
+        inventory.issue_reserved("bolt").unwrap();
+        assert_eq!(inventory.level("bolt").available(), 1);
+        assert_eq!(inventory.level("nut"), StockLevel::default());
+    }
+
+    #[test]
+    fn test_reserve_and_release_limits() {
+        let mut inventory = Inventory::new();
+        inventory.receive("nut");
+
+        assert_eq!(inventory.reserve("nut", 2), Err("Not enough available stock"));
+        assert!(inventory.reserve("nut", 1).is_ok());
+        // Второй заказ не может занять ту же единицу
+        assert_eq!(inventory.reserve("nut", 1), Err("Not enough available stock"));
+
+        assert_eq!(inventory.release("nut", 2), Err("Cannot release more than reserved"));
+        assert!(inventory.release("nut", 1).is_ok());
+        assert_eq!(inventory.issue_reserved("nut"), Err("Nothing reserved"));
+    }
+
+    #[test]
+    fn test_in_transit() {
+        let mut inventory = Inventory::new();
+        inventory.expect("bolt", 2);
+        assert_eq!(inventory.level("bolt").in_transit, 2);
+
+        inventory.receive_expected("bolt");
+        assert_eq!(inventory.level("bolt").in_transit, 1);
+        assert_eq!(inventory.level("bolt").on_hand, 1);
+
+        // Незаказанный приход не трогает товар в пути
+        inventory.receive("bolt");
+        assert_eq!(inventory.level("bolt").in_transit, 1);
+
+        inventory.cancel_expected("bolt", 5);
+        assert_eq!(inventory.level("bolt").in_transit, 0);
+    }
+
+    #[test]
+    fn test_on_order() {
+        let mut inventory = Inventory::new();
+        inventory.order("bolt", 5);
+        assert_eq!(inventory.level("bolt").on_order, 5);
+
+        // Отгрузка части заказа переводит её в путь, не удваивая ожидаемое
+        inventory.expect("bolt", 3);
+        let level = inventory.level("bolt");
+        assert_eq!((level.on_order, level.in_transit), (2, 3));
+
+        inventory.receive_ordered("bolt");
+        let level = inventory.level("bolt");
+        assert_eq!((level.on_hand, level.on_order, level.in_transit), (1, 1, 3));
+
+        inventory.cancel_order("bolt", 4);
+        assert_eq!(inventory.level("bolt").on_order, 0);
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let mut inventory = Inventory::new();
+        inventory.receive("bolt");
+        inventory.receive("nut");
+
+        assert!(inventory.reconcile(["bolt", "nut"]).is_empty());
+
+        let diffs = inventory.reconcile(["bolt", "bolt", "washer"]);
+        assert_eq!(diffs.len(), 3);
+        assert_eq!(diffs[0].sku, "bolt");
+        assert_eq!(diffs[0].counted, 2);
+        assert_eq!(diffs[1].sku, "nut");
+        assert_eq!(diffs[2].recorded.on_hand, 0);
+    }
+}
@@ -0,0 +1,319 @@
+use crate::collections::LimitedQueue;
+use crate::models::{Category, Product};
+
+const AISLE_WIDTH_M: f64 = 3.0;
+const SLOT_LENGTH_M: f64 = 1.2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZoneKind {
+    Receiving,
+    Bulk,
+    PickFace,
+    Cold,
+    Hazmat,
+    Shipping,
+}
+
+impl ZoneKind {
+    pub fn is_storage(&self) -> bool {
+        !matches!(self, ZoneKind::Receiving | ZoneKind::Shipping)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BinId {
+    pub zone: usize,
+    pub bin: usize,
+}
+
+// Товар в ячейке вместе с порядковым номером приёмки
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stored {
+    pub received: u64,
+    pub product: Product,
+}
+
+pub struct Bin {
+    aisle: u32,
+    slot: u32,
+    allowed: Vec<Category>,
+    cargo: LimitedQueue<Stored>,
+}
+
+impl Bin {
+    pub fn new(capacity: usize, aisle: u32, slot: u32) -> Self {
+        Self {
+            aisle,
+            slot,
+            allowed: vec![Category::General, Category::Chilled, Category::Hazardous],
+            cargo: LimitedQueue::new(capacity),
+        }
+    }
+
+    pub fn allow(mut self, categories: &[Category]) -> Self {
+        self.allowed = categories.to_vec();
+        self
+    }
+
+    pub fn allows(&self, category: Category) -> bool {
+        self.allowed.contains(&category)
+    }
+
+    pub fn aisle(&self) -> u32 {
+        self.aisle
+    }
+
+    pub fn slot(&self) -> u32 {
+        self.slot
+    }
+
+    // Расстояние от ворот (0, 0) по проходам, в метрах
+    pub fn distance_m(&self) -> f64 {
+        self.aisle as f64 * AISLE_WIDTH_M + self.slot as f64 * SLOT_LENGTH_M
+    }
+
+    pub fn distance_to(&self, other: &Bin) -> f64 {
+        self.aisle.abs_diff(other.aisle) as f64 * AISLE_WIDTH_M
+            + self.slot.abs_diff(other.slot) as f64 * SLOT_LENGTH_M
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.cargo.max_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.cargo.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cargo.is_empty()
+    }
+
+    pub fn has_room(&self) -> bool {
+        self.cargo.len() < self.cargo.max_size
+    }
+
+    pub fn accepts(&self, product: &Product) -> bool {
+        self.has_room() && self.allows(product.category)
+    }
+
+    pub fn contents(&self) -> impl Iterator<Item = &Stored> {
+        self.cargo.iter()
+    }
+
+    pub fn store(&mut self, stored: Stored) -> Result<(), &'static str> {
+        if !self.allows(stored.product.category) {
+            return Err("Bin does not allow this category");
+        }
+        self.cargo.enqueue(stored)
+    }
+
+    pub fn take(&mut self, received: u64) -> Option<Stored> {
+        self.cargo.remove_first(|stored| stored.received == received)
+    }
+}
+
+pub struct Zone {
+    kind: ZoneKind,
+    bins: Vec<Bin>,
+}
+
+impl Zone {
+    pub fn new(kind: ZoneKind) -> Self {
+        Self {
+            kind,
+            bins: Vec::new(),
+        }
+    }
+
+    pub fn with_bin(mut self, bin: Bin) -> Self {
+        self.bins.push(bin);
+        self
+    }
+
+    pub fn kind(&self) -> ZoneKind {
+        self.kind
+    }
+
+    pub fn bins(&self) -> &[Bin] {
+        &self.bins
+    }
+}
+
+pub struct Layout {
+    zones: Vec<Zone>,
+}
+
+impl Layout {
+    pub fn new(zones: Vec<Zone>) -> Self {
+        Self { zones }
+    }
+
+    // Вырожденный склад: одна ячейка на всё
+    pub fn single_bin(capacity: usize) -> Self {
+        Self::new(vec![Zone::new(ZoneKind::Bulk).with_bin(Bin::new(capacity, 0, 0))])
+    }
+
+    pub fn zones(&self) -> &[Zone] {
+        &self.zones
+    }
+
+    pub fn bin(&self, id: BinId) -> Option<&Bin> {
+        self.zones.get(id.zone)?.bins.get(id.bin)
+    }
+
+    pub fn bin_mut(&mut self, id: BinId) -> Option<&mut Bin> {
+        self.zones.get_mut(id.zone)?.bins.get_mut(id.bin)
+    }
+
+    pub fn bins(&self) -> impl Iterator<Item = (BinId, &Bin)> {
+        self.zones.iter().enumerate().flat_map(|(z, zone)| {
+            zone.bins
+                .iter()
+                .enumerate()
+                .map(move |(b, bin)| (BinId { zone: z, bin: b }, bin))
+        })
+    }
+
+    pub fn zone_kind(&self, id: BinId) -> Option<ZoneKind> {
+        self.zones.get(id.zone).map(|zone| zone.kind)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.bins().map(|(_, bin)| bin.capacity()).sum()
+    }
+
+    pub fn len(&self) -> usize {
+        self.bins().map(|(_, bin)| bin.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn stored(&self) -> impl Iterator<Item = (BinId, &Stored)> {
+        self.bins()
+            .flat_map(|(id, bin)| bin.contents().map(move |stored| (id, stored)))
+    }
+
+    pub fn locate(&self, sku: &str) -> Vec<(BinId, usize)> {
+        self.bins()
+            .filter_map(|(id, bin)| {
+                let count = bin.contents().filter(|s| s.product.sku == sku).count();
+                (count > 0).then_some((id, count))
+            })
+            .collect()
+    }
+
+    // Правило размещения: сначала профильная зона категории, затем ближайшая подходящая ячейка
+    pub fn put_away(&self, product: &Product) -> Result<BinId, &'static str> {
+        let preferred: &[ZoneKind] = match product.category {
+            Category::Chilled => &[ZoneKind::Cold],
+            Category::Hazardous => &[ZoneKind::Hazmat],
+            Category::General => &[ZoneKind::PickFace, ZoneKind::Bulk],
+        };
+        let candidates: Vec<(BinId, &Bin)> = self
+            .bins()
+            .filter(|(id, bin)| {
+                self.zones[id.zone].kind.is_storage() && bin.allows(product.category)
+            })
+            .collect();
+        if candidates.is_empty() {
+            return Err("No bin allows this category");
+        }
+        let rank = |id: &BinId| {
+            preferred
+                .iter()
+                .position(|kind| *kind == self.zones[id.zone].kind)
+                .unwrap_or(preferred.len())
+        };
+        candidates
+            .into_iter()
+            .filter(|(_, bin)| bin.has_room())
+            .min_by(|(a, bin_a), (b, bin_b)| {
+                rank(a)
+                    .cmp(&rank(b))
+                    .then(bin_a.distance_m().total_cmp(&bin_b.distance_m()))
+                    .then(a.cmp(b))
+            })
+            .map(|(id, _)| id)
+            .ok_or("Queue overflow")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Factory;
+
+    fn product(category: Category) -> Product {
+        let mut factory = Factory::new("LayoutFactory".to_string());
+        factory.set_category("item", category);
+        factory.create_sku("item").unwrap()
+    }
+
+    fn sample_layout() -> Layout {
+        Layout::new(vec![
+            Zone::new(ZoneKind::Receiving).with_bin(Bin::new(10, 0, 0)),
+            Zone::new(ZoneKind::Bulk)
+                .with_bin(Bin::new(1, 4, 0).allow(&[Category::General]))
+                .with_bin(Bin::new(5, 5, 0).allow(&[Category::General])),
+            Zone::new(ZoneKind::PickFace).with_bin(Bin::new(1, 1, 3).allow(&[Category::General])),
+            Zone::new(ZoneKind::Cold).with_bin(Bin::new(2, 2, 0).allow(&[Category::Chilled])),
+            Zone::new(ZoneKind::Shipping).with_bin(Bin::new(10, 0, 1)),
+        ])
+    }
+
+    #[test]
+    fn test_single_bin_layout() {
+        let layout = Layout::single_bin(3);
+
+        assert_eq!(layout.capacity(), 3);
+        assert!(layout.is_empty());
+        assert_eq!(layout.zones()[0].kind(), ZoneKind::Bulk);
+        assert_eq!(layout.put_away(&product(Category::Hazardous)), Ok(BinId { zone: 0, bin: 0 }));
+    }
+
+    #[test]
+    fn test_put_away_prefers_category_zone() {
+        let layout = sample_layout();
+
+        assert_eq!(layout.put_away(&product(Category::General)), Ok(BinId { zone: 2, bin: 0 }));
+        assert_eq!(layout.put_away(&product(Category::Chilled)), Ok(BinId { zone: 3, bin: 0 }));
+        // Опасный груз некуда положить: приёмка и отгрузка не хранение
+        assert_eq!(
+            layout.put_away(&product(Category::Hazardous)),
+            Err("No bin allows this category")
+        );
+    }
+
+    #[test]
+    fn test_put_away_falls_back_to_next_bin() {
+        let mut layout = sample_layout();
+        let pick_face = BinId { zone: 2, bin: 0 };
+        layout
+            .bin_mut(pick_face)
+            .unwrap()
+            .store(Stored { received: 1, product: product(Category::General) })
+            .unwrap();
+
+        assert_eq!(layout.put_away(&product(Category::General)), Ok(BinId { zone: 1, bin: 0 }));
+        assert_eq!(layout.locate("item"), vec![(pick_face, 1)]);
+        assert_eq!(layout.len(), 1);
+    }
+
+    #[test]
+    fn test_bin_policy_and_take() {
+        let mut bin = Bin::new(2, 3, 2).allow(&[Category::Chilled]);
+
+        assert_eq!(bin.distance_m(), 11.4);
+        assert_eq!(
+            bin.store(Stored { received: 1, product: product(Category::General) }),
+            Err("Bin does not allow this category")
+        );
+        bin.store(Stored { received: 7, product: product(Category::Chilled) }).unwrap();
+        assert!(bin.take(1).is_none());
+        assert_eq!(bin.take(7).unwrap().received, 7);
+        assert!(bin.is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Account {
+    Cash,
+    Revenue,
+    ProductionCost,
+    StorageCost,
+    TransportCost,
+}
+
+impl Account {
+    // Счета, которые растут по дебету
+    pub fn is_debit_normal(&self) -> bool {
+        !matches!(self, Account::Revenue)
+    }
+
+    pub fn is_expense(&self) -> bool {
+        matches!(
+            self,
+            Account::ProductionCost | Account::StorageCost | Account::TransportCost
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    pub time: f64,
+    pub debit: Account,
+    pub credit: Account,
+    pub amount: f64,
+    pub memo: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProfitAndLoss {
+    pub revenue: f64,
+    pub production: f64,
+    pub storage: f64,
+    pub transport: f64,
+    pub units_delivered: usize,
+}
+
+impl ProfitAndLoss {
+    pub fn costs(&self) -> f64 {
+        self.production + self.storage + self.transport
+    }
+
+    pub fn profit(&self) -> f64 {
+        self.revenue - self.costs()
+    }
+
+    pub fn margin(&self) -> f64 {
+        if self.revenue == 0.0 {
+            return 0.0;
+        }
+        self.profit() / self.revenue
+    }
+
+    pub fn cost_per_unit_delivered(&self) -> Option<f64> {
+        if self.units_delivered == 0 {
+            return None;
+        }
+        Some(self.costs() / self.units_delivered as f64)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Ledger {
+    entries: Vec<JournalEntry>,
+}
+
+impl Ledger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn post(
+        &mut self,
+        time: f64,
+        debit: Account,
+        credit: Account,
+        amount: f64,
+        memo: &str,
+    ) -> Result<(), &'static str> {
+        if debit == credit {
+            return Err("Debit and credit accounts must differ");
+        }
+        if !amount.is_finite() || amount <= 0.0 {
+            return Err("Amount must be positive");
+        }
+        self.entries.push(JournalEntry {
+            time,
+            debit,
+            credit,
+            amount,
+            memo: memo.to_string(),
+        });
+        Ok(())
+    }
+
+    // Расход оплачен из кассы
+    pub fn expense(
+        &mut self,
+        time: f64,
+        account: Account,
+        amount: f64,
+        memo: &str,
+    ) -> Result<(), &'static str> {
+        if !account.is_expense() {
+            return Err("Not an expense account");
+        }
+        self.post(time, account, Account::Cash, amount, memo)
+    }
+
+    pub fn sale(&mut self, time: f64, amount: f64, memo: &str) -> Result<(), &'static str> {
+        self.post(time, Account::Cash, Account::Revenue, amount, memo)
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    pub fn balance(&self, account: Account) -> f64 {
+        let mut debits = 0.0;
+        let mut credits = 0.0;
+        for entry in &self.entries {
+            if entry.debit == account {
+                debits += entry.amount;
+            }
+            if entry.credit == account {
+                credits += entry.amount;
+            }
+        }
+        if account.is_debit_normal() {
+            debits - credits
+        } else {
+            credits - debits
+        }
+    }
+
+    // Оборотно-сальдовая проверка: дебетовые остатки равны кредитовым
+    pub fn is_balanced(&self) -> bool {
+        let accounts = [
+            Account::Cash,
+            Account::Revenue,
+            Account::ProductionCost,
+            Account::StorageCost,
+            Account::TransportCost,
+        ];
+        let mut debit = 0.0;
+        let mut credit = 0.0;
+        for account in accounts {
+            if account.is_debit_normal() {
+                debit += self.balance(account);
+            } else {
+                credit += self.balance(account);
+            }
+        }
+        (debit - credit).abs() < 1e-6
+    }
+
+    pub fn profit_and_loss(&self, units_delivered: usize) -> ProfitAndLoss {
+        ProfitAndLoss {
+            revenue: self.balance(Account::Revenue),
+            production: self.balance(Account::ProductionCost),
+            storage: self.balance(Account::StorageCost),
+            transport: self.balance(Account::TransportCost),
+            units_delivered,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_keep_books_balanced() {
+        let mut ledger = Ledger::new();
+        ledger
+            .expense(0.0, Account::ProductionCost, 40.0, "4 units")
+            .unwrap();
+        ledger
+            .expense(1.0, Account::TransportCost, 25.0, "trip")
+            .unwrap();
+        ledger.sale(2.0, 100.0, "4 units sold").unwrap();
+
+        assert!(ledger.is_balanced());
+        assert_eq!(ledger.balance(Account::Cash), 35.0);
+        assert_eq!(ledger.balance(Account::Revenue), 100.0);
+
+        let report = ledger.profit_and_loss(4);
+        assert_eq!(report.profit(), 35.0);
+        assert_eq!(report.margin(), 0.35);
+        assert_eq!(report.cost_per_unit_delivered(), Some(16.25));
+        assert_eq!(ledger.profit_and_loss(0).cost_per_unit_delivered(), None);
+    }
+
+    #[test]
+    fn test_invalid_postings_are_rejected() {
+        let mut ledger = Ledger::new();
+        assert_eq!(
+            ledger.post(0.0, Account::Cash, Account::Cash, 1.0, "loop"),
+            Err("Debit and credit accounts must differ")
+        );
+        assert_eq!(
+            ledger.sale(0.0, -5.0, "refund"),
+            Err("Amount must be positive")
+        );
+        assert_eq!(
+            ledger.expense(0.0, Account::Revenue, 5.0, "wrong"),
+            Err("Not an expense account")
+        );
+        assert!(ledger.entries().is_empty());
+    }
+}
@@ -0,0 +1,26 @@
+pub mod collections;
+pub mod crossdock;
+pub mod docks;
+pub mod drivers;
+pub mod emissions;
+pub mod energy;
+pub mod fleet;
+pub mod inventory;
+pub mod layout;
+pub mod ledger;
+pub mod loadplan;
+pub mod market;
+pub mod models;
+pub mod network;
+pub mod orders;
+pub mod packing;
+pub mod picking;
+pub mod quality;
+pub mod random;
+pub mod reliability;
+pub mod replenishment;
+pub mod routing;
+pub mod simulation;
+pub mod strategies;
+pub mod trip;
+pub mod vrp;
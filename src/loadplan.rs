@@ -0,0 +1,257 @@
+use crate::models::{Product, Truck};
+use crate::network::NodeId;
+use crate::routing::Route;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drop {
+    pub node: NodeId,
+    pub products: Vec<Product>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadPlan {
+    // Порядок погрузки: первый элемент ложится на дно кузова
+    pub sequence: Vec<Product>,
+    // Остановки в порядке объезда и сколько единиц снять на каждой
+    pub stops: Vec<(NodeId, usize)>,
+}
+
+impl LoadPlan {
+    pub fn units(&self) -> usize {
+        self.sequence.len()
+    }
+
+    pub fn weight_kg(&self) -> f64 {
+        self.sequence.iter().map(|product| product.weight_kg).sum()
+    }
+
+    // Грузится либо вся последовательность, либо ничего: при отказе товар остаётся в плане
+    pub fn load_into(&mut self, truck: &mut Truck) -> Result<usize, &'static str> {
+        truck.check_load_all(&self.sequence)?;
+        let loaded = self.sequence.len();
+        for product in self.sequence.drain(..) {
+            truck.load(product).expect("load was checked");
+        }
+        Ok(loaded)
+    }
+}
+
+// Сначала нехрупкое, тяжёлое ниже; хрупкое сверху, от тяжёлого к лёгкому
+fn stack_order(products: &mut [Product]) {
+    products.sort_by(|a, b| {
+        a.fragile
+            .cmp(&b.fragile)
+            .then(b.weight_kg.total_cmp(&a.weight_kg))
+    });
+}
+
+// Первая остановка маршрута разгружается первой, поэтому её товар грузится последним
+pub fn plan_load(truck: &Truck, route: &Route, drops: Vec<Drop>) -> Result<LoadPlan, &'static str> {
+    let mut ordered: Vec<(usize, Drop)> = Vec::new();
+    for drop in drops {
+        let position = route
+            .path
+            .iter()
+            .skip(1)
+            .position(|node| *node == drop.node)
+            .ok_or("Stop is not on the route")?;
+        match ordered
+            .iter_mut()
+            .find(|(_, known)| known.node == drop.node)
+        {
+            Some((_, known)) => known.products.extend(drop.products),
+            None => ordered.push((position, drop)),
+        }
+    }
+    ordered.sort_by_key(|(position, _)| *position);
+
+    let units: usize = ordered.iter().map(|(_, drop)| drop.products.len()).sum();
+    if truck.loaded() + units > truck.capacity() {
+        return Err("Not enough capacity");
+    }
+    let weight: f64 = ordered
+        .iter()
+        .flat_map(|(_, drop)| drop.products.iter())
+        .map(|product| product.weight_kg)
+        .sum();
+    if truck.payload_kg() + weight > truck.max_payload_kg() {
+        return Err("Payload limit exceeded");
+    }
+
+    let stops = ordered
+        .iter()
+        .map(|(_, drop)| (drop.node, drop.products.len()))
+        .collect();
+    let mut sequence = Vec::with_capacity(units);
+    for (_, mut drop) in ordered.into_iter().rev() {
+        stack_order(&mut drop.products);
+        sequence.extend(drop.products);
+    }
+
+    // Над хрупким товаром не должно оказаться ничего тяжелее него,
+    // включая то, что уже лежит в кузове
+    let below = truck.cargo().chain(sequence.iter());
+    let mut heaviest_above = 0.0_f64;
+    let stacked: Vec<&Product> = below.collect();
+    for product in stacked.iter().rev() {
+        if product.fragile && heaviest_above > product.weight_kg {
+            return Err("Heavy cargo would sit on fragile goods");
+        }
+        heaviest_above = heaviest_above.max(product.weight_kg);
+    }
+
+    Ok(LoadPlan { sequence, stops })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Factory;
+    use crate::network::{Location, RoadNetwork, SiteKind};
+
+    fn network() -> (RoadNetwork, Vec<NodeId>) {
+        let mut network = RoadNetwork::new();
+        let nodes: Vec<NodeId> = (0..4)
+            .map(|i| {
+                network.add_location(Location::new(
+                    &format!("stop {}", i),
+                    SiteKind::Market,
+                    i as f64 * 10.0,
+                    0.0,
+                ))
+            })
+            .collect();
+        for pair in nodes.windows(2) {
+            network.add_road(pair[0], pair[1], 10.0, 0.2).unwrap();
+        }
+        (network, nodes)
+    }
+
+    fn factory() -> Factory {
+        let mut factory = Factory::new("PlanFactory".to_string());
+        factory.set_weight("glass", 5.0);
+        factory.set_fragile("glass", true);
+        factory.set_weight("steel", 40.0);
+        factory.set_weight("box", 10.0);
+        factory
+    }
+
+    fn drop(node: NodeId, factory: &mut Factory, skus: &[&str]) -> Drop {
+        Drop {
+            node,
+            products: skus
+                .iter()
+                .map(|sku| factory.create_sku(sku).unwrap())
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_stops_unload_from_top_in_route_order() {
+        let (network, nodes) = network();
+        let route = network.route(nodes[0], nodes[3]).unwrap();
+        let mut factory = factory();
+        let mut truck = Truck::new("Planner".to_string(), 10).with_location(nodes[0]);
+        let drops = vec![
+            drop(nodes[3], &mut factory, &["box"]),
+            drop(nodes[1], &mut factory, &["box", "steel"]),
+            drop(nodes[2], &mut factory, &["box"]),
+        ];
+
+        let mut plan = plan_load(&truck, &route, drops).unwrap();
+        assert_eq!(
+            plan.stops,
+            vec![(nodes[1], 2), (nodes[2], 1), (nodes[3], 1)]
+        );
+        assert_eq!(plan.weight_kg(), 70.0);
+        assert_eq!(plan.load_into(&mut truck), Ok(4));
+        assert_eq!(plan.units(), 0);
+
+        // Первая остановка снимает свои две единицы сверху
+        let first: Vec<f64> = (0..2).map(|_| truck.unload().unwrap().weight_kg).collect();
+        assert_eq!(first, vec![10.0, 40.0]);
+    }
+
+    #[test]
+    fn test_fragile_goods_stay_on_top() {
+        let (network, nodes) = network();
+        let route = network.route(nodes[0], nodes[1]).unwrap();
+        let mut factory = factory();
+        let truck = Truck::new("Planner".to_string(), 10);
+
+        let plan = plan_load(
+            &truck,
+            &route,
+            vec![drop(nodes[1], &mut factory, &["glass", "steel", "box"])],
+        )
+        .unwrap();
+        let skus: Vec<&str> = plan.sequence.iter().map(|p| p.sku.as_str()).collect();
+        assert_eq!(skus, vec!["steel", "box", "glass"]);
+    }
+
+    #[test]
+    fn test_infeasible_plans_are_reported() {
+        let (network, nodes) = network();
+        let route = network.route(nodes[0], nodes[2]).unwrap();
+        let mut factory = factory();
+
+        // Стекло едет дальше, а сталь для первой остановки легла бы на него
+        let truck = Truck::new("Planner".to_string(), 10);
+        let drops = vec![
+            drop(nodes[2], &mut factory, &["glass"]),
+            drop(nodes[1], &mut factory, &["steel"]),
+        ];
+        assert_eq!(
+            plan_load(&truck, &route, drops),
+            Err("Heavy cargo would sit on fragile goods")
+        );
+
+        let small = Truck::new("Small".to_string(), 1);
+        assert_eq!(
+            plan_load(
+                &small,
+                &route,
+                vec![drop(nodes[1], &mut factory, &["box", "box"])]
+            ),
+            Err("Not enough capacity")
+        );
+
+        let light = Truck::new("Light".to_string(), 10).with_max_payload(30.0);
+        assert_eq!(
+            plan_load(
+                &light,
+                &route,
+                vec![drop(nodes[1], &mut factory, &["steel"])]
+            ),
+            Err("Payload limit exceeded")
+        );
+
+        assert_eq!(
+            plan_load(&truck, &route, vec![drop(nodes[3], &mut factory, &["box"])]),
+            Err("Stop is not on the route")
+        );
+    }
+
+    #[test]
+    fn test_failed_load_keeps_whole_sequence() {
+        let (network, nodes) = network();
+        let route = network.route(nodes[0], nodes[3]).unwrap();
+        let mut factory = factory();
+        let mut truck = Truck::new("Planner".to_string(), 10).with_location(nodes[0]);
+        let drops = vec![drop(nodes[1], &mut factory, &["box", "steel"])];
+        let mut plan = plan_load(&truck, &route, drops).unwrap();
+
+        // Машина сломалась между планированием и погрузкой — груз остаётся в плане
+        truck.reliability_mut().break_down();
+        assert_eq!(plan.load_into(&mut truck), Err("Truck is broken"));
+        assert_eq!(truck.loaded(), 0);
+        assert_eq!(plan.units(), 2);
+
+        let mut spare = Truck::new("Spare".to_string(), 1);
+        assert_eq!(plan.load_into(&mut spare), Err("Stack overflow"));
+        assert_eq!(spare.loaded(), 0);
+
+        let mut replacement = Truck::new("Replacement".to_string(), 10);
+        assert_eq!(plan.load_into(&mut replacement), Ok(2));
+    }
+}
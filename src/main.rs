@@ -1,44 +1,66 @@
-mod collections;
-mod models;
-
-use models::{Factory, Warehouse, Truck, Product};
+use factories_and_trucks::fleet::VehicleType;
+use factories_and_trucks::market::Market;
+use factories_and_trucks::models::Factory;
+use factories_and_trucks::network::{Location, RoadNetwork, SiteKind};
+use factories_and_trucks::random::Poisson;
+use factories_and_trucks::simulation::Simulation;
 
 fn main() {
-    let factory = Factory::new(String::from("first factory"));
-    let product = factory.create();
-    println!("factory produced {}", product.name);
-
-    let mut warehouse = Warehouse::new(5);
-    let _ = warehouse.load(product);
-    println!("{} moved to warehouse", warehouse.last_cargo().name);
-    println!("warehouse has {} cargo", warehouse.loaded());
-
-    let mut truck = Truck::new(String::from("small truck"), 3);
-
-    let product = match warehouse.unload(){
-        Some(prd) => prd,
-        None => panic!() // Нужно паниковать!
-    };
+    let mut network = RoadNetwork::new();
+    let plant = network.add_location(Location::new("plant", SiteKind::Factory, 0.0, 0.0));
+    let depot = network.add_location(Location::new("depot", SiteKind::Warehouse, 40.0, 30.0));
+    let city = network.add_location(Location::new("city", SiteKind::Market, 80.0, 30.0));
+    network.add_two_way_road(plant, depot, 50.0, 0.8).unwrap();
+    network.add_two_way_road(depot, city, 40.0, 0.6).unwrap();
 
-    println!("{} moved out from warehouse", product.name);
-    println!("warehouse has {} cargos", warehouse.loaded());
+    let mut sim = Simulation::new(network).with_seed(42);
+    sim.add_factory(
+        Factory::new(String::from("first factory"))
+            .with_output_capacity(8)
+            .with_location(plant)
+            .with_unit_cost(4.0),
+    );
+    sim.add_truck(VehicleType::Van.truck("small truck").with_location(plant));
 
-    let _ = truck.load(product);
+    let mut market = Market::new(String::from("city market")).with_location(city);
+    market.set_price("product", 12.0);
+    market.set_demand("product", Box::new(Poisson { mean: 0.8 }));
+    sim.add_market(market);
 
-    println!("{} moved to truck", truck.last_cargo().name);
-    println!("truck has {} cargo", truck.loaded());
+    sim.start_production(0);
+    sim.start_demand(0);
 
-    let mut market: Vec<Product> = Vec::new();
+    // Грузовик челноком возит выпуск завода на рынок каждые 3 часа
+    let mut hour = 0.0;
+    while hour < 24.0 {
+        hour += 3.0;
+        sim.run_until(hour);
+        let next = if sim.truck(0).location() == Some(plant) { city } else { plant };
+        if let Err(err) = sim.dispatch(0, next) {
+            println!("truck could not leave: {}", err);
+        }
+    }
+    sim.run_until(hour + 1.0);
 
-    let product = match truck.unload(){
-        Some(prd) => prd,
-        None => panic!() // Нужно паниковать!
-    };
+    let factory = sim.factory(0);
+    println!("after {} h factory has {} products in output buffer", sim.now(), factory.buffered());
+    println!("factory lost {} units of capacity while blocked", factory.lost_capacity());
+    println!("truck drove {} km", sim.truck(0).odometer_km());
 
-    println!("{} moved out from truck", product.name);
-    println!("truck has {} cargos", truck.loaded());
+    let market = sim.market(0);
+    println!("market sold {} products for {}", market.units_sold("product"), market.revenue());
+    println!("market lost {} sales, fill rate {:.2}", market.units_lost("product"), market.fill_rate());
 
-    market.push(product);
-    println!("{} moved in market", market.last().unwrap().name);
-    println!("market has {} products", market.len());
+    let report = sim.profit_and_loss();
+    println!(
+        "revenue {:.2}, production {:.2}, transport {:.2}, profit {:.2}",
+        report.revenue,
+        report.production,
+        report.transport,
+        report.profit()
+    );
+    if let Some(cost) = report.cost_per_unit_delivered() {
+        println!("cost per unit delivered {:.2}", cost);
+    }
+    println!("{} events processed", sim.log().len());
 }
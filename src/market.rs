@@ -0,0 +1,269 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::models::{Product, Truck};
+use crate::network::NodeId;
+use crate::random::{Distribution, Rng};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sale {
+    pub time: f64,
+    pub sku: String,
+    pub quantity: usize,
+    pub revenue: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LostSale {
+    pub time: f64,
+    pub sku: String,
+    pub quantity: usize,
+}
+
+pub struct Market {
+    name: String,
+    location: Option<NodeId>,
+    period_hours: f64,
+    prices: HashMap<String, f64>,
+    demand: Vec<(String, Box<dyn Distribution>)>,
+    stock: HashMap<String, VecDeque<Product>>,
+    sales: Vec<Sale>,
+    lost: Vec<LostSale>,
+    received: usize,
+    rng: Rng,
+}
+
+impl Market {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            location: None,
+            period_hours: 1.0,
+            prices: HashMap::new(),
+            demand: Vec::new(),
+            stock: HashMap::new(),
+            sales: Vec::new(),
+            lost: Vec::new(),
+            received: 0,
+            rng: Rng::new(0),
+        }
+    }
+
+    pub fn with_location(mut self, node: NodeId) -> Self {
+        self.location = Some(node);
+        self
+    }
+
+    pub fn with_period(mut self, hours: f64) -> Self {
+        self.period_hours = hours;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    pub fn set_rng(&mut self, rng: Rng) {
+        self.rng = rng;
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn location(&self) -> Option<NodeId> {
+        self.location
+    }
+
+    pub fn period(&self) -> f64 {
+        self.period_hours
+    }
+
+    pub fn set_price(&mut self, sku: &str, price: f64) {
+        self.prices.insert(sku.to_string(), price);
+    }
+
+    pub fn price(&self, sku: &str) -> f64 {
+        self.prices.get(sku).copied().unwrap_or(0.0)
+    }
+
+    // Спрос за один период, в штуках
+    pub fn set_demand(&mut self, sku: &str, distribution: Box<dyn Distribution>) {
+        match self.demand.iter_mut().find(|(s, _)| s == sku) {
+            Some(entry) => entry.1 = distribution,
+            None => self.demand.push((sku.to_string(), distribution)),
+        }
+    }
+
+    pub fn receive(&mut self, product: Product) {
+        self.received += 1;
+        self.stock
+            .entry(product.sku.clone())
+            .or_default()
+            .push_back(product);
+    }
+
+    pub fn deliver_from(&mut self, truck: &mut Truck) -> Result<usize, &'static str> {
+        let mut delivered = 0;
+        while truck.loaded() > 0 {
+            self.receive(truck.unload()?);
+            delivered += 1;
+        }
+        Ok(delivered)
+    }
+
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    pub fn stock(&self, sku: &str) -> usize {
+        self.stock.get(sku).map_or(0, |units| units.len())
+    }
+
+    pub fn sell(&mut self, sku: &str, quantity: usize, now: f64) -> usize {
+        let units = self.stock.entry(sku.to_string()).or_default();
+        let sold = quantity.min(units.len());
+        units.drain(..sold);
+        if sold > 0 {
+            self.sales.push(Sale {
+                time: now,
+                sku: sku.to_string(),
+                quantity: sold,
+                revenue: sold as f64 * self.price(sku),
+            });
+        }
+        if sold < quantity {
+            self.lost.push(LostSale {
+                time: now,
+                sku: sku.to_string(),
+                quantity: quantity - sold,
+            });
+        }
+        sold
+    }
+
+    pub fn generate_demand(&mut self, now: f64) -> usize {
+        let mut requested = Vec::with_capacity(self.demand.len());
+        for (sku, distribution) in &self.demand {
+            let quantity = distribution.sample(&mut self.rng).round().max(0.0) as usize;
+            requested.push((sku.clone(), quantity));
+        }
+        requested
+            .into_iter()
+            .filter(|(_, quantity)| *quantity > 0)
+            .map(|(sku, quantity)| self.sell(&sku, quantity, now))
+            .sum()
+    }
+
+    pub fn sales(&self) -> &[Sale] {
+        &self.sales
+    }
+
+    pub fn lost_sales(&self) -> &[LostSale] {
+        &self.lost
+    }
+
+    pub fn revenue(&self) -> f64 {
+        self.sales.iter().map(|sale| sale.revenue).sum()
+    }
+
+    pub fn units_sold(&self, sku: &str) -> usize {
+        self.sales
+            .iter()
+            .filter(|sale| sale.sku == sku)
+            .map(|sale| sale.quantity)
+            .sum()
+    }
+
+    pub fn units_lost(&self, sku: &str) -> usize {
+        self.lost
+            .iter()
+            .filter(|lost| lost.sku == sku)
+            .map(|lost| lost.quantity)
+            .sum()
+    }
+
+    pub fn fill_rate(&self) -> f64 {
+        let sold: usize = self.sales.iter().map(|sale| sale.quantity).sum();
+        let lost: usize = self.lost.iter().map(|lost| lost.quantity).sum();
+        if sold + lost == 0 {
+            return 1.0;
+        }
+        sold as f64 / (sold + lost) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Factory;
+    use crate::random::{Constant, Poisson};
+
+    fn stocked_market(units: usize) -> Market {
+        let mut factory = Factory::new("MarketFactory".to_string()).with_sku("soap");
+        let mut market = Market::new("Bazaar".to_string());
+        market.set_price("soap", 2.5);
+        for _ in 0..units {
+            market.receive(factory.create().unwrap());
+        }
+        market
+    }
+
+    #[test]
+    fn test_sell_records_sales_and_lost_sales() {
+        let mut market = stocked_market(3);
+
+        assert_eq!(market.sell("soap", 2, 1.0), 2);
+        assert_eq!(market.stock("soap"), 1);
+        assert_eq!(market.revenue(), 5.0);
+
+        // Спрос больше запаса: продаём остаток, остальное теряется
+        assert_eq!(market.sell("soap", 4, 2.0), 1);
+        assert_eq!(market.units_sold("soap"), 3);
+        assert_eq!(market.units_lost("soap"), 3);
+        assert_eq!(market.fill_rate(), 0.5);
+        assert_eq!(market.lost_sales()[0].time, 2.0);
+    }
+
+    #[test]
+    fn test_generate_demand() {
+        let mut market = stocked_market(5);
+        market.set_demand("soap", Box::new(Constant(2.0)));
+        market.set_demand("rope", Box::new(Constant(1.0)));
+
+        assert_eq!(market.generate_demand(1.0), 2);
+        assert_eq!(market.generate_demand(2.0), 2);
+        assert_eq!(market.generate_demand(3.0), 1);
+        assert_eq!(market.stock("soap"), 0);
+        assert_eq!(market.units_lost("soap"), 1);
+        assert_eq!(market.units_lost("rope"), 3);
+        assert_eq!(market.sales().len(), 3);
+    }
+
+    #[test]
+    fn test_demand_is_seeded() {
+        let run = |seed| {
+            let mut market = stocked_market(100).with_seed(seed);
+            market.set_demand("soap", Box::new(Poisson { mean: 3.0 }));
+            (0..20).map(|t| market.generate_demand(t as f64)).collect::<Vec<_>>()
+        };
+
+        assert_eq!(run(4), run(4));
+        assert_eq!(run(4)[..8], [4, 3, 1, 5, 3, 0, 4, 2]);
+        assert_ne!(run(4), run(5));
+    }
+
+    #[test]
+    fn test_deliver_from_truck() {
+        let mut factory = Factory::new("Supplier".to_string()).with_sku("soap");
+        let mut truck = Truck::new("Van".to_string(), 3);
+        truck.load(factory.create().unwrap()).unwrap();
+        truck.load(factory.create().unwrap()).unwrap();
+
+        let mut market = Market::new("Shop".to_string());
+        assert_eq!(market.deliver_from(&mut truck), Ok(2));
+        assert_eq!(truck.loaded(), 0);
+        assert_eq!(market.stock("soap"), 2);
+        assert_eq!(market.price("soap"), 0.0);
+    }
+}
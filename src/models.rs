@@ -1,28 +1,501 @@
+use std::collections::{HashMap, VecDeque};
+
 use collections::{LimitedQueue, LimitedStack};
+use energy::Energy;
+use fleet::{OperatingCost, VehicleType};
+use inventory::{Discrepancy, Inventory, StockLevel};
+use layout::{BinId, Layout, Stored};
+#[cfg(test)]
+use layout::{Bin, Zone, ZoneKind};
+use network::{NodeId, SiteKind};
+#[cfg(test)]
+use network::Location;
+use quality::{DefectAction, LotStats};
+use random::{Distribution, Rng};
+use reliability::{Condition, Reliability};
+#[cfg(test)]
+use strategies::{ClosestEmptyBin, Fefo, LeastTravel};
+use strategies::{CategoryZones, Fifo, HandlingStats, PickCandidate, PickingStrategy, PutAwayStrategy};
+use network::RoadNetwork;
+use packing::{CargoSpace, Packaging, PackingPlan};
+use routing::{Route, VehicleProfile};
+use trip::{Trip, TruckState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    General,
+    Chilled,
+    Hazardous,
+}
 
+#[derive(Debug, Clone, PartialEq)]
 pub struct Product {
     pub name: String,
+    pub sku: String,
+    pub origin: String,
+    pub lot: u32,
+    pub defective: bool,
+    pub order: Option<u64>,
+    pub category: Category,
+    pub expires_at: Option<f64>,
+    pub weight_kg: f64,
+    pub fragile: bool,
+    pub packaging: Packaging,
+    // Углеродный след, накопленный с производства, кг CO2e
+    pub co2e_kg: f64,
+    // Склад, под заказ пополнения которого выпущено изделие
+    pub destination: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backpressure {
+    Block,
+    Stop,
+    Spill,
 }
 
 pub struct Factory {
     name: String,
+    sku: String,
+    lot: u32,
+    defect_rates: HashMap<String, f64>,
+    categories: HashMap<String, Category>,
+    shelf_lives: HashMap<String, f64>,
+    weights: HashMap<String, f64>,
+    fragile: Vec<String>,
+    packaging: HashMap<String, Packaging>,
+    defect_action: DefectAction,
+    rng: Rng,
+    rework: LimitedQueue<Product>,
+    scrap: Vec<Product>,
+    lot_stats: HashMap<u32, LotStats>,
+    output: LimitedQueue<Product>,
+    overflow: Vec<Product>,
+    backpressure: Backpressure,
+    cycle_time: f64,
+    production_time: Option<Box<dyn Distribution>>,
+    stopped: bool,
+    blocked_hours: f64,
+    reliability: Reliability,
+    location: Option<NodeId>,
+    unit_cost: f64,
+    energy_kwh_per_unit: f64,
+    grid_kg_per_kwh: f64,
+    production_orders: VecDeque<(String, usize, Option<usize>)>,
+    make_to_order: bool,
 }
 
 impl Factory {
     pub fn new(name: String) -> Self {
-        Self { name }
+        Self {
+            name,
+            sku: String::from("product"),
+            lot: 1,
+            defect_rates: HashMap::new(),
+            categories: HashMap::new(),
+            shelf_lives: HashMap::new(),
+            weights: HashMap::new(),
+            fragile: Vec::new(),
+            packaging: HashMap::new(),
+            defect_action: DefectAction::Rework,
+            rng: Rng::new(0),
+            rework: LimitedQueue::new(16),
+            scrap: Vec::new(),
+            lot_stats: HashMap::new(),
+            output: LimitedQueue::new(16),
+            overflow: Vec::new(),
+            backpressure: Backpressure::Block,
+            cycle_time: 1.0,
+            production_time: None,
+            stopped: false,
+            blocked_hours: 0.0,
+            reliability: Reliability::never_fails(),
+            location: None,
+            unit_cost: 0.0,
+            energy_kwh_per_unit: 0.0,
+            grid_kg_per_kwh: 0.0,
+            production_orders: VecDeque::new(),
+            make_to_order: false,
+        }
+    }
+
+    // Производит только под заказы, без них линия простаивает
+    pub fn with_make_to_order(mut self) -> Self {
+        self.make_to_order = true;
+        self
+    }
+
+    pub fn order_production(&mut self, sku: &str, quantity: usize, destination: Option<usize>) {
+        if quantity > 0 {
+            self.production_orders
+                .push_back((sku.to_string(), quantity, destination));
+        }
+    }
+
+    pub fn production_backlog(&self) -> usize {
+        self.production_orders.iter().map(|(_, quantity, _)| quantity).sum()
+    }
+
+    pub fn with_energy_use(mut self, kwh_per_unit: f64, grid_kg_per_kwh: f64) -> Self {
+        self.energy_kwh_per_unit = kwh_per_unit;
+        self.grid_kg_per_kwh = grid_kg_per_kwh;
+        self
+    }
+
+    pub fn energy_kwh_per_unit(&self) -> f64 {
+        self.energy_kwh_per_unit
+    }
+
+    pub fn unit_emissions(&self) -> f64 {
+        self.energy_kwh_per_unit * self.grid_kg_per_kwh
+    }
+
+    pub fn with_unit_cost(mut self, cost: f64) -> Self {
+        self.unit_cost = cost;
+        self
+    }
+
+    pub fn unit_cost(&self) -> f64 {
+        self.unit_cost
+    }
+
+    pub fn sku(&self) -> &str {
+        &self.sku
+    }
+
+    pub fn with_location(mut self, node: NodeId) -> Self {
+        self.location = Some(node);
+        self
+    }
+
+    pub fn with_sku(mut self, sku: &str) -> Self {
+        self.sku = sku.to_string();
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    pub fn with_defect_action(mut self, action: DefectAction) -> Self {
+        self.defect_action = action;
+        self
+    }
+
+    pub fn with_rework_capacity(mut self, capacity: usize) -> Self {
+        self.rework = LimitedQueue::new(capacity);
+        self
+    }
+
+    pub fn with_output_capacity(mut self, capacity: usize) -> Self {
+        self.output = LimitedQueue::new(capacity);
+        self
+    }
+
+    pub fn with_backpressure(mut self, backpressure: Backpressure) -> Self {
+        self.backpressure = backpressure;
+        self
+    }
+
+    pub fn with_cycle_time(mut self, hours: f64) -> Self {
+        self.cycle_time = hours;
+        self
+    }
+
+    pub fn with_production_time(mut self, distribution: Box<dyn Distribution>) -> Self {
+        self.cycle_time = distribution.mean();
+        self.production_time = Some(distribution);
+        self
+    }
+
+    pub fn set_rng(&mut self, rng: Rng) {
+        self.rng = rng;
+    }
+
+    pub fn with_reliability(mut self, reliability: Reliability) -> Self {
+        self.reliability = reliability;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn location(&self) -> Option<NodeId> {
+        self.location
+    }
+
+    pub fn reliability(&self) -> &Reliability {
+        &self.reliability
+    }
+
+    pub fn reliability_mut(&mut self) -> &mut Reliability {
+        &mut self.reliability
     }
-    pub fn create(&self) -> Product {
-        Product {
+
+    pub fn advance(&mut self, hours: f64) {
+        self.reliability.advance(hours);
+    }
+
+    pub fn set_defect_rate(&mut self, sku: &str, probability: f64) {
+        self.defect_rates
+            .insert(sku.to_string(), probability.clamp(0.0, 1.0));
+    }
+
+    pub fn set_category(&mut self, sku: &str, category: Category) {
+        self.categories.insert(sku.to_string(), category);
+    }
+
+    // Срок годности в часах с момента выпуска
+    pub fn set_shelf_life(&mut self, sku: &str, hours: f64) {
+        self.shelf_lives.insert(sku.to_string(), hours);
+    }
+
+    pub fn set_weight(&mut self, sku: &str, kg: f64) {
+        self.weights.insert(sku.to_string(), kg);
+    }
+
+    pub fn set_fragile(&mut self, sku: &str, fragile: bool) {
+        self.fragile.retain(|known| known != sku);
+        if fragile {
+            self.fragile.push(sku.to_string());
+        }
+    }
+
+    pub fn set_packaging(&mut self, sku: &str, packaging: Packaging) {
+        self.packaging.insert(sku.to_string(), packaging);
+    }
+
+    pub fn defect_rate(&self, sku: &str) -> f64 {
+        self.defect_rates.get(sku).copied().unwrap_or(0.0)
+    }
+
+    pub fn lot(&self) -> u32 {
+        self.lot
+    }
+
+    pub fn next_lot(&mut self) -> u32 {
+        self.lot += 1;
+        self.lot
+    }
+
+    pub fn create(&mut self) -> Result<Product, &'static str> {
+        let sku = self.sku.clone();
+        self.create_sku(&sku)
+    }
+
+    pub fn create_sku(&mut self, sku: &str) -> Result<Product, &'static str> {
+        match self.reliability.condition() {
+            Condition::Operational => {}
+            Condition::Broken => return Err("Factory is broken"),
+            Condition::UnderMaintenance => return Err("Factory is under maintenance"),
+        }
+        let defective = self.rng.chance(self.defect_rate(sku));
+        self.lot_stats.entry(self.lot).or_default().produced += 1;
+        Ok(Product {
             name: format!("product from {}", self.name),
+            sku: sku.to_string(),
+            origin: self.name.clone(),
+            lot: self.lot,
+            defective,
+            order: None,
+            category: self.categories.get(sku).copied().unwrap_or(Category::General),
+            expires_at: self
+                .shelf_lives
+                .get(sku)
+                .map(|hours| self.reliability.clock() + hours),
+            weight_kg: self.weights.get(sku).copied().unwrap_or(1.0),
+            fragile: self.fragile.iter().any(|known| known == sku),
+            packaging: self.packaging.get(sku).copied().unwrap_or_default(),
+            co2e_kg: self.unit_emissions(),
+            destination: None,
+        })
+    }
+
+    pub fn inspect(&mut self, product: Product) -> Option<Product> {
+        let stats = self.lot_stats.entry(product.lot).or_default();
+        if !product.defective {
+            stats.passed += 1;
+            return Some(product);
+        }
+        if self.defect_action == DefectAction::Rework && self.rework.len() < self.rework.max_size {
+            stats.reworked += 1;
+            let _ = self.rework.enqueue(product);
+        } else {
+            stats.scrapped += 1;
+            self.scrap.push(product);
+        }
+        None
+    }
+
+    pub fn produce(&mut self) -> Result<Option<Product>, &'static str> {
+        let sku = self.sku.clone();
+        self.produce_sku(&sku)
+    }
+
+    fn produce_sku(&mut self, sku: &str) -> Result<Option<Product>, &'static str> {
+        let product = self.create_sku(sku)?;
+        Ok(self.inspect(product))
+    }
+
+    // Заказ закрывается только годными изделиями, брак перевыпускается
+    fn next_sku(&self) -> Option<String> {
+        match self.production_orders.front() {
+            Some((sku, _, _)) => Some(sku.clone()),
+            None if self.make_to_order => None,
+            None => Some(self.sku.clone()),
         }
     }
+
+    fn fulfil_production_order(&mut self) {
+        if let Some((_, quantity, _)) = self.production_orders.front_mut() {
+            *quantity -= 1;
+            if *quantity == 0 {
+                self.production_orders.pop_front();
+            }
+        }
+    }
+
+    pub fn rework_next(&mut self) -> Option<Product> {
+        let mut product = self.rework.dequeue()?;
+        product.defective = false;
+        Some(product)
+    }
+
+    pub fn rework_queue(&self) -> usize {
+        self.rework.len()
+    }
+
+    pub fn scrapped(&self) -> usize {
+        self.scrap.len()
+    }
+
+    pub fn lot_stats(&self, lot: u32) -> Option<&LotStats> {
+        self.lot_stats.get(&lot)
+    }
+
+    pub fn produced(&self) -> usize {
+        self.lot_stats.values().map(|stats| stats.produced).sum()
+    }
+
+    pub fn step(&mut self) -> Result<(), &'static str> {
+        if self.stopped {
+            self.blocked_hours += self.cycle_time;
+            return Err("Factory is stopped");
+        }
+        let full = self.output.len() >= self.output.max_size;
+        if full && self.backpressure != Backpressure::Spill {
+            self.blocked_hours += self.cycle_time;
+            if self.backpressure == Backpressure::Stop {
+                self.stopped = true;
+            }
+            return Err("Output buffer full");
+        }
+        let sku = self.next_sku().ok_or("No production orders")?;
+        if let Some(mut product) = self.produce_sku(&sku)? {
+            product.destination = self
+                .production_orders
+                .front()
+                .and_then(|(_, _, destination)| *destination);
+            self.fulfil_production_order();
+            if full {
+                self.overflow.push(product);
+            } else {
+                self.output.enqueue(product)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn resume(&mut self) {
+        self.stopped = false;
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    pub fn peek_output(&self) -> Option<&Product> {
+        self.output.front()
+    }
+
+    pub fn take_output(&mut self) -> Option<Product> {
+        self.take_output_where(|_| true)
+    }
+
+    // Первая подходящая единица выпуска, остальные сохраняют свой порядок
+    fn take_output_where<F: Fn(&Product) -> bool>(&mut self, predicate: F) -> Option<Product> {
+        let product = self.output.remove_first(predicate)?;
+        if !self.overflow.is_empty() {
+            let _ = self.output.enqueue(self.overflow.remove(0));
+        }
+        Some(product)
+    }
+
+    // Напрямую склад получает только своё: выпуск под его заказ или на склад вообще
+    pub fn ship_to(&mut self, warehouse: &mut Warehouse, index: usize) -> usize {
+        let mut shipped = 0;
+        while let Some(product) = self.take_output_where(|product| {
+            product.destination.is_none_or(|d| d == index)
+                && warehouse.can_accept(product)
+        }) {
+            if product.destination.is_some() {
+                warehouse.receive_ordered(product).expect("warehouse has room");
+            } else {
+                warehouse.load(product).expect("warehouse has room");
+            }
+            shipped += 1;
+        }
+        shipped
+    }
+
+    pub fn buffered(&self) -> usize {
+        self.output.len()
+    }
+
+    pub fn overflowed(&self) -> usize {
+        self.overflow.len()
+    }
+
+    pub fn cycle_time(&self) -> f64 {
+        self.cycle_time
+    }
+
+    pub fn next_cycle_time(&mut self) -> f64 {
+        match &self.production_time {
+            Some(distribution) => distribution.sample(&mut self.rng).max(0.0),
+            None => self.cycle_time,
+        }
+    }
+
+    pub fn blocked_hours(&self) -> f64 {
+        self.blocked_hours
+    }
+
+    pub fn lost_capacity(&self) -> f64 {
+        self.blocked_hours / self.cycle_time
+    }
 }
 
 pub struct Truck {
     name: String,
     capacity: usize,
     cargo: LimitedStack<Product>,
+    reliability: Reliability,
+    location: Option<NodeId>,
+    profile: VehicleProfile,
+    state: TruckState,
+    docked: bool,
+    trip: Option<Trip>,
+    refuelling_left: f64,
+    odometer_km: f64,
+    max_payload_kg: f64,
+    cargo_space: Option<CargoSpace>,
+    packing_plan: Option<PackingPlan>,
+    energy: Option<Energy>,
+    cost: OperatingCost,
+    kind: Option<VehicleType>,
 }
 
 impl Truck {
@@ -31,15 +504,324 @@ impl Truck {
             name,
             capacity,
             cargo: LimitedStack::new(capacity),
+            reliability: Reliability::never_fails(),
+            location: None,
+            profile: VehicleProfile::default(),
+            state: TruckState::Idle,
+            docked: true,
+            trip: None,
+            refuelling_left: 0.0,
+            odometer_km: 0.0,
+            max_payload_kg: f64::INFINITY,
+            cargo_space: None,
+            packing_plan: None,
+            energy: None,
+            cost: OperatingCost::new(0.0, 0.0, 0.0),
+            kind: None,
+        }
+    }
+
+    pub fn with_kind(mut self, kind: VehicleType) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    pub fn kind(&self) -> Option<VehicleType> {
+        self.kind
+    }
+
+    pub fn with_cost(mut self, cost: OperatingCost) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    pub fn cost(&self) -> &OperatingCost {
+        &self.cost
+    }
+
+    pub fn with_energy(mut self, energy: Energy) -> Self {
+        self.energy = Some(energy);
+        self
+    }
+
+    pub fn energy(&self) -> Option<&Energy> {
+        self.energy.as_ref()
+    }
+
+    pub fn with_cargo_space(mut self, space: CargoSpace) -> Self {
+        self.cargo_space = Some(space);
+        self
+    }
+
+    pub fn cargo_space(&self) -> Option<&CargoSpace> {
+        self.cargo_space.as_ref()
+    }
+
+    pub fn packing_plan(&self) -> Option<&PackingPlan> {
+        self.packing_plan.as_ref()
+    }
+
+    // С планом каждая погрузка должна идти в порядке раскладки
+    pub fn apply_plan(&mut self, plan: PackingPlan) -> Result<(), &'static str> {
+        if !self.cargo.is_empty() {
+            return Err("Truck must be empty to apply a packing plan");
+        }
+        let space = self.cargo_space.as_ref().ok_or("Truck has no cargo space")?;
+        plan.check_fits(space)?;
+        if plan.weight_kg() > self.max_payload_kg {
+            return Err("Payload limit exceeded");
+        }
+        self.packing_plan = Some(plan);
+        Ok(())
+    }
+
+    pub fn with_max_payload(mut self, kg: f64) -> Self {
+        self.max_payload_kg = kg;
+        self
+    }
+
+    pub fn max_payload_kg(&self) -> f64 {
+        self.max_payload_kg
+    }
+
+    pub fn payload_kg(&self) -> f64 {
+        self.cargo.iter().map(|product| product.weight_kg).sum()
+    }
+
+    pub fn with_location(mut self, node: NodeId) -> Self {
+        self.location = Some(node);
+        self
+    }
+
+    pub fn with_profile(mut self, profile: VehicleProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    pub fn location(&self) -> Option<NodeId> {
+        self.location
+    }
+
+    pub fn profile(&self) -> &VehicleProfile {
+        &self.profile
+    }
+
+    pub fn with_reliability(mut self, reliability: Reliability) -> Self {
+        self.reliability = reliability;
+        self
+    }
+
+    fn ensure_operational(&self) -> Result<(), &'static str> {
+        match self.reliability.condition() {
+            Condition::Operational => Ok(()),
+            Condition::Broken => Err("Truck is broken"),
+            Condition::UnderMaintenance => Err("Truck is under maintenance"),
+        }
+    }
+
+    fn ensure_docked(&self) -> Result<(), &'static str> {
+        self.ensure_operational()?;
+        if !self.docked {
+            return Err("Truck is not docked");
         }
+        Ok(())
     }
 
     pub fn load(&mut self, product: Product) -> Result<(), &'static str> {
-        self.cargo.push(product)
+        self.check_load(&product)?;
+        self.cargo.push(product)?;
+        self.state = TruckState::Loading;
+        Ok(())
     }
 
-    pub fn unload(&mut self) -> Option<Product> {
-        self.cargo.pop()
+    // Проверка до погрузки: при отказе товар остаётся у отправителя
+    pub fn check_load(&self, product: &Product) -> Result<(), &'static str> {
+        self.check_load_all(std::slice::from_ref(product))
+    }
+
+    // Проверка всей партии в порядке погрузки, ничего не грузя
+    pub fn check_load_all(&self, products: &[Product]) -> Result<(), &'static str> {
+        self.ensure_docked()?;
+        if self.cargo.len() + products.len() > self.capacity {
+            return Err("Stack overflow");
+        }
+        let weight: f64 = products.iter().map(|product| product.weight_kg).sum();
+        if self.payload_kg() + weight > self.max_payload_kg {
+            return Err("Payload limit exceeded");
+        }
+        if let Some(plan) = &self.packing_plan {
+            for (i, product) in products.iter().enumerate() {
+                let placement = plan
+                    .placements
+                    .get(self.cargo.len() + i)
+                    .ok_or("Load is not in the packing plan")?;
+                if placement.sku != product.sku {
+                    return Err("Load does not follow the packing plan");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn unload(&mut self) -> Result<Product, &'static str> {
+        self.ensure_docked()?;
+        let product = self.cargo.pop().ok_or("Truck is empty")?;
+        self.state = TruckState::Unloading;
+        if self.cargo.is_empty() {
+            self.packing_plan = None;
+        }
+        Ok(product)
+    }
+
+    pub fn state(&self) -> TruckState {
+        self.state
+    }
+
+    pub fn is_docked(&self) -> bool {
+        self.docked
+    }
+
+    pub fn trip(&self) -> Option<&Trip> {
+        self.trip.as_ref()
+    }
+
+    pub fn odometer_km(&self) -> f64 {
+        self.odometer_km
+    }
+
+    fn ensure_stationary(&self) -> Result<(), &'static str> {
+        match self.state {
+            TruckState::EnRoute => Err("Truck is en route"),
+            TruckState::Refuelling => Err("Truck is refuelling"),
+            TruckState::Stranded => Err("Truck has run dry"),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn dock(&mut self, network: &RoadNetwork) -> Result<(), &'static str> {
+        self.ensure_stationary()?;
+        let node = self.location.ok_or("Truck has no location")?;
+        match network.location(node) {
+            Some(location) if location.kind.is_facility() => {
+                self.docked = true;
+                self.state = TruckState::Idle;
+                Ok(())
+            }
+            Some(_) => Err("No facility to dock at"),
+            None => Err("Unknown location"),
+        }
+    }
+
+    pub fn undock(&mut self) {
+        self.docked = false;
+        if !matches!(
+            self.state,
+            TruckState::EnRoute | TruckState::Refuelling | TruckState::Stranded
+        ) {
+            self.state = TruckState::Idle;
+        }
+    }
+
+    pub fn dispatch(&mut self, route: Route) -> Result<(), &'static str> {
+        self.ensure_operational()?;
+        self.ensure_stationary()?;
+        if self.location.is_some_and(|node| node != route.origin()) {
+            return Err("Route does not start at truck location");
+        }
+        self.location = Some(route.origin());
+        self.docked = false;
+        let trip = Trip::new(route);
+        if trip.is_finished() {
+            self.state = TruckState::Idle;
+        } else {
+            self.state = TruckState::EnRoute;
+            self.trip = Some(trip);
+        }
+        Ok(())
+    }
+
+    pub fn begin_refuelling(&mut self, hours: f64) -> Result<(), &'static str> {
+        self.ensure_stationary()?;
+        self.state = TruckState::Refuelling;
+        self.refuelling_left = hours;
+        Ok(())
+    }
+
+    // Заправка или зарядка до полного на станции
+    pub fn refuel(&mut self, network: &RoadNetwork) -> Result<f64, &'static str> {
+        let node = self.location.ok_or("Truck has no location")?;
+        if network.location(node).map(|location| location.kind) != Some(SiteKind::Station) {
+            return Err("Truck is not at a station");
+        }
+        let hours = self.energy.as_ref().map_or(0.0, |energy| energy.refill_hours(1.0));
+        self.begin_refuelling(hours)?;
+        Ok(hours)
+    }
+
+    // Топливо, подвезённое вставшей на дороге машине
+    pub fn top_up(&mut self, amount: f64) {
+        if let Some(energy) = self.energy.as_mut() {
+            energy.top_up(amount);
+            if self.state == TruckState::Stranded && !energy.is_empty() {
+                self.state = TruckState::EnRoute;
+            }
+        }
+    }
+
+    pub fn reliability(&self) -> &Reliability {
+        &self.reliability
+    }
+
+    pub fn reliability_mut(&mut self) -> &mut Reliability {
+        &mut self.reliability
+    }
+
+    pub fn advance(&mut self, hours: f64) {
+        // Машина движется только в исправные часы этого интервала
+        let before = self.reliability.stats().operational_hours;
+        self.reliability.advance(hours);
+        let hours = self.reliability.stats().operational_hours - before;
+        if hours <= 0.0 {
+            return;
+        }
+        match self.state {
+            TruckState::EnRoute => {
+                let payload = self.payload_kg();
+                if let Some(trip) = self.trip.as_mut() {
+                    // Машина едет, пока хватает топлива на текущий груз
+                    let range = self
+                        .energy
+                        .as_ref()
+                        .map_or(f64::INFINITY, |energy| energy.range_km(payload));
+                    let until_dry = trip.hours_for_km(range);
+                    let (reached, km) = trip.advance(hours.min(until_dry));
+                    self.odometer_km += km;
+                    if let Some(energy) = self.energy.as_mut() {
+                        let _ = energy.consume(km.min(range), payload);
+                    }
+                    if reached.is_some() {
+                        self.location = reached;
+                    }
+                    if trip.is_finished() {
+                        self.trip = None;
+                        self.state = TruckState::Idle;
+                    } else if until_dry < hours {
+                        self.state = TruckState::Stranded;
+                    }
+                }
+            }
+            TruckState::Refuelling => {
+                if let Some(energy) = self.energy.as_mut() {
+                    energy.refill(hours.min(self.refuelling_left));
+                }
+                self.refuelling_left -= hours;
+                if self.refuelling_left <= 0.0 {
+                    self.refuelling_left = 0.0;
+                    self.state = TruckState::Idle;
+                }
+            }
+            _ => {}
+        }
     }
 
     pub fn loaded(&self) -> usize {
@@ -52,39 +834,297 @@ impl Truck {
             None => panic!() // Нужно понять, как паниковать
         }
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn cargo(&self) -> impl Iterator<Item = &Product> {
+        self.cargo.iter()
+    }
+
+    pub(crate) fn cargo_mut(&mut self) -> impl Iterator<Item = &mut Product> {
+        self.cargo.iter_mut()
+    }
 }
 
 pub struct Warehouse {
     capacity: usize,
-    cargo: LimitedQueue<Product>,
+    layout: Layout,
+    received: u64,
+    location: Option<NodeId>,
+    inventory: Inventory,
+    put_away: Box<dyn PutAwayStrategy>,
+    picking: Box<dyn PickingStrategy>,
+    handling: HandlingStats,
+    storage_cost: f64,
 }
 
 impl Warehouse {
     pub fn new(capacity: usize) -> Self {
+        Self::from_layout(Layout::single_bin(capacity))
+    }
+
+    pub fn from_layout(layout: Layout) -> Self {
         Self {
-            capacity,
-            cargo: LimitedQueue::new(capacity),
+            capacity: layout.capacity(),
+            layout,
+            received: 0,
+            location: None,
+            inventory: Inventory::new(),
+            put_away: Box::new(CategoryZones),
+            picking: Box::new(Fifo),
+            handling: HandlingStats::default(),
+            storage_cost: 0.0,
         }
     }
 
-    pub fn load(&mut self, product: Product) -> Result<(), &'static str> {
-        self.cargo.enqueue(product)
+    pub fn with_storage_cost(mut self, per_unit_day: f64) -> Self {
+        self.storage_cost = per_unit_day;
+        self
+    }
+
+    pub fn storage_cost_per_unit_day(&self) -> f64 {
+        self.storage_cost
+    }
+
+    pub fn with_location(mut self, node: NodeId) -> Self {
+        self.location = Some(node);
+        self
+    }
+
+    pub fn with_put_away(mut self, strategy: Box<dyn PutAwayStrategy>) -> Self {
+        self.put_away = strategy;
+        self
+    }
+
+    pub fn with_picking(mut self, strategy: Box<dyn PickingStrategy>) -> Self {
+        self.picking = strategy;
+        self
+    }
+
+    pub fn location(&self) -> Option<NodeId> {
+        self.location
+    }
+
+    pub fn layout(&self) -> &Layout {
+        &self.layout
+    }
+
+    pub fn put_away_strategy(&self) -> &str {
+        self.put_away.name()
+    }
+
+    pub fn picking_strategy(&self) -> &str {
+        self.picking.name()
+    }
+
+    pub fn handling(&self) -> &HandlingStats {
+        &self.handling
+    }
+
+    pub fn can_accept(&self, product: &Product) -> bool {
+        self.put_away.choose_bin(&self.layout, product).is_ok()
+    }
+
+    // Поместится ли вся партия: раскладываем её по свободным местам ячеек хранения
+    pub fn has_room_for(&self, products: &[Product]) -> bool {
+        let mut free: Vec<_> = self
+            .layout
+            .bins()
+            .filter(|(id, _)| self.layout.zone_kind(*id).is_some_and(|kind| kind.is_storage()))
+            .map(|(_, bin)| (bin, bin.capacity() - bin.len()))
+            .collect();
+        products.iter().all(|product| {
+            free.iter_mut()
+                .find(|(bin, room)| *room > 0 && bin.allows(product.category))
+                .map(|(_, room)| *room -= 1)
+                .is_some()
+        })
+    }
+
+    pub fn load(&mut self, product: Product) -> Result<BinId, &'static str> {
+        let sku = product.sku.clone();
+        let bin = self.store(product)?;
+        self.inventory.receive(&sku);
+        Ok(bin)
+    }
+
+    // Приход по ожидаемой поставке
+    pub fn receive(&mut self, product: Product) -> Result<BinId, &'static str> {
+        let sku = product.sku.clone();
+        let bin = self.store(product)?;
+        self.inventory.receive_expected(&sku);
+        Ok(bin)
+    }
+
+    // Приход прямо с завода по заказу, минуя путь
+    pub fn receive_ordered(&mut self, product: Product) -> Result<BinId, &'static str> {
+        let sku = product.sku.clone();
+        let bin = self.store(product)?;
+        self.inventory.receive_ordered(&sku);
+        Ok(bin)
+    }
+
+    fn store(&mut self, product: Product) -> Result<BinId, &'static str> {
+        let bin = self.put_away.choose_bin(&self.layout, &product)?;
+        self.received += 1;
+        let stored = Stored {
+            received: self.received,
+            product,
+        };
+        self.layout
+            .bin_mut(bin)
+            .ok_or("Unknown bin")?
+            .store(stored)?;
+        self.handling.record(self.bin_distance(bin), false);
+        Ok(bin)
+    }
+
+    fn bin_distance(&self, bin: BinId) -> f64 {
+        self.layout.bin(bin).map_or(0.0, |bin| bin.distance_m())
+    }
+
+    // Какую единицу забрать, решает стратегия отбора
+    fn choose_pick<F: Fn(BinId, &Product) -> bool>(&self, predicate: F) -> Option<(BinId, u64)> {
+        let candidates: Vec<PickCandidate> = self
+            .layout
+            .stored()
+            .filter(|(bin, stored)| predicate(*bin, &stored.product))
+            .map(|(bin, stored)| PickCandidate {
+                bin,
+                received: stored.received,
+                expires_at: stored.product.expires_at,
+                distance_m: self.bin_distance(bin),
+            })
+            .collect();
+        let chosen = self.picking.choose(&candidates)?;
+        Some((chosen.bin, chosen.received))
+    }
+
+    fn take(&mut self, bin: BinId, received: u64) -> Option<Product> {
+        let stored = self.layout.bin_mut(bin)?.take(received)?;
+        self.handling.record(self.bin_distance(bin), true);
+        Some(stored.product)
     }
 
+    // Забирает товар, который не зарезервирован под заказы
     pub fn unload(&mut self) -> Option<Product> {
-        self.cargo.dequeue()
+        let inventory = &self.inventory;
+        let (bin, received) = self.choose_pick(|_, product| inventory.level(&product.sku).available() > 0)?;
+        let product = self.take(bin, received)?;
+        self.inventory.issue(&product.sku);
+        Some(product)
+    }
+
+    pub fn unload_sku(&mut self, sku: &str) -> Option<Product> {
+        if self.inventory.level(sku).available() == 0 {
+            return None;
+        }
+        let (bin, received) = self.choose_pick(|_, product| product.sku == sku)?;
+        let product = self.take(bin, received)?;
+        self.inventory.issue(sku);
+        Some(product)
+    }
+
+    pub fn unload_reserved(&mut self, sku: &str) -> Result<Product, &'static str> {
+        if self.inventory.level(sku).reserved == 0 {
+            return Err("Nothing reserved");
+        }
+        let (bin, received) = self
+            .choose_pick(|_, product| product.sku == sku)
+            .ok_or("Reserved stock is missing")?;
+        let product = self.take(bin, received).ok_or("Reserved stock is missing")?;
+        self.inventory.issue_reserved(sku)?;
+        Ok(product)
+    }
+
+    pub fn unload_reserved_from(&mut self, bin: BinId, sku: &str) -> Result<Product, &'static str> {
+        if self.inventory.level(sku).reserved == 0 {
+            return Err("Nothing reserved");
+        }
+        let (bin, received) = self
+            .choose_pick(|at, product| at == bin && product.sku == sku)
+            .ok_or("Bin is short")?;
+        let product = self.take(bin, received).ok_or("Bin is short")?;
+        self.inventory.issue_reserved(sku)?;
+        Ok(product)
+    }
+
+    pub fn locate(&self, sku: &str) -> Vec<(BinId, usize)> {
+        self.layout.locate(sku)
+    }
+
+    pub fn count_sku(&self, sku: &str) -> usize {
+        self.cargo().filter(|product| product.sku == sku).count()
+    }
+
+    pub fn stock(&self, sku: &str) -> StockLevel {
+        self.inventory.level(sku)
+    }
+
+    pub fn skus(&self) -> impl Iterator<Item = &str> {
+        self.inventory.skus()
+    }
+
+    pub fn reserve(&mut self, sku: &str, quantity: usize) -> Result<(), &'static str> {
+        self.inventory.reserve(sku, quantity)
+    }
+
+    pub fn release(&mut self, sku: &str, quantity: usize) -> Result<(), &'static str> {
+        self.inventory.release(sku, quantity)
+    }
+
+    pub fn order(&mut self, sku: &str, quantity: usize) {
+        self.inventory.order(sku, quantity);
+    }
+
+    pub fn cancel_order(&mut self, sku: &str, quantity: usize) {
+        self.inventory.cancel_order(sku, quantity);
+    }
+
+    pub fn expect(&mut self, sku: &str, quantity: usize) {
+        self.inventory.expect(sku, quantity);
+    }
+
+    pub fn cancel_expected(&mut self, sku: &str, quantity: usize) {
+        self.inventory.cancel_expected(sku, quantity);
+    }
+
+    pub fn reconcile(&self) -> Vec<Discrepancy> {
+        self.inventory
+            .reconcile(self.cargo().map(|product| product.sku.as_str()))
     }
 
     pub fn loaded(&self) -> usize {
-        self.cargo.len()
+        self.layout.len()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.loaded() >= self.capacity
     }
 
     pub fn last_cargo(&self) -> &Product {
-        match self.cargo.peek() {
-            Some(prd) => prd,
+        match self.layout.stored().max_by_key(|(_, stored)| stored.received) {
+            Some((_, stored)) => &stored.product,
             None => panic!() // Нужно понять, как паниковать
         }
     }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // Товары в порядке приёмки
+    pub fn cargo(&self) -> impl Iterator<Item = &Product> {
+        let mut stored: Vec<&Stored> = self.layout.stored().map(|(_, stored)| stored).collect();
+        stored.sort_by_key(|stored| stored.received);
+        stored.into_iter().map(|stored| &stored.product)
+    }
 }
 
 #[cfg(test)]
@@ -93,11 +1133,9 @@ use super::*;
 
 #[test]
 fn test_factory_create() {
-    let factory = Factory {
-        name: "TestFactory".to_string(),
-    };
+    let mut factory = Factory::new("TestFactory".to_string());
 
-    let product = factory.create();
+    let product = factory.create().unwrap();
     assert_eq!(product.name, "product from TestFactory");
 }
 
@@ -113,12 +1151,10 @@ fn test_truck_new() {
 #[test]
 fn test_truck_load_and_unload() {
     let mut truck = Truck::new("Truck1".to_string(), 3);
-    let factory = Factory {
-        name: "Factory1".to_string(),
-    };
+    let mut factory = Factory::new("Factory1".to_string());
 
-    let product1 = factory.create();
-    let product2 = factory.create();
+    let product1 = factory.create().unwrap();
+    let product2 = factory.create().unwrap();
 
     // Загрузка продуктов
     assert!(truck.load(product1).is_ok());
@@ -138,12 +1174,10 @@ fn test_truck_load_and_unload() {
 #[test]
 fn test_truck_load_overflow() {
     let mut truck = Truck::new("SmallTruck".to_string(), 1);
-    let factory = Factory {
-        name: "Factory1".to_string(),
-    };
+    let mut factory = Factory::new("Factory1".to_string());
 
-    let product1 = factory.create();
-    let product2 = factory.create();
+    let product1 = factory.create().unwrap();
+    let product2 = factory.create().unwrap();
 
     assert!(truck.load(product1).is_ok());
 
@@ -156,7 +1190,7 @@ fn test_truck_load_overflow() {
 fn test_truck_unload_empty() {
     let mut truck = Truck::new("EmptyTruck".to_string(), 5);
 
-    assert!(truck.unload().is_none());
+    assert_eq!(truck.unload().err(), Some("Truck is empty"));
 }
 
 #[test]
@@ -164,32 +1198,30 @@ fn test_warehouse_new() {
     let warehouse = Warehouse::new(20);
 
     assert_eq!(warehouse.capacity, 20);
-    assert!(warehouse.cargo.is_empty());
+    assert!(warehouse.cargo().next().is_none());
 }
 
 #[test]
 fn test_warehouse_operations() {
     let mut warehouse = Warehouse::new(3);
-    let factory = Factory {
-        name: "MainFactory".to_string(),
-    };
+    let mut factory = Factory::new("MainFactory".to_string());
 
-    let product1 = factory.create();
-    let product2 = factory.create();
-    let product3 = factory.create();
+    let product1 = factory.create().unwrap();
+    let product2 = factory.create().unwrap();
+    let product3 = factory.create().unwrap();
 
     // Добавление продуктов на склад
-    assert!(warehouse.cargo.enqueue(product1).is_ok());
-    assert!(warehouse.cargo.enqueue(product2).is_ok());
-    assert!(warehouse.cargo.enqueue(product3).is_ok());
+    assert!(warehouse.load(product1).is_ok());
+    assert!(warehouse.load(product2).is_ok());
+    assert!(warehouse.load(product3).is_ok());
     assert_eq!(warehouse.loaded(), 3);
 
     // Извлечение (FIFO для очереди)
-    let dequeued_product1 = warehouse.cargo.dequeue().unwrap();
+    let dequeued_product1 = warehouse.unload().unwrap();
     assert_eq!(dequeued_product1.name, "product from MainFactory");
     assert_eq!(warehouse.loaded(), 2);
 
-    let dequeued_product2 = warehouse.cargo.dequeue().unwrap();
+    let dequeued_product2 = warehouse.unload().unwrap();
     assert_eq!(dequeued_product2.name, "product from MainFactory");
     assert_eq!(warehouse.loaded(), 1);
 }
@@ -197,16 +1229,14 @@ fn test_warehouse_operations() {
 #[test]
 fn test_warehouse_overflow() {
     let mut warehouse = Warehouse::new(1);
-    let factory = Factory {
-        name: "SmallFactory".to_string(),
-    };
+    let mut factory = Factory::new("SmallFactory".to_string());
 
-    let product1 = factory.create();
-    let product2 = factory.create();
+    let product1 = factory.create().unwrap();
+    let product2 = factory.create().unwrap();
 
-    assert!(warehouse.cargo.enqueue(product1).is_ok());
+    assert!(warehouse.load(product1).is_ok());
 
-    let result = warehouse.cargo.enqueue(product2);
+    let result = warehouse.load(product2);
     assert!(result.is_err());
     assert_eq!(result.err(), Some("Queue overflow"));
 }
@@ -215,23 +1245,21 @@ fn test_warehouse_overflow() {
 fn test_truck_to_warehouse_transfer() {
     let mut truck = Truck::new("DeliveryTruck".to_string(), 2);
     let mut warehouse = Warehouse::new(2);
-    let factory = Factory {
-        name: "DeliveryFactory".to_string(),
-    };
+    let mut factory = Factory::new("DeliveryFactory".to_string());
 
     // Загружаем товар в грузовик
-    let product1 = factory.create();
-    let product2 = factory.create();
+    let product1 = factory.create().unwrap();
+    let product2 = factory.create().unwrap();
 
     assert!(truck.load(product1).is_ok());
     assert!(truck.load(product2).is_ok());
 
     // Разгружаем из грузовика на склад
     let product_from_truck = truck.unload().unwrap();
-    assert!(warehouse.cargo.enqueue(product_from_truck).is_ok());
+    assert!(warehouse.load(product_from_truck).is_ok());
 
     let product_from_truck = truck.unload().unwrap();
-    assert!(warehouse.cargo.enqueue(product_from_truck).is_ok());
+    assert!(warehouse.load(product_from_truck).is_ok());
 
     assert!(truck.cargo.is_empty());
     assert_eq!(warehouse.loaded(), 2);
@@ -239,15 +1267,11 @@ fn test_truck_to_warehouse_transfer() {
 
 #[test]
 fn test_product_creation_different_factories() {
-    let factory1 = Factory {
-        name: "FactoryA".to_string(),
-    };
-    let factory2 = Factory {
-        name: "FactoryB".to_string(),
-    };
+    let mut factory1 = Factory::new("FactoryA".to_string());
+    let mut factory2 = Factory::new("FactoryB".to_string());
 
-    let product1 = factory1.create();
-    let product2 = factory2.create();
+    let product1 = factory1.create().unwrap();
+    let product2 = factory2.create().unwrap();
 
     assert_eq!(product1.name, "product from FactoryA");
     assert_eq!(product2.name, "product from FactoryB");
@@ -256,12 +1280,10 @@ fn test_product_creation_different_factories() {
 #[test]
 fn test_truck_peek_cargo() {
     let mut truck = Truck::new("PeekTruck".to_string(), 3);
-    let factory = Factory {
-        name: "TestFactory".to_string(),
-    };
+    let mut factory = Factory::new("TestFactory".to_string());
 
-    let product1 = factory.create();
-    let product2 = factory.create();
+    let product1 = factory.create().unwrap();
+    let product2 = factory.create().unwrap();
 
     truck.load(product1).unwrap();
     truck.load(product2).unwrap();
@@ -277,18 +1299,16 @@ fn test_truck_peek_cargo() {
 #[test]
 fn test_warehouse_peek() {
     let mut warehouse = Warehouse::new(2);
-    let factory = Factory {
-        name: "TestFactory".to_string(),
-    };
+    let mut factory = Factory::new("TestFactory".to_string());
 
-    let product1 = factory.create();
-    let product2 = factory.create();
+    let product1 = factory.create().unwrap();
+    let product2 = factory.create().unwrap();
 
-    warehouse.cargo.enqueue(product1).unwrap();
-    warehouse.cargo.enqueue(product2).unwrap();
+    warehouse.load(product1).unwrap();
+    warehouse.load(product2).unwrap();
 
     // Peek должен показывать первый продукт (FIFO)
-    let first_product = warehouse.cargo.peek().unwrap();
+    let first_product = warehouse.cargo().next().unwrap();
     assert_eq!(first_product.name, "product from TestFactory");
 
     // Peek не должен удалять продукт
@@ -299,12 +1319,10 @@ fn test_warehouse_peek() {
 fn test_multiple_trucks() {
     let mut truck1 = Truck::new("Truck1".to_string(), 2);
     let mut truck2 = Truck::new("Truck2".to_string(), 2);
-    let factory = Factory {
-        name: "MultiFactory".to_string(),
-    };
+    let mut factory = Factory::new("MultiFactory".to_string());
 
-    let product1 = factory.create();
-    let product2 = factory.create();
+    let product1 = factory.create().unwrap();
+    let product2 = factory.create().unwrap();
 
     // Загрузка в разные грузовики
     assert!(truck1.load(product1).is_ok());
@@ -322,3 +1340,566 @@ fn test_multiple_trucks() {
     assert!(truck1.cargo.is_empty());
     assert!(truck2.cargo.is_empty());
 }
+
+#[test]
+fn test_factory_products_carry_sku_and_lot() {
+    let mut factory = Factory::new("LotFactory".to_string()).with_sku("widget");
+
+    let product = factory.create().unwrap();
+    assert_eq!(product.sku, "widget");
+    assert_eq!(product.origin, "LotFactory");
+    assert_eq!(product.lot, 1);
+
+    factory.next_lot();
+    assert_eq!(factory.create().unwrap().lot, 2);
+    assert_eq!(factory.create_sku("gadget").unwrap().sku, "gadget");
+}
+
+#[test]
+fn test_factory_inspection_routes_defects_to_rework() {
+    let mut factory = Factory::new("QcFactory".to_string()).with_seed(1);
+    factory.set_defect_rate("product", 1.0);
+
+    // Все продукты бракованные и уходят на доработку
+    assert_eq!(factory.produce(), Ok(None));
+    assert_eq!(factory.produce(), Ok(None));
+    assert_eq!(factory.rework_queue(), 2);
+
+    let fixed = factory.rework_next().unwrap();
+    assert!(!fixed.defective);
+
+    let stats = factory.lot_stats(1).unwrap();
+    assert_eq!(stats.produced, 2);
+    assert_eq!(stats.reworked, 2);
+    assert_eq!(stats.passed, 0);
+}
+
+#[test]
+fn test_factory_inspection_scraps_when_configured() {
+    let mut factory = Factory::new("ScrapFactory".to_string())
+        .with_defect_action(DefectAction::Scrap);
+    factory.set_defect_rate("product", 1.0);
+
+    assert_eq!(factory.produce(), Ok(None));
+    assert_eq!(factory.scrapped(), 1);
+    assert_eq!(factory.rework_queue(), 0);
+}
+
+#[test]
+fn test_factory_rework_overflow_goes_to_scrap() {
+    let mut factory = Factory::new("BusyFactory".to_string()).with_rework_capacity(1);
+    factory.set_defect_rate("product", 1.0);
+
+    factory.produce().unwrap();
+    factory.produce().unwrap();
+
+    assert_eq!(factory.rework_queue(), 1);
+    assert_eq!(factory.scrapped(), 1);
+}
+
+#[test]
+fn test_factory_defect_rate_is_seeded() {
+    let run = |seed| {
+        let mut factory = Factory::new("SeedFactory".to_string()).with_seed(seed);
+        factory.set_defect_rate("product", 0.5);
+        (0..50).map(|_| factory.produce().unwrap().is_some()).collect::<Vec<_>>()
+    };
+
+    assert_eq!(run(9), run(9));
+    assert_eq!(Factory::new("F".to_string()).defect_rate("unknown"), 0.0);
+}
+
+#[test]
+fn test_factory_step_fills_output_buffer() {
+    let mut factory = Factory::new("BufferFactory".to_string()).with_output_capacity(2);
+
+    assert!(factory.step().is_ok());
+    assert!(factory.step().is_ok());
+    assert_eq!(factory.buffered(), 2);
+
+    // Буфер полон: по умолчанию производство блокируется
+    assert_eq!(factory.step(), Err("Output buffer full"));
+    assert_eq!(factory.buffered(), 2);
+    assert_eq!(factory.lost_capacity(), 1.0);
+
+    factory.take_output();
+    assert!(factory.step().is_ok());
+}
+
+#[test]
+fn test_factory_stop_policy_requires_resume() {
+    let mut factory = Factory::new("StopFactory".to_string())
+        .with_output_capacity(1)
+        .with_backpressure(Backpressure::Stop)
+        .with_cycle_time(0.5);
+
+    factory.step().unwrap();
+    assert!(factory.step().is_err());
+    assert!(factory.is_stopped());
+
+    // Даже после освобождения буфера завод стоит до resume
+    factory.take_output();
+    assert_eq!(factory.step(), Err("Factory is stopped"));
+    assert_eq!(factory.blocked_hours(), 1.0);
+
+    factory.resume();
+    assert!(factory.step().is_ok());
+}
+
+#[test]
+fn test_factory_spill_policy_uses_overflow() {
+    let mut factory = Factory::new("SpillFactory".to_string())
+        .with_output_capacity(1)
+        .with_backpressure(Backpressure::Spill);
+
+    factory.step().unwrap();
+    factory.step().unwrap();
+    factory.step().unwrap();
+    assert_eq!(factory.buffered(), 1);
+    assert_eq!(factory.overflowed(), 2);
+    assert_eq!(factory.lost_capacity(), 0.0);
+
+    // Переполнение возвращается в буфер по мере отгрузки
+    factory.take_output();
+    assert_eq!(factory.buffered(), 1);
+    assert_eq!(factory.overflowed(), 1);
+}
+
+#[test]
+fn test_factory_make_to_order_follows_production_orders() {
+    let mut factory = Factory::new("OrderFactory".to_string()).with_make_to_order();
+    assert_eq!(factory.step(), Err("No production orders"));
+
+    factory.order_production("bolt", 2, None);
+    factory.order_production("nut", 1, Some(3));
+    factory.order_production("washer", 0, None);
+    assert_eq!(factory.production_backlog(), 3);
+
+    for _ in 0..3 {
+        factory.step().unwrap();
+    }
+    let made: Vec<(String, Option<usize>)> = (0..3)
+        .map(|_| factory.take_output().unwrap())
+        .map(|product| (product.sku, product.destination))
+        .collect();
+    assert_eq!(
+        made,
+        [
+            ("bolt".to_string(), None),
+            ("bolt".to_string(), None),
+            ("nut".to_string(), Some(3))
+        ]
+    );
+    assert_eq!(factory.production_backlog(), 0);
+    assert_eq!(factory.step(), Err("No production orders"));
+}
+
+#[test]
+fn test_factory_ship_to_never_loses_products() {
+    let mut factory = Factory::new("ShipFactory".to_string()).with_output_capacity(3);
+    let mut warehouse = Warehouse::new(2);
+
+    for _ in 0..3 {
+        factory.step().unwrap();
+    }
+
+    assert_eq!(factory.ship_to(&mut warehouse, 0), 2);
+    assert_eq!(warehouse.loaded(), 2);
+    assert!(warehouse.is_full());
+    assert_eq!(factory.buffered(), 1);
+
+    warehouse.unload();
+    assert_eq!(factory.ship_to(&mut warehouse, 0), 1);
+    assert_eq!(factory.buffered(), 0);
+}
+
+#[test]
+fn test_broken_factory_rejects_create() {
+    let mut factory = Factory::new("BrokenFactory".to_string());
+
+    factory.reliability_mut().break_down();
+    assert_eq!(factory.create().err(), Some("Factory is broken"));
+    assert_eq!(factory.step(), Err("Factory is broken"));
+    assert_eq!(factory.buffered(), 0);
+}
+
+#[test]
+fn test_factory_under_maintenance_rejects_create() {
+    let mut factory = Factory::new("ServiceFactory".to_string());
+    factory.reliability_mut().schedule_maintenance(1.0, 2.0);
+
+    factory.advance(1.5);
+    assert_eq!(factory.create().err(), Some("Factory is under maintenance"));
+
+    factory.advance(2.0);
+    assert!(factory.create().is_ok());
+    assert_eq!(factory.reliability().stats().maintenance_hours, 2.0);
+}
+
+#[test]
+fn test_broken_truck_rejects_load_and_unload() {
+    let mut factory = Factory::new("TruckFactory".to_string());
+    let mut truck = Truck::new("BrokenTruck".to_string(), 2);
+    truck.load(factory.create().unwrap()).unwrap();
+
+    truck.reliability_mut().break_down();
+    assert_eq!(truck.load(factory.create().unwrap()).err(), Some("Truck is broken"));
+    assert_eq!(truck.unload().err(), Some("Truck is broken"));
+    assert_eq!(truck.loaded(), 1);
+}
+
+#[test]
+fn test_models_attach_to_network_nodes() {
+    let mut network = RoadNetwork::new();
+    let plant = network.add_location(Location::new("plant", SiteKind::Factory, 0.0, 0.0));
+    let dc = network.add_location(Location::new("dc", SiteKind::Warehouse, 10.0, 0.0));
+
+    let factory = Factory::new("NodeFactory".to_string()).with_location(plant);
+    let warehouse = Warehouse::new(1).with_location(dc);
+    let truck = Truck::new("NodeTruck".to_string(), 1).with_location(plant);
+
+    assert_eq!(factory.location(), Some(plant));
+    assert_eq!(warehouse.location(), Some(dc));
+    assert_eq!(truck.location(), Some(plant));
+    assert!(Warehouse::new(1).location().is_none());
+    assert_eq!(network.location(dc).unwrap().kind, SiteKind::Warehouse);
+}
+
+#[cfg(test)]
+fn trip_network() -> (RoadNetwork, NodeId, NodeId, NodeId) {
+    let mut network = RoadNetwork::new();
+    let dc = network.add_location(Location::new("dc", SiteKind::Warehouse, 0.0, 0.0));
+    let cross = network.add_location(Location::new("cross", SiteKind::Junction, 30.0, 0.0));
+    let city = network.add_location(Location::new("city", SiteKind::Market, 60.0, 0.0));
+    network.add_two_way_road(dc, cross, 30.0, 0.5).unwrap();
+    network.add_two_way_road(cross, city, 30.0, 0.5).unwrap();
+    (network, dc, cross, city)
+}
+
+#[test]
+fn test_truck_trip_lifecycle() {
+    let (network, dc, cross, city) = trip_network();
+    let mut factory = Factory::new("TripFactory".to_string());
+    let mut truck = Truck::new("TripTruck".to_string(), 2).with_location(dc);
+
+    truck.load(factory.create().unwrap()).unwrap();
+    assert_eq!(truck.state(), TruckState::Loading);
+
+    let route = network.route(dc, city).unwrap();
+    truck.dispatch(route).unwrap();
+    assert_eq!(truck.state(), TruckState::EnRoute);
+    assert!(!truck.is_docked());
+
+    // В пути грузить и разгружать нельзя
+    assert_eq!(truck.unload().err(), Some("Truck is not docked"));
+
+    truck.advance(0.75);
+    assert_eq!(truck.location(), Some(cross));
+    assert_eq!(truck.state(), TruckState::EnRoute);
+    assert_eq!(truck.dock(&network), Err("Truck is en route"));
+
+    truck.advance(0.25);
+    assert_eq!(truck.location(), Some(city));
+    assert_eq!(truck.state(), TruckState::Idle);
+    assert_eq!(truck.odometer_km(), 60.0);
+
+    truck.dock(&network).unwrap();
+    assert!(truck.unload().is_ok());
+    assert_eq!(truck.state(), TruckState::Unloading);
+}
+
+#[test]
+fn test_truck_cannot_dock_outside_facility() {
+    let (network, dc, cross, _) = trip_network();
+    let mut truck = Truck::new("LostTruck".to_string(), 1).with_location(dc);
+
+    truck.dispatch(network.route(dc, cross).unwrap()).unwrap();
+    truck.advance(1.0);
+
+    assert_eq!(truck.location(), Some(cross));
+    assert_eq!(truck.dock(&network), Err("No facility to dock at"));
+    assert_eq!(Truck::new("Nowhere".to_string(), 1).dock(&network), Err("Truck has no location"));
+}
+
+#[test]
+fn test_truck_dispatch_checks_origin_and_state() {
+    let (network, dc, _, city) = trip_network();
+    let mut truck = Truck::new("BusyTruck".to_string(), 1).with_location(dc);
+
+    assert_eq!(
+        truck.dispatch(network.route(city, dc).unwrap()),
+        Err("Route does not start at truck location")
+    );
+
+    truck.dispatch(network.route(dc, city).unwrap()).unwrap();
+    assert_eq!(truck.dispatch(network.route(dc, city).unwrap()), Err("Truck is en route"));
+}
+
+#[test]
+fn test_truck_without_energy_never_runs_dry() {
+    let mut network = RoadNetwork::new();
+    let plant = network.add_location(Location::new("plant", SiteKind::Factory, 0.0, 0.0));
+    let depot = network.add_location(Location::new("depot", SiteKind::Warehouse, 40.0, 30.0));
+    let city = network.add_location(Location::new("city", SiteKind::Market, 80.0, 30.0));
+    network.add_two_way_road(plant, depot, 50.0, 0.8).unwrap();
+    network.add_two_way_road(depot, city, 40.0, 0.6).unwrap();
+    let mut truck = Truck::new("EndlessTruck".to_string(), 1).with_location(plant);
+
+    // 1.4 - 0.8 в плавающей точке чуть меньше 0.6
+    truck.dispatch(network.route(plant, city).unwrap()).unwrap();
+    truck.advance(2.0);
+    assert_eq!(truck.state(), TruckState::Idle);
+    assert_eq!(truck.location(), Some(city));
+}
+
+#[test]
+fn test_truck_refuelling() {
+    let mut truck = Truck::new("ThirstyTruck".to_string(), 1);
+
+    truck.begin_refuelling(0.5).unwrap();
+    assert_eq!(truck.state(), TruckState::Refuelling);
+
+    truck.advance(0.25);
+    assert_eq!(truck.state(), TruckState::Refuelling);
+
+    truck.advance(0.25);
+    assert_eq!(truck.state(), TruckState::Idle);
+}
+
+#[test]
+fn test_broken_truck_stops_moving() {
+    let (network, dc, _, _) = trip_network();
+    let mut truck = Truck::new("StuckTruck".to_string(), 1)
+        .with_location(dc)
+        .with_reliability(Reliability::new(f64::INFINITY, 100.0, 5).unwrap());
+
+    truck.dispatch(network.route(dc, NodeId(2)).unwrap()).unwrap();
+    truck.reliability_mut().break_down();
+    truck.advance(0.1);
+
+    assert_eq!(truck.odometer_km(), 0.0);
+    assert_eq!(truck.location(), Some(dc));
+}
+
+#[test]
+fn test_truck_moves_only_outside_maintenance() {
+    let (network, dc, _, _) = trip_network();
+    let route = network.route(dc, NodeId(2)).unwrap();
+    let eta = route.eta_hours;
+    let mut truck = Truck::new("ServicedTruck".to_string(), 1).with_location(dc);
+    truck.reliability_mut().schedule_maintenance(0.5, 1.0);
+
+    // Полчаса в пути, затем стоянка до 1.5
+    truck.dispatch(route).unwrap();
+    truck.advance(eta);
+    assert_eq!(truck.state(), TruckState::EnRoute);
+    assert!((truck.trip().unwrap().remaining_hours() - 0.5).abs() < 1e-9);
+
+    truck.advance(0.5);
+    assert!((truck.trip().unwrap().remaining_hours() - 0.5).abs() < 1e-9);
+    truck.advance(0.5);
+    assert_eq!(truck.state(), TruckState::Idle);
+    assert_eq!(truck.location(), Some(NodeId(2)));
+}
+
+#[test]
+fn test_factory_production_time_distribution() {
+    let mut fixed = Factory::new("FixedFactory".to_string()).with_cycle_time(2.0);
+    assert_eq!(fixed.next_cycle_time(), 2.0);
+
+    let mut noisy = Factory::new("NoisyFactory".to_string())
+        .with_production_time(Box::new(random::Triangular { min: 1.0, mode: 1.0, max: 4.0 }));
+    assert_eq!(noisy.cycle_time(), 2.0);
+    for _ in 0..100 {
+        let t = noisy.next_cycle_time();
+        assert!((1.0..=4.0).contains(&t));
+    }
+}
+
+#[test]
+fn test_warehouse_unload_by_sku() {
+    let mut factory = Factory::new("SkuFactory".to_string());
+    let mut warehouse = Warehouse::new(3);
+    warehouse.load(factory.create_sku("bolt").unwrap()).unwrap();
+    warehouse.load(factory.create_sku("nut").unwrap()).unwrap();
+    warehouse.load(factory.create_sku("nut").unwrap()).unwrap();
+
+    assert_eq!(warehouse.count_sku("nut"), 2);
+    assert_eq!(warehouse.unload_sku("nut").unwrap().sku, "nut");
+    assert!(warehouse.unload_sku("screw").is_none());
+    assert_eq!(warehouse.count_sku("nut"), 1);
+    assert_eq!(warehouse.unload().unwrap().sku, "bolt");
+}
+
+#[test]
+fn test_warehouse_inventory_views() {
+    let mut factory = Factory::new("StockFactory".to_string());
+    let mut warehouse = Warehouse::new(5);
+    warehouse.load(factory.create_sku("bolt").unwrap()).unwrap();
+    warehouse.load(factory.create_sku("bolt").unwrap()).unwrap();
+    warehouse.load(factory.create_sku("nut").unwrap()).unwrap();
+    warehouse.expect("nut", 4);
+
+    warehouse.reserve("bolt", 1).unwrap();
+    let bolts = warehouse.stock("bolt");
+    assert_eq!((bolts.on_hand, bolts.reserved, bolts.available()), (2, 1, 1));
+    assert_eq!(warehouse.stock("nut").in_transit, 4);
+    assert_eq!(warehouse.skus().collect::<Vec<_>>(), vec!["bolt", "nut"]);
+    assert!(warehouse.reconcile().is_empty());
+}
+
+#[test]
+fn test_warehouse_reserved_units_are_protected() {
+    let mut factory = Factory::new("ReserveFactory".to_string());
+    let mut warehouse = Warehouse::new(5);
+    warehouse.load(factory.create_sku("bolt").unwrap()).unwrap();
+    warehouse.load(factory.create_sku("nut").unwrap()).unwrap();
+    warehouse.reserve("bolt", 1).unwrap();
+
+    // Зарезервированный болт нельзя забрать обычной выгрузкой
+    assert!(warehouse.unload_sku("bolt").is_none());
+    assert_eq!(warehouse.unload().unwrap().sku, "nut");
+    assert!(warehouse.unload().is_none());
+
+    assert_eq!(warehouse.unload_reserved("bolt").unwrap().sku, "bolt");
+    assert_eq!(warehouse.unload_reserved("bolt").err(), Some("Nothing reserved"));
+    assert_eq!(warehouse.stock("bolt"), StockLevel::default());
+}
+
+#[test]
+fn test_warehouse_reconcile_detects_drift() {
+    let mut factory = Factory::new("DriftFactory".to_string());
+    let mut warehouse = Warehouse::new(5);
+    warehouse.load(factory.create_sku("bolt").unwrap()).unwrap();
+
+    // Товар положили в ячейку в обход учёта
+    let stray = Stored {
+        received: 99,
+        product: factory.create_sku("bolt").unwrap(),
+    };
+    warehouse.layout.bin_mut(BinId { zone: 0, bin: 0 }).unwrap().store(stray).unwrap();
+
+    let diffs = warehouse.reconcile();
+    assert_eq!(diffs.len(), 1);
+    assert_eq!(diffs[0].recorded.on_hand, 1);
+    assert_eq!(diffs[0].counted, 2);
+}
+
+#[test]
+fn test_zoned_warehouse_puts_away_and_locates() {
+    let layout = Layout::new(vec![
+        Zone::new(ZoneKind::PickFace).with_bin(Bin::new(1, 1, 0).allow(&[Category::General])),
+        Zone::new(ZoneKind::Bulk).with_bin(Bin::new(5, 6, 0).allow(&[Category::General])),
+        Zone::new(ZoneKind::Cold).with_bin(Bin::new(2, 3, 0).allow(&[Category::Chilled])),
+    ]);
+    let mut warehouse = Warehouse::from_layout(layout);
+    let mut factory = Factory::new("ZoneFactory".to_string());
+    factory.set_category("milk", Category::Chilled);
+
+    assert_eq!(warehouse.capacity(), 8);
+    assert_eq!(warehouse.load(factory.create_sku("bolt").unwrap()), Ok(BinId { zone: 0, bin: 0 }));
+    assert_eq!(warehouse.load(factory.create_sku("milk").unwrap()), Ok(BinId { zone: 2, bin: 0 }));
+    assert_eq!(warehouse.load(factory.create_sku("bolt").unwrap()), Ok(BinId { zone: 1, bin: 0 }));
+
+    assert_eq!(
+        warehouse.locate("bolt"),
+        vec![(BinId { zone: 0, bin: 0 }, 1), (BinId { zone: 1, bin: 0 }, 1)]
+    );
+
+    // FIFO сохраняется между ячейками
+    assert_eq!(warehouse.unload().unwrap().sku, "bolt");
+    assert_eq!(warehouse.unload().unwrap().sku, "milk");
+    assert_eq!(warehouse.last_cargo().sku, "bolt");
+}
+
+#[test]
+fn test_zoned_warehouse_rejects_unsupported_category() {
+    let layout = Layout::new(vec![
+        Zone::new(ZoneKind::Bulk).with_bin(Bin::new(2, 1, 0).allow(&[Category::General])),
+    ]);
+    let mut warehouse = Warehouse::from_layout(layout);
+    let mut factory = Factory::new("HazFactory".to_string());
+    factory.set_category("acid", Category::Hazardous);
+
+    let acid = factory.create_sku("acid").unwrap();
+    assert!(!warehouse.can_accept(&acid));
+    assert_eq!(warehouse.load(acid).err(), Some("No bin allows this category"));
+    assert_eq!(warehouse.loaded(), 0);
+}
+
+#[cfg(test)]
+fn aisle_warehouse() -> Warehouse {
+    Warehouse::from_layout(Layout::new(vec![
+        Zone::new(ZoneKind::Bulk)
+            .with_bin(Bin::new(4, 1, 0))
+            .with_bin(Bin::new(4, 8, 0)),
+    ]))
+}
+
+#[test]
+fn test_factory_shelf_life() {
+    let mut factory = Factory::new("DairyFactory".to_string());
+    factory.set_shelf_life("milk", 72.0);
+    factory.advance(10.0);
+
+    assert_eq!(factory.create_sku("milk").unwrap().expires_at, Some(82.0));
+    assert_eq!(factory.create_sku("bolt").unwrap().expires_at, None);
+}
+
+#[test]
+fn test_warehouse_fefo_picking() {
+    let mut warehouse = aisle_warehouse().with_picking(Box::new(Fefo));
+    let mut factory = Factory::new("DairyFactory".to_string());
+    factory.set_shelf_life("milk", 72.0);
+
+    let fresh_first = factory.create_sku("milk").unwrap();
+    factory.advance(48.0);
+    let later = factory.create_sku("milk").unwrap();
+    let mut soon = later.clone();
+    soon.expires_at = Some(30.0);
+    warehouse.load(fresh_first).unwrap();
+    warehouse.load(later).unwrap();
+    warehouse.load(soon).unwrap();
+
+    assert_eq!(warehouse.picking_strategy(), "FEFO");
+    assert_eq!(warehouse.unload_sku("milk").unwrap().expires_at, Some(30.0));
+    assert_eq!(warehouse.unload_sku("milk").unwrap().expires_at, Some(72.0));
+}
+
+#[test]
+fn test_warehouse_strategies_change_travel() {
+    let mut factory = Factory::new("TravelFactory".to_string());
+    let mut fifo = aisle_warehouse().with_put_away(Box::new(ClosestEmptyBin));
+    let mut nearest = aisle_warehouse()
+        .with_put_away(Box::new(ClosestEmptyBin))
+        .with_picking(Box::new(LeastTravel));
+
+    // Второй товар уходит в пустую дальнюю ячейку
+    for warehouse in [&mut fifo, &mut nearest] {
+        warehouse.load(factory.create_sku("bolt").unwrap()).unwrap();
+        warehouse.load(factory.create_sku("nut").unwrap()).unwrap();
+        warehouse.load(factory.create_sku("bolt").unwrap()).unwrap();
+        assert_eq!(warehouse.locate("nut"), vec![(BinId { zone: 0, bin: 1 }, 1)]);
+    }
+    assert_eq!(fifo.unload().unwrap().sku, "bolt");
+    assert_eq!(fifo.unload().unwrap().sku, "nut");
+    assert_eq!(nearest.unload().unwrap().sku, "bolt");
+    assert_eq!(nearest.unload().unwrap().sku, "bolt");
+
+    assert_eq!(fifo.handling().picks, 2);
+    assert!(nearest.handling().travel_m < fifo.handling().travel_m);
+    assert!(nearest.handling().picks_per_hour(4000.0, 0.01) > fifo.handling().picks_per_hour(4000.0, 0.01));
+}
+
+#[test]
+fn test_truck_payload_limit() {
+    let mut factory = Factory::new("HeavyFactory".to_string());
+    factory.set_weight("steel", 40.0);
+    factory.set_fragile("glass", true);
+    let mut truck = Truck::new("Light".to_string(), 10).with_max_payload(50.0);
+
+    assert!(factory.create_sku("glass").unwrap().fragile);
+    assert_eq!(truck.load(factory.create_sku("steel").unwrap()), Ok(()));
+    assert_eq!(truck.load(factory.create_sku("steel").unwrap()), Err("Payload limit exceeded"));
+    assert_eq!(truck.payload_kg(), 40.0);
+}
@@ -0,0 +1,251 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::routing::{DistanceMatrix, VehicleProfile};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SiteKind {
+    Factory,
+    Warehouse,
+    Market,
+    Station,
+    Junction,
+}
+
+impl SiteKind {
+    pub fn is_facility(&self) -> bool {
+        matches!(self, SiteKind::Factory | SiteKind::Warehouse | SiteKind::Market)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Location {
+    pub name: String,
+    pub kind: SiteKind,
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Location {
+    pub fn new(name: &str, kind: SiteKind, x: f64, y: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            kind,
+            x,
+            y,
+        }
+    }
+
+    pub fn distance_to(&self, other: &Location) -> f64 {
+        ((self.x - other.x).powi(2) + (self.y - other.y).powi(2)).sqrt()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Restrictions {
+    pub max_height_m: Option<f64>,
+    pub max_weight_t: Option<f64>,
+    pub hazmat_banned: bool,
+}
+
+impl Restrictions {
+    pub fn allows(&self, profile: &VehicleProfile) -> bool {
+        if self.max_height_m.is_some_and(|limit| profile.height_m > limit) {
+            return false;
+        }
+        if self.max_weight_t.is_some_and(|limit| profile.gross_weight_t > limit) {
+            return false;
+        }
+        !(self.hazmat_banned && profile.hazmat)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Road {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub distance_km: f64,
+    pub travel_hours: f64,
+    pub restrictions: Restrictions,
+}
+
+pub struct RoadNetwork {
+    nodes: Vec<Location>,
+    roads: Vec<Road>,
+    adjacency: Vec<Vec<usize>>,
+    matrices: RefCell<Vec<(Option<VehicleProfile>, Rc<DistanceMatrix>)>>,
+}
+
+impl RoadNetwork {
+    pub fn new() -> Self {
+        Self {
+            nodes: Vec::new(),
+            roads: Vec::new(),
+            adjacency: Vec::new(),
+            matrices: RefCell::new(Vec::new()),
+        }
+    }
+
+    fn invalidate(&mut self) {
+        self.matrices.get_mut().clear();
+    }
+
+    pub fn add_location(&mut self, location: Location) -> NodeId {
+        self.invalidate();
+        self.nodes.push(location);
+        self.adjacency.push(Vec::new());
+        NodeId(self.nodes.len() - 1)
+    }
+
+    pub fn add_road(&mut self, from: NodeId, to: NodeId, distance_km: f64, travel_hours: f64) -> Result<(), &'static str> {
+        if from.0 >= self.nodes.len() || to.0 >= self.nodes.len() {
+            return Err("Unknown location");
+        }
+        if distance_km < 0.0 || travel_hours < 0.0 {
+            return Err("Road length must not be negative");
+        }
+        self.invalidate();
+        self.roads.push(Road {
+            from,
+            to,
+            distance_km,
+            travel_hours,
+            restrictions: Restrictions::default(),
+        });
+        self.adjacency[from.0].push(self.roads.len() - 1);
+        Ok(())
+    }
+
+    pub fn add_two_way_road(&mut self, a: NodeId, b: NodeId, distance_km: f64, travel_hours: f64) -> Result<(), &'static str> {
+        self.add_road(a, b, distance_km, travel_hours)?;
+        self.add_road(b, a, distance_km, travel_hours)
+    }
+
+    pub fn restrict_road(&mut self, from: NodeId, to: NodeId, restrictions: Restrictions) -> Result<(), &'static str> {
+        let index = self
+            .adjacency
+            .get(from.0)
+            .into_iter()
+            .flatten()
+            .copied()
+            .find(|&index| self.roads[index].to == to)
+            .ok_or("Unknown road")?;
+        self.invalidate();
+        self.roads[index].restrictions = restrictions;
+        Ok(())
+    }
+
+    pub fn distance_matrix(&self, profile: Option<&VehicleProfile>) -> Rc<DistanceMatrix> {
+        let key = profile.copied();
+        if let Some((_, matrix)) = self.matrices.borrow().iter().find(|(p, _)| *p == key) {
+            return Rc::clone(matrix);
+        }
+        let matrix = Rc::new(DistanceMatrix::build(self, profile));
+        self.matrices.borrow_mut().push((key, Rc::clone(&matrix)));
+        matrix
+    }
+
+    pub fn cached_matrices(&self) -> usize {
+        self.matrices.borrow().len()
+    }
+
+    pub fn location(&self, node: NodeId) -> Option<&Location> {
+        self.nodes.get(node.0)
+    }
+
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|l| l.name == name).map(NodeId)
+    }
+
+    pub fn roads_from(&self, node: NodeId) -> impl Iterator<Item = &Road> {
+        self.adjacency
+            .get(node.0)
+            .into_iter()
+            .flatten()
+            .map(|&index| &self.roads[index])
+    }
+
+    pub fn road(&self, from: NodeId, to: NodeId) -> Option<&Road> {
+        self.roads_from(from).find(|road| road.to == to)
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &Location)> {
+        self.nodes.iter().enumerate().map(|(i, l)| (NodeId(i), l))
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+impl Default for RoadNetwork {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_network() -> (RoadNetwork, NodeId, NodeId, NodeId) {
+        let mut network = RoadNetwork::new();
+        let factory = network.add_location(Location::new("plant", SiteKind::Factory, 0.0, 0.0));
+        let warehouse = network.add_location(Location::new("dc", SiteKind::Warehouse, 30.0, 40.0));
+        let market = network.add_location(Location::new("city", SiteKind::Market, 60.0, 40.0));
+        network.add_two_way_road(factory, warehouse, 55.0, 0.75).unwrap();
+        network.add_road(warehouse, market, 32.0, 0.5).unwrap();
+        (network, factory, warehouse, market)
+    }
+
+    #[test]
+    fn test_add_locations_and_roads() {
+        let (network, factory, warehouse, market) = sample_network();
+
+        assert_eq!(network.len(), 3);
+        assert_eq!(network.find("dc"), Some(warehouse));
+        assert_eq!(network.location(market).unwrap().kind, SiteKind::Market);
+        assert_eq!(network.roads_from(factory).count(), 1);
+        assert_eq!(network.roads_from(warehouse).count(), 2);
+    }
+
+    #[test]
+    fn test_one_way_road() {
+        let (network, _, warehouse, market) = sample_network();
+
+        let road = network.road(warehouse, market).unwrap();
+        assert_eq!(road.distance_km, 32.0);
+        assert_eq!(road.travel_hours, 0.5);
+
+        // Обратной дороги нет
+        assert!(network.road(market, warehouse).is_none());
+    }
+
+    #[test]
+    fn test_invalid_roads() {
+        let (mut network, factory, _, _) = sample_network();
+
+        assert_eq!(network.add_road(factory, NodeId(42), 1.0, 1.0), Err("Unknown location"));
+        assert_eq!(
+            network.add_road(factory, factory, -1.0, 1.0),
+            Err("Road length must not be negative")
+        );
+    }
+
+    #[test]
+    fn test_location_distance_and_kind() {
+        let a = Location::new("a", SiteKind::Junction, 0.0, 0.0);
+        let b = Location::new("b", SiteKind::Station, 3.0, 4.0);
+
+        assert_eq!(a.distance_to(&b), 5.0);
+        assert!(!a.kind.is_facility());
+        assert!(SiteKind::Warehouse.is_facility());
+    }
+}
@@ -0,0 +1,543 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::{Product, Truck, Warehouse};
+use crate::network::NodeId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum OrderStatus {
+    Received,
+    Allocated,
+    Picked,
+    Loaded,
+    Shipped,
+    Delivered,
+    Invoiced,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderLine {
+    pub sku: String,
+    pub quantity: usize,
+    pub allocated: usize,
+    pub picked: usize,
+    pub loaded: usize,
+    pub shipped: usize,
+    pub delivered: usize,
+    pub invoiced: usize,
+}
+
+impl OrderLine {
+    pub fn new(sku: &str, quantity: usize) -> Self {
+        Self {
+            sku: sku.to_string(),
+            quantity,
+            allocated: 0,
+            picked: 0,
+            loaded: 0,
+            shipped: 0,
+            delivered: 0,
+            invoiced: 0,
+        }
+    }
+
+    pub fn backordered(&self) -> usize {
+        self.quantity - self.allocated
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Order {
+    pub id: u64,
+    pub customer: String,
+    pub lines: Vec<OrderLine>,
+    pub due: f64,
+    pub priority: u8,
+    pub destination: NodeId,
+    pub status: OrderStatus,
+    pub amount_invoiced: f64,
+}
+
+impl Order {
+    pub fn is_complete(&self) -> bool {
+        self.lines.iter().all(|line| line.delivered == line.quantity)
+    }
+
+    pub fn is_partial(&self) -> bool {
+        self.status >= OrderStatus::Shipped && !self.is_closed() && !self.is_complete()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        matches!(self.status, OrderStatus::Invoiced | OrderStatus::Cancelled)
+    }
+
+    // Статус только растёт: добор бэкордера не откатывает уже отгруженный заказ
+    pub(crate) fn advance_to(&mut self, status: OrderStatus) {
+        if self.status < status {
+            self.status = status;
+        }
+    }
+
+    pub fn backordered(&self) -> usize {
+        self.lines.iter().map(|line| line.backordered()).sum()
+    }
+}
+
+pub struct OrderBook {
+    orders: BTreeMap<u64, Order>,
+    staged: HashMap<u64, Vec<Product>>,
+    next_id: u64,
+}
+
+impl OrderBook {
+    pub fn new() -> Self {
+        Self {
+            orders: BTreeMap::new(),
+            staged: HashMap::new(),
+            next_id: 1,
+        }
+    }
+
+    pub fn place(
+        &mut self,
+        customer: &str,
+        lines: Vec<OrderLine>,
+        due: f64,
+        priority: u8,
+        destination: NodeId,
+    ) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.orders.insert(
+            id,
+            Order {
+                id,
+                customer: customer.to_string(),
+                lines,
+                due,
+                priority,
+                destination,
+                status: OrderStatus::Received,
+                amount_invoiced: 0.0,
+            },
+        );
+        id
+    }
+
+    pub fn order(&self, id: u64) -> Option<&Order> {
+        self.orders.get(&id)
+    }
+
+    pub fn status(&self, id: u64) -> Option<OrderStatus> {
+        self.orders.get(&id).map(|order| order.status)
+    }
+
+    // Открытые заказы: сначала высокий приоритет, затем ранний срок
+    pub fn open_orders(&self) -> Vec<u64> {
+        let mut open: Vec<&Order> = self
+            .orders
+            .values()
+            .filter(|order| !order.is_closed())
+            .collect();
+        open.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then(a.due.total_cmp(&b.due))
+                .then(a.id.cmp(&b.id))
+        });
+        open.into_iter().map(|order| order.id).collect()
+    }
+
+    pub fn staged(&self, id: u64) -> usize {
+        self.staged.get(&id).map_or(0, |products| products.len())
+    }
+
+    pub(crate) fn order_mut(&mut self, id: u64) -> Result<&mut Order, &'static str> {
+        self.orders.get_mut(&id).ok_or("Unknown order")
+    }
+
+    pub(crate) fn stage(&mut self, id: u64, product: Product) {
+        self.staged.entry(id).or_default().push(product);
+    }
+
+    pub fn allocate(&mut self, id: u64, warehouse: &mut Warehouse) -> Result<usize, &'static str> {
+        let order = self.order_mut(id)?;
+        if order.is_closed() {
+            return Err("Order is closed");
+        }
+        let mut allocated = 0;
+        for line in order.lines.iter_mut() {
+            let take = line.backordered().min(warehouse.stock(&line.sku).available());
+            if take > 0 {
+                warehouse.reserve(&line.sku, take)?;
+                line.allocated += take;
+                allocated += take;
+            }
+        }
+        if allocated == 0 {
+            return Err("Nothing available to allocate");
+        }
+        order.advance_to(OrderStatus::Allocated);
+        Ok(allocated)
+    }
+
+    // Снимает резерв и возвращает на склад собранное, но не погруженное
+    pub fn cancel(&mut self, id: u64, warehouse: &mut Warehouse) -> Result<usize, &'static str> {
+        let order = self.orders.get_mut(&id).ok_or("Unknown order")?;
+        if order.is_closed() {
+            return Err("Order is closed");
+        }
+        if order.status >= OrderStatus::Loaded {
+            return Err("Order is already loaded");
+        }
+        let staged = self.staged.entry(id).or_default();
+        if !warehouse.has_room_for(staged) {
+            return Err("Warehouse has no room for staged units");
+        }
+        let mut released = 0;
+        for line in order.lines.iter_mut() {
+            let unpicked = line.allocated - line.picked;
+            if unpicked > 0 {
+                warehouse.release(&line.sku, unpicked)?;
+                line.allocated = line.picked;
+                released += unpicked;
+            }
+        }
+        while let Some(mut product) = staged.pop() {
+            product.order = None;
+            if let Some(line) = order.lines.iter_mut().find(|line| line.sku == product.sku) {
+                line.picked -= 1;
+                line.allocated -= 1;
+            }
+            warehouse.load(product)?;
+            released += 1;
+        }
+        order.status = OrderStatus::Cancelled;
+        Ok(released)
+    }
+
+    pub fn pick(&mut self, id: u64, warehouse: &mut Warehouse) -> Result<usize, &'static str> {
+        let order = self.orders.get_mut(&id).ok_or("Unknown order")?;
+        if order.is_closed() {
+            return Err("Order is closed");
+        }
+        let staged = self.staged.entry(id).or_default();
+        let mut picked = 0;
+        let mut failure = None;
+        // Собранное не откатывается: при сбое возвращаем, сколько успели
+        for line in order.lines.iter_mut() {
+            while line.picked < line.allocated {
+                match warehouse.unload_reserved(&line.sku) {
+                    Ok(mut product) => {
+                        product.order = Some(id);
+                        staged.push(product);
+                        line.picked += 1;
+                        picked += 1;
+                    }
+                    Err(err) => {
+                        failure.get_or_insert(err);
+                        break;
+                    }
+                }
+            }
+        }
+        if picked == 0 {
+            return Err(failure.unwrap_or("Nothing allocated to pick"));
+        }
+        order.advance_to(OrderStatus::Picked);
+        Ok(picked)
+    }
+
+    pub fn load(&mut self, id: u64, truck: &mut Truck) -> Result<usize, &'static str> {
+        let order = self.orders.get_mut(&id).ok_or("Unknown order")?;
+        if order.is_closed() {
+            return Err("Order is closed");
+        }
+        let staged = self.staged.entry(id).or_default();
+        if staged.is_empty() {
+            return Err("Nothing picked to load");
+        }
+        let mut loaded = 0;
+        let mut failure = None;
+        while truck.loaded() < truck.capacity() {
+            let Some(product) = staged.last() else { break };
+            if let Err(err) = truck.check_load(product) {
+                failure = Some(err);
+                break;
+            }
+            let product = staged.pop().expect("checked last");
+            if let Some(line) = order.lines.iter_mut().find(|line| line.sku == product.sku) {
+                line.loaded += 1;
+            }
+            truck.load(product)?;
+            loaded += 1;
+        }
+        if loaded == 0 {
+            return Err(failure.unwrap_or("Truck is full"));
+        }
+        order.advance_to(OrderStatus::Loaded);
+        Ok(loaded)
+    }
+
+    pub fn ship(&mut self, id: u64) -> Result<usize, &'static str> {
+        let order = self.order_mut(id)?;
+        let mut shipped = 0;
+        for line in order.lines.iter_mut() {
+            shipped += line.loaded - line.shipped;
+            line.shipped = line.loaded;
+        }
+        if shipped == 0 {
+            return Err("Nothing loaded to ship");
+        }
+        order.advance_to(OrderStatus::Shipped);
+        Ok(shipped)
+    }
+
+    // Выгружает с верха кузова всё, что относится к заказу
+    pub fn deliver(&mut self, id: u64, truck: &mut Truck) -> Result<Vec<Product>, &'static str> {
+        let order = self.order_mut(id)?;
+        let mut delivered = Vec::new();
+        while truck.cargo().last().is_some_and(|product| product.order == Some(id)) {
+            let product = truck.unload()?;
+            if let Some(line) = order
+                .lines
+                .iter_mut()
+                .find(|line| line.sku == product.sku && line.delivered < line.shipped)
+            {
+                line.delivered += 1;
+            }
+            delivered.push(product);
+        }
+        if delivered.is_empty() {
+            return Err("No cargo for this order on top of the truck");
+        }
+        order.advance_to(OrderStatus::Delivered);
+        Ok(delivered)
+    }
+
+    pub fn invoice(&mut self, id: u64, prices: &HashMap<String, f64>) -> Result<f64, &'static str> {
+        let order = self.order_mut(id)?;
+        if order.is_closed() {
+            return Err("Order is closed");
+        }
+        let mut amount = 0.0;
+        let mut units = 0;
+        for line in order.lines.iter_mut() {
+            let billable = line.delivered - line.invoiced;
+            amount += billable as f64 * prices.get(&line.sku).copied().unwrap_or(0.0);
+            units += billable;
+            line.invoiced = line.delivered;
+        }
+        if units == 0 {
+            return Err("Nothing delivered to invoice");
+        }
+        order.amount_invoiced += amount;
+        if order.is_complete() {
+            order.status = OrderStatus::Invoiced;
+        }
+        Ok(amount)
+    }
+}
+
+impl Default for OrderBook {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Factory;
+
+    fn stocked_warehouse(bolts: usize, nuts: usize) -> Warehouse {
+        let mut factory = Factory::new("OrderFactory".to_string());
+        let mut warehouse = Warehouse::new(20);
+        for _ in 0..bolts {
+            warehouse.load(factory.create_sku("bolt").unwrap()).unwrap();
+        }
+        for _ in 0..nuts {
+            warehouse.load(factory.create_sku("nut").unwrap()).unwrap();
+        }
+        warehouse
+    }
+
+    fn prices() -> HashMap<String, f64> {
+        HashMap::from([("bolt".to_string(), 2.0), ("nut".to_string(), 0.5)])
+    }
+
+    #[test]
+    fn test_full_order_lifecycle() {
+        let mut book = OrderBook::new();
+        let mut warehouse = stocked_warehouse(3, 4);
+        let mut truck = Truck::new("OrderTruck".to_string(), 10);
+        let id = book.place(
+            "Acme",
+            vec![OrderLine::new("bolt", 2), OrderLine::new("nut", 4)],
+            48.0,
+            1,
+            NodeId(0),
+        );
+        assert_eq!(book.status(id), Some(OrderStatus::Received));
+
+        assert_eq!(book.allocate(id, &mut warehouse), Ok(6));
+        assert_eq!(book.status(id), Some(OrderStatus::Allocated));
+
+        assert_eq!(book.pick(id, &mut warehouse), Ok(6));
+        assert_eq!(warehouse.loaded(), 1);
+        assert_eq!(book.staged(id), 6);
+
+        assert_eq!(book.load(id, &mut truck), Ok(6));
+        assert_eq!(book.status(id), Some(OrderStatus::Loaded));
+        assert_eq!(book.ship(id), Ok(6));
+
+        let delivered = book.deliver(id, &mut truck).unwrap();
+        assert_eq!(delivered.len(), 6);
+        assert!(delivered.iter().all(|p| p.order == Some(id)));
+        assert!(book.order(id).unwrap().is_complete());
+
+        assert_eq!(book.invoice(id, &prices()), Ok(6.0));
+        assert_eq!(book.status(id), Some(OrderStatus::Invoiced));
+        assert!(book.open_orders().is_empty());
+    }
+
+    #[test]
+    fn test_partial_shipment_and_backorder() {
+        let mut book = OrderBook::new();
+        let mut warehouse = stocked_warehouse(1, 0);
+        let mut truck = Truck::new("PartialTruck".to_string(), 10);
+        let id = book.place("Acme", vec![OrderLine::new("bolt", 3)], 10.0, 0, NodeId(0));
+
+        // На складе только один болт: остальное в бэкордере
+        assert_eq!(book.allocate(id, &mut warehouse), Ok(1));
+        assert_eq!(book.order(id).unwrap().backordered(), 2);
+        book.pick(id, &mut warehouse).unwrap();
+        book.load(id, &mut truck).unwrap();
+        book.ship(id).unwrap();
+        assert!(book.order(id).unwrap().is_partial());
+        book.deliver(id, &mut truck).unwrap();
+        assert_eq!(book.invoice(id, &prices()), Ok(2.0));
+        assert_eq!(book.status(id), Some(OrderStatus::Delivered));
+
+        // Пополнение склада закрывает бэкордер
+        let mut warehouse = stocked_warehouse(5, 0);
+        assert_eq!(book.allocate(id, &mut warehouse), Ok(2));
+        assert_eq!(book.status(id), Some(OrderStatus::Delivered));
+        book.pick(id, &mut warehouse).unwrap();
+        book.load(id, &mut truck).unwrap();
+        book.ship(id).unwrap();
+        book.deliver(id, &mut truck).unwrap();
+        assert_eq!(book.invoice(id, &prices()), Ok(4.0));
+        assert_eq!(book.status(id), Some(OrderStatus::Invoiced));
+        assert_eq!(book.order(id).unwrap().amount_invoiced, 6.0);
+    }
+
+    #[test]
+    fn test_orders_do_not_claim_same_units() {
+        let mut book = OrderBook::new();
+        let mut warehouse = stocked_warehouse(2, 0);
+        let first = book.place("A", vec![OrderLine::new("bolt", 2)], 1.0, 0, NodeId(0));
+        let second = book.place("B", vec![OrderLine::new("bolt", 1)], 1.0, 0, NodeId(0));
+
+        assert_eq!(book.allocate(first, &mut warehouse), Ok(2));
+        assert_eq!(book.allocate(second, &mut warehouse), Err("Nothing available to allocate"));
+        assert_eq!(book.status(second), Some(OrderStatus::Received));
+
+        // Отмена первого заказа освобождает резерв
+        assert_eq!(book.cancel(first, &mut warehouse), Ok(2));
+        assert_eq!(warehouse.stock("bolt").reserved, 0);
+        assert_eq!(book.allocate(second, &mut warehouse), Ok(1));
+    }
+
+    #[test]
+    fn test_cancel_closes_order_and_restocks_picks() {
+        let mut book = OrderBook::new();
+        let mut warehouse = stocked_warehouse(3, 0);
+        let id = book.place("A", vec![OrderLine::new("bolt", 3)], 1.0, 0, NodeId(0));
+        book.allocate(id, &mut warehouse).unwrap();
+        book.pick(id, &mut warehouse).unwrap();
+        assert_eq!(warehouse.loaded(), 0);
+
+        assert_eq!(book.cancel(id, &mut warehouse), Ok(3));
+        assert_eq!(book.status(id), Some(OrderStatus::Cancelled));
+        assert_eq!(book.staged(id), 0);
+        assert_eq!(warehouse.stock("bolt").available(), 3);
+        assert!(book.open_orders().is_empty());
+        assert!(!book.order(id).unwrap().is_partial());
+        assert_eq!(book.allocate(id, &mut warehouse), Err("Order is closed"));
+        assert_eq!(book.cancel(id, &mut warehouse), Err("Order is closed"));
+        assert_eq!(book.invoice(id, &prices()), Err("Order is closed"));
+    }
+
+    #[test]
+    fn test_cancel_refuses_without_room_or_after_loading() {
+        let mut factory = Factory::new("OrderFactory".to_string());
+        let mut book = OrderBook::new();
+        let mut warehouse = stocked_warehouse(3, 0);
+        let id = book.place("A", vec![OrderLine::new("bolt", 3)], 1.0, 0, NodeId(0));
+        book.allocate(id, &mut warehouse).unwrap();
+        book.pick(id, &mut warehouse).unwrap();
+        while !warehouse.is_full() {
+            warehouse.load(factory.create_sku("nut").unwrap()).unwrap();
+        }
+
+        // Собранное некуда вернуть — заказ остаётся как был
+        assert_eq!(
+            book.cancel(id, &mut warehouse),
+            Err("Warehouse has no room for staged units")
+        );
+        assert_eq!(book.status(id), Some(OrderStatus::Picked));
+        assert_eq!(book.staged(id), 3);
+
+        let mut truck = Truck::new("OrderTruck".to_string(), 10);
+        book.load(id, &mut truck).unwrap();
+        assert_eq!(book.cancel(id, &mut warehouse), Err("Order is already loaded"));
+        assert_eq!(book.status(id), Some(OrderStatus::Loaded));
+    }
+
+    #[test]
+    fn test_load_stops_at_payload_without_losing_units() {
+        let mut book = OrderBook::new();
+        let mut warehouse = stocked_warehouse(3, 0);
+        let mut truck = Truck::new("LightTruck".to_string(), 10).with_max_payload(2.0);
+        let id = book.place("A", vec![OrderLine::new("bolt", 3)], 1.0, 0, NodeId(0));
+        book.allocate(id, &mut warehouse).unwrap();
+        book.pick(id, &mut warehouse).unwrap();
+
+        // Третий болт не проходит по массе и остаётся в зоне отгрузки
+        assert_eq!(book.load(id, &mut truck), Ok(2));
+        assert_eq!(book.status(id), Some(OrderStatus::Loaded));
+        assert_eq!(book.order(id).unwrap().lines[0].loaded, 2);
+        assert_eq!(book.load(id, &mut truck), Err("Payload limit exceeded"));
+        assert_eq!(book.staged(id), 1);
+        assert_eq!(truck.loaded(), 2);
+    }
+
+    #[test]
+    fn test_truck_capacity_splits_load() {
+        let mut book = OrderBook::new();
+        let mut warehouse = stocked_warehouse(3, 0);
+        let mut truck = Truck::new("TinyTruck".to_string(), 2);
+        let id = book.place("A", vec![OrderLine::new("bolt", 3)], 1.0, 0, NodeId(0));
+
+        book.allocate(id, &mut warehouse).unwrap();
+        book.pick(id, &mut warehouse).unwrap();
+        assert_eq!(book.load(id, &mut truck), Ok(2));
+        assert_eq!(book.staged(id), 1);
+        assert_eq!(book.load(id, &mut truck), Err("Truck is full"));
+    }
+
+    #[test]
+    fn test_workflow_guards_and_priorities() {
+        let mut book = OrderBook::new();
+        let low = book.place("A", vec![OrderLine::new("bolt", 1)], 5.0, 0, NodeId(0));
+        let urgent = book.place("B", vec![OrderLine::new("bolt", 1)], 9.0, 5, NodeId(0));
+        let soon = book.place("C", vec![OrderLine::new("bolt", 1)], 1.0, 0, NodeId(0));
+
+        assert_eq!(book.open_orders(), vec![urgent, soon, low]);
+        assert_eq!(book.ship(low), Err("Nothing loaded to ship"));
+        assert_eq!(book.invoice(low, &prices()), Err("Nothing delivered to invoice"));
+        assert_eq!(book.status(99), None);
+        assert_eq!(book.allocate(99, &mut Warehouse::new(1)), Err("Unknown order"));
+    }
+}
@@ -0,0 +1,518 @@
+use crate::models::Product;
+
+const EPS: f64 = 1e-9;
+// Какая доля основания должна опираться на пол или на груз под ним
+const MIN_SUPPORT: f64 = 0.75;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dimensions {
+    pub length_m: f64,
+    pub width_m: f64,
+    pub height_m: f64,
+}
+
+impl Dimensions {
+    pub fn new(length_m: f64, width_m: f64, height_m: f64) -> Self {
+        Self {
+            length_m,
+            width_m,
+            height_m,
+        }
+    }
+
+    pub fn volume_m3(&self) -> f64 {
+        self.length_m * self.width_m * self.height_m
+    }
+
+    // Вертикально ориентированный груз можно только поворачивать вокруг вертикали
+    fn orientations(&self, upright: bool) -> Vec<Dimensions> {
+        let (l, w, h) = (self.length_m, self.width_m, self.height_m);
+        let mut all = vec![Self::new(l, w, h), Self::new(w, l, h)];
+        if !upright {
+            all.extend([
+                Self::new(l, h, w),
+                Self::new(h, l, w),
+                Self::new(w, h, l),
+                Self::new(h, w, l),
+            ]);
+        }
+        all
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Packaging {
+    pub dimensions: Dimensions,
+    pub upright: bool,
+    pub max_top_load_kg: f64,
+}
+
+impl Default for Packaging {
+    fn default() -> Self {
+        Self {
+            dimensions: Dimensions::new(0.4, 0.3, 0.3),
+            upright: false,
+            max_top_load_kg: f64::INFINITY,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Axles {
+    // Положение осей от передней стенки кузова
+    pub front_m: f64,
+    pub rear_m: f64,
+    pub max_front_kg: f64,
+    pub max_rear_kg: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CargoSpace {
+    pub dimensions: Dimensions,
+    pub axles: Option<Axles>,
+}
+
+impl CargoSpace {
+    pub fn new(length_m: f64, width_m: f64, height_m: f64) -> Self {
+        Self {
+            dimensions: Dimensions::new(length_m, width_m, height_m),
+            axles: None,
+        }
+    }
+
+    pub fn with_axles(mut self, axles: Axles) -> Self {
+        self.axles = Some(axles);
+        self
+    }
+}
+
+// Индекс размещения и приходящаяся на него величина
+type Shares = Vec<(usize, f64)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    pub sku: String,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub size: Dimensions,
+    pub weight_kg: f64,
+    max_top_load_kg: f64,
+    top_load_kg: f64,
+    // Опоры и доля веса, которая на каждую передаётся
+    supports: Shares,
+}
+
+impl Placement {
+    pub fn top_load_kg(&self) -> f64 {
+        self.top_load_kg
+    }
+
+    fn overlap_xy(&self, x: f64, y: f64, size: &Dimensions) -> f64 {
+        let dx = (self.x + self.size.length_m).min(x + size.length_m) - self.x.max(x);
+        let dy = (self.y + self.size.width_m).min(y + size.width_m) - self.y.max(y);
+        dx.max(0.0) * dy.max(0.0)
+    }
+
+    fn intersects(&self, x: f64, y: f64, z: f64, size: &Dimensions) -> bool {
+        let dz = (self.z + self.size.height_m).min(z + size.height_m) - self.z.max(z);
+        self.overlap_xy(x, y, size) > EPS && dz > EPS
+    }
+
+    fn top(&self) -> f64 {
+        self.z + self.size.height_m
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PackingPlan {
+    // Порядок размещения совпадает с порядком погрузки
+    pub placements: Vec<Placement>,
+    pub utilization_pct: f64,
+    pub front_axle_kg: f64,
+    pub rear_axle_kg: f64,
+}
+
+impl PackingPlan {
+    pub fn weight_kg(&self) -> f64 {
+        self.placements
+            .iter()
+            .map(|placement| placement.weight_kg)
+            .sum()
+    }
+
+    // План, собранный для другого кузова, может не влезть или перегрузить оси
+    pub fn check_fits(&self, space: &CargoSpace) -> Result<(), &'static str> {
+        let bounds = space.dimensions;
+        let outside = self.placements.iter().any(|p| {
+            p.x + p.size.length_m > bounds.length_m + EPS
+                || p.y + p.size.width_m > bounds.width_m + EPS
+                || p.z + p.size.height_m > bounds.height_m + EPS
+        });
+        if outside {
+            return Err("Cargo does not fit the truck");
+        }
+        axle_loads(space, &self.placements).map(|_| ())
+    }
+}
+
+fn axle_loads(space: &CargoSpace, placed: &[Placement]) -> Result<(f64, f64), &'static str> {
+    let total: f64 = placed.iter().map(|placement| placement.weight_kg).sum();
+    match space.axles {
+        Some(axles) if (axles.rear_m - axles.front_m).abs() < EPS => {
+            Err("Axles must be at different positions")
+        }
+        Some(axles) if total > 0.0 => {
+            let moment: f64 = placed
+                .iter()
+                .map(|p| p.weight_kg * (p.x + p.size.length_m / 2.0))
+                .sum();
+            let center = moment / total;
+            let rear = total * (center - axles.front_m) / (axles.rear_m - axles.front_m);
+            let front = total - rear;
+            if front < 0.0 || rear < 0.0 || front > axles.max_front_kg || rear > axles.max_rear_kg {
+                return Err("Axle load out of balance");
+            }
+            Ok((front, rear))
+        }
+        _ => Ok((0.0, 0.0)),
+    }
+}
+
+fn place(
+    placed: &[Placement],
+    space: &Dimensions,
+    x: f64,
+    y: f64,
+    z: f64,
+    size: &Dimensions,
+    weight: f64,
+) -> Option<(Shares, Shares)> {
+    if x + size.length_m > space.length_m + EPS
+        || y + size.width_m > space.width_m + EPS
+        || z + size.height_m > space.height_m + EPS
+    {
+        return None;
+    }
+    if placed.iter().any(|other| other.intersects(x, y, z, size)) {
+        return None;
+    }
+    if z < EPS {
+        return Some((Vec::new(), Vec::new()));
+    }
+    let supports: Vec<(usize, f64)> = placed
+        .iter()
+        .enumerate()
+        .filter(|(_, other)| (other.top() - z).abs() < EPS)
+        .map(|(index, other)| (index, other.overlap_xy(x, y, size)))
+        .filter(|(_, area)| *area > EPS)
+        .collect();
+    let supported: f64 = supports.iter().map(|(_, area)| area).sum();
+    if supported < MIN_SUPPORT * size.length_m * size.width_m {
+        return None;
+    }
+    // Вес делится между опорами пропорционально площади касания
+    // и через них передаётся дальше вниз до пола
+    let fractions: Shares = supports
+        .into_iter()
+        .map(|(index, area)| (index, area / supported))
+        .collect();
+    let mut added = vec![0.0; placed.len()];
+    for (index, fraction) in &fractions {
+        added[*index] += weight * fraction;
+    }
+    for index in (0..placed.len()).rev() {
+        if added[index] <= 0.0 {
+            continue;
+        }
+        for (below, fraction) in &placed[index].supports {
+            added[*below] += added[index] * fraction;
+        }
+    }
+    let loads: Shares = added
+        .into_iter()
+        .enumerate()
+        .filter(|(_, load)| *load > 0.0)
+        .collect();
+    let overloaded = loads.iter().any(|(index, load)| {
+        placed[*index].top_load_kg + load > placed[*index].max_top_load_kg + EPS
+    });
+    if overloaded { None } else { Some((fractions, loads)) }
+}
+
+// Раскладка методом крайних точек: товары ставятся в заданном порядке,
+// каждый — в самую переднюю, нижнюю и левую свободную позицию
+pub fn pack(space: &CargoSpace, products: &[Product]) -> Result<PackingPlan, &'static str> {
+    let bounds = space.dimensions;
+    let mut placed: Vec<Placement> = Vec::with_capacity(products.len());
+    let mut points: Vec<(f64, f64, f64)> = vec![(0.0, 0.0, 0.0)];
+
+    for product in products {
+        points.sort_by(|a, b| {
+            a.0.total_cmp(&b.0)
+                .then(a.2.total_cmp(&b.2))
+                .then(a.1.total_cmp(&b.1))
+        });
+        let packaging = &product.packaging;
+        let found = points.iter().enumerate().find_map(|(point, &(x, y, z))| {
+            packaging
+                .dimensions
+                .orientations(packaging.upright)
+                .into_iter()
+                .find_map(|size| {
+                    place(&placed, &bounds, x, y, z, &size, product.weight_kg)
+                        .map(|(supports, loads)| (point, size, supports, loads))
+                })
+        });
+        let (point, size, supports, loads) = found.ok_or("Cargo does not fit the truck")?;
+        let (x, y, z) = points.remove(point);
+        for (index, load) in loads {
+            placed[index].top_load_kg += load;
+        }
+        placed.push(Placement {
+            sku: product.sku.clone(),
+            x,
+            y,
+            z,
+            size,
+            weight_kg: product.weight_kg,
+            max_top_load_kg: packaging.max_top_load_kg,
+            top_load_kg: 0.0,
+            supports,
+        });
+        points.extend([
+            (x + size.length_m, y, z),
+            (x, y + size.width_m, z),
+            (x, y, z + size.height_m),
+        ]);
+    }
+
+    let (front_axle_kg, rear_axle_kg) = axle_loads(space, &placed)?;
+    let volume: f64 = placed
+        .iter()
+        .map(|placement| placement.size.volume_m3())
+        .sum();
+
+    Ok(PackingPlan {
+        placements: placed,
+        utilization_pct: 100.0 * volume / bounds.volume_m3(),
+        front_axle_kg,
+        rear_axle_kg,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Factory, Truck};
+
+    fn products(sku: &str, packaging: Packaging, weight: f64, count: usize) -> Vec<Product> {
+        let mut factory = Factory::new("PackFactory".to_string());
+        factory.set_packaging(sku, packaging);
+        factory.set_weight(sku, weight);
+        (0..count)
+            .map(|_| factory.create_sku(sku).unwrap())
+            .collect()
+    }
+
+    fn pallet() -> Packaging {
+        Packaging {
+            dimensions: Dimensions::new(1.2, 0.8, 1.0),
+            upright: true,
+            max_top_load_kg: f64::INFINITY,
+        }
+    }
+
+    #[test]
+    fn test_pallets_fill_floor_then_stack() {
+        let space = CargoSpace::new(2.4, 1.6, 2.0);
+        let plan = pack(&space, &products("pallet", pallet(), 100.0, 8)).unwrap();
+
+        // Кузов заполняется от передней стенки: второй ярус встаёт раньше, чем задние поддоны
+        assert_eq!(plan.placements.len(), 8);
+        assert_eq!(plan.placements[2].z, 1.0);
+        assert_eq!(plan.placements.iter().filter(|p| p.z == 0.0).count(), 4);
+        assert!((plan.utilization_pct - 100.0).abs() < 1e-9);
+        assert_eq!(plan.placements[0].top_load_kg(), 100.0);
+        assert_eq!(plan.weight_kg(), 800.0);
+    }
+
+    #[test]
+    fn test_orientation_and_overflow() {
+        let space = CargoSpace::new(1.0, 1.0, 2.0);
+        let tall = Packaging {
+            dimensions: Dimensions::new(0.5, 0.5, 2.0),
+            upright: false,
+            max_top_load_kg: f64::INFINITY,
+        };
+        // Лежачий груз можно положить на бок
+        let plan = pack(
+            &CargoSpace::new(2.0, 0.5, 0.5),
+            &products("pipe", tall, 5.0, 1),
+        )
+        .unwrap();
+        assert_eq!(plan.placements[0].size, Dimensions::new(2.0, 0.5, 0.5));
+
+        let upright = Packaging {
+            upright: true,
+            ..tall
+        };
+        assert_eq!(
+            pack(
+                &CargoSpace::new(2.0, 0.5, 0.5),
+                &products("pipe", upright, 5.0, 1)
+            ),
+            Err("Cargo does not fit the truck")
+        );
+        assert_eq!(
+            pack(&space, &products("pipe", upright, 5.0, 5)),
+            Err("Cargo does not fit the truck")
+        );
+    }
+
+    #[test]
+    fn test_stacking_limit() {
+        let space = CargoSpace::new(1.2, 0.8, 3.0);
+        let fragile = Packaging {
+            max_top_load_kg: 50.0,
+            ..pallet()
+        };
+
+        assert!(pack(&space, &products("eggs", fragile, 40.0, 2)).is_ok());
+        assert_eq!(
+            pack(&space, &products("eggs", fragile, 60.0, 2)),
+            Err("Cargo does not fit the truck")
+        );
+    }
+
+    #[test]
+    fn test_stacking_limit_counts_whole_column() {
+        let space = CargoSpace::new(1.2, 0.8, 4.0);
+        let fragile = Packaging {
+            max_top_load_kg: 50.0,
+            ..pallet()
+        };
+        let column = |above: usize| {
+            let mut cargo = products("eggs", fragile, 10.0, 1);
+            cargo.extend(products("pallet", pallet(), 20.0, above));
+            pack(&space, &cargo)
+        };
+
+        // Каждый поддон по отдельности лёгкий, но третий сверху перегружает нижний
+        let plan = column(2).unwrap();
+        assert_eq!(plan.placements[0].top_load_kg(), 40.0);
+        assert_eq!(plan.placements[1].top_load_kg(), 20.0);
+        assert_eq!(column(3), Err("Cargo does not fit the truck"));
+    }
+
+    #[test]
+    fn test_axles_at_same_position_are_rejected() {
+        let axles = Axles {
+            front_m: 2.0,
+            rear_m: 2.0,
+            max_front_kg: 500.0,
+            max_rear_kg: 500.0,
+        };
+        let space = CargoSpace::new(4.8, 0.8, 1.0).with_axles(axles);
+        assert_eq!(
+            pack(&space, &products("pallet", pallet(), 100.0, 1)),
+            Err("Axles must be at different positions")
+        );
+    }
+
+    #[test]
+    fn test_axle_balance() {
+        let axles = Axles {
+            front_m: -1.0,
+            rear_m: 5.8,
+            max_front_kg: 520.0,
+            max_rear_kg: 520.0,
+        };
+        let space = CargoSpace::new(4.8, 0.8, 1.0).with_axles(axles);
+
+        // Один тяжёлый поддон у передней стенки перегружает переднюю ось
+        assert_eq!(
+            pack(&space, &products("pallet", pallet(), 700.0, 1)),
+            Err("Axle load out of balance")
+        );
+
+        let plan = pack(&space, &products("pallet", pallet(), 250.0, 4)).unwrap();
+        assert!((plan.front_axle_kg - 500.0).abs() < 1e-9);
+        assert!((plan.rear_axle_kg - 500.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_truck_loads_by_plan() {
+        let space = CargoSpace::new(2.4, 0.8, 1.0);
+        let mut cargo = products("pallet", pallet(), 100.0, 1);
+        cargo.extend(products("crate", pallet(), 50.0, 1));
+        let plan = pack(&space, &cargo).unwrap();
+        let mut truck = Truck::new("Packed".to_string(), 10).with_cargo_space(space);
+
+        truck.apply_plan(plan).unwrap();
+        assert_eq!(
+            truck.load(cargo[1].clone()),
+            Err("Load does not follow the packing plan")
+        );
+        assert_eq!(truck.load(cargo[0].clone()), Ok(()));
+        assert_eq!(truck.load(cargo[1].clone()), Ok(()));
+        assert_eq!(
+            truck.load(cargo[1].clone()),
+            Err("Load is not in the packing plan")
+        );
+        assert_eq!(
+            truck.apply_plan(pack(&space, &cargo[..1]).unwrap()),
+            Err("Truck must be empty to apply a packing plan")
+        );
+
+        // Разгруженный грузовик забывает старый план
+        truck.unload().unwrap();
+        truck.unload().unwrap();
+        assert!(truck.packing_plan().is_none());
+    }
+
+    #[test]
+    fn test_plan_must_match_truck() {
+        let trailer = CargoSpace::new(4.8, 0.8, 1.0);
+        let cargo = products("pallet", pallet(), 250.0, 4);
+        let plan = pack(&trailer, &cargo).unwrap();
+
+        let mut bare = Truck::new("Bare".to_string(), 10);
+        assert_eq!(
+            bare.apply_plan(plan.clone()),
+            Err("Truck has no cargo space")
+        );
+
+        // План для длинного прицепа не влезает в короткий кузов
+        let mut short =
+            Truck::new("Short".to_string(), 10).with_cargo_space(CargoSpace::new(2.4, 0.8, 1.0));
+        assert_eq!(
+            short.apply_plan(plan.clone()),
+            Err("Cargo does not fit the truck")
+        );
+
+        let weak_axles = Axles {
+            front_m: -1.0,
+            rear_m: 5.8,
+            max_front_kg: 400.0,
+            max_rear_kg: 400.0,
+        };
+        let mut weak =
+            Truck::new("Weak".to_string(), 10).with_cargo_space(trailer.with_axles(weak_axles));
+        assert_eq!(
+            weak.apply_plan(plan.clone()),
+            Err("Axle load out of balance")
+        );
+
+        let mut light = Truck::new("Light".to_string(), 10)
+            .with_cargo_space(trailer)
+            .with_max_payload(900.0);
+        assert_eq!(
+            light.apply_plan(plan.clone()),
+            Err("Payload limit exceeded")
+        );
+
+        let mut fitting = Truck::new("Fitting".to_string(), 10).with_cargo_space(trailer);
+        assert_eq!(fitting.apply_plan(plan), Ok(()));
+    }
+}
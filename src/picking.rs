@@ -0,0 +1,445 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::layout::{BinId, Layout};
+use crate::models::Warehouse;
+use crate::orders::{OrderBook, OrderStatus};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Grouping {
+    // Несколько заказов в одну тележку, не больше max_units единиц
+    Batch { max_units: usize },
+    // Заказы с близким сроком отгрузки в одну волну
+    Wave { hours: f64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskStatus {
+    Open,
+    Confirmed,
+    Short,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickTask {
+    pub order: u64,
+    pub sku: String,
+    pub bin: BinId,
+    pub quantity: usize,
+    pub picked: usize,
+    pub status: TaskStatus,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickException {
+    pub order: u64,
+    pub sku: String,
+    pub bin: BinId,
+    pub expected: usize,
+    pub substituted: usize,
+    pub backordered: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PickList {
+    pub id: usize,
+    pub orders: Vec<u64>,
+    pub tasks: Vec<PickTask>,
+}
+
+impl PickList {
+    pub fn units(&self) -> usize {
+        self.tasks.iter().map(|task| task.quantity).sum()
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.tasks
+            .iter()
+            .all(|task| task.status != TaskStatus::Open)
+    }
+
+    pub fn confirmed(&self) -> usize {
+        self.tasks
+            .iter()
+            .filter(|task| task.status == TaskStatus::Confirmed)
+            .count()
+    }
+
+    // Змейка по проходам: первый проход идём вглубь, следующий обратно и так далее
+    pub fn sequence(&mut self, layout: &Layout) {
+        let mut aisles: Vec<u32> = self
+            .tasks
+            .iter()
+            .filter_map(|task| layout.bin(task.bin).map(|bin| bin.aisle()))
+            .collect();
+        aisles.sort();
+        aisles.dedup();
+        let position = |id: BinId| {
+            layout.bin(id).map_or((u32::MAX, 0), |bin| {
+                let slot = if aisles.binary_search(&bin.aisle()).unwrap_or(0) % 2 == 0 {
+                    bin.slot() as i64
+                } else {
+                    -(bin.slot() as i64)
+                };
+                (bin.aisle(), slot)
+            })
+        };
+        self.tasks.sort_by(|a, b| {
+            position(a.bin)
+                .cmp(&position(b.bin))
+                .then(a.order.cmp(&b.order))
+        });
+    }
+
+    // Путь от ворот через все ячейки списка и обратно
+    pub fn travel_m(&self, layout: &Layout) -> f64 {
+        let mut travel = 0.0;
+        let mut previous: Option<BinId> = None;
+        for task in &self.tasks {
+            let Some(bin) = layout.bin(task.bin) else {
+                continue;
+            };
+            travel += match previous.and_then(|id| layout.bin(id)) {
+                Some(from) => from.distance_to(bin),
+                None => bin.distance_m(),
+            };
+            previous = Some(task.bin);
+        }
+        travel
+            + previous
+                .and_then(|id| layout.bin(id))
+                .map_or(0.0, |bin| bin.distance_m())
+    }
+}
+
+impl OrderBook {
+    // Собирает зарезервированные, но ещё не собранные строки заказов в листы отбора
+    pub fn pick_lists(&self, warehouse: &Warehouse, grouping: Grouping) -> Vec<PickList> {
+        let layout = warehouse.layout();
+        let mut remaining: HashMap<String, Vec<(BinId, usize)>> = HashMap::new();
+        let mut groups: BTreeMap<i64, Vec<u64>> = BTreeMap::new();
+        let mut tasks: HashMap<u64, Vec<PickTask>> = HashMap::new();
+        let mut units = 0;
+        let mut batch = 0;
+
+        for id in self.open_orders() {
+            let Some(order) = self.order(id) else {
+                continue;
+            };
+            if order.lines.iter().all(|line| line.picked == line.allocated) {
+                continue;
+            }
+            let mut order_tasks = Vec::new();
+            for line in &order.lines {
+                let mut needed = line.allocated - line.picked;
+                let bins = remaining.entry(line.sku.clone()).or_insert_with(|| {
+                    let mut bins = layout.locate(&line.sku);
+                    bins.sort_by(|a, b| {
+                        let distance = |id: BinId| {
+                            layout.bin(id).map_or(f64::INFINITY, |bin| bin.distance_m())
+                        };
+                        distance(a.0).total_cmp(&distance(b.0)).then(a.0.cmp(&b.0))
+                    });
+                    bins
+                });
+                for (bin, count) in bins.iter_mut() {
+                    if needed == 0 {
+                        break;
+                    }
+                    let take = needed.min(*count);
+                    if take == 0 {
+                        continue;
+                    }
+                    *count -= take;
+                    needed -= take;
+                    order_tasks.push(PickTask {
+                        order: id,
+                        sku: line.sku.clone(),
+                        bin: *bin,
+                        quantity: take,
+                        picked: 0,
+                        status: TaskStatus::Open,
+                    });
+                }
+            }
+            if order_tasks.is_empty() {
+                continue;
+            }
+            let order_units: usize = order_tasks.iter().map(|task| task.quantity).sum();
+            let key = match grouping {
+                Grouping::Batch { max_units } => {
+                    if units > 0 && units + order_units > max_units {
+                        batch += 1;
+                        units = 0;
+                    }
+                    units += order_units;
+                    batch
+                }
+                Grouping::Wave { hours } => (order.due / hours).floor() as i64,
+            };
+            groups.entry(key).or_default().push(id);
+            tasks.insert(id, order_tasks);
+        }
+
+        groups
+            .into_values()
+            .enumerate()
+            .map(|(index, orders)| {
+                let mut list = PickList {
+                    id: index + 1,
+                    tasks: orders
+                        .iter()
+                        .flat_map(|id| tasks.remove(id).unwrap_or_default())
+                        .collect(),
+                    orders,
+                };
+                list.sequence(layout);
+                list
+            })
+            .collect()
+    }
+
+    // Проходит лист по порядку; недостачу в ячейке добирает из других ячеек,
+    // а то, что найти не удалось, снимает с резерва и возвращает в бэкордер
+    pub fn execute(
+        &mut self,
+        list: &mut PickList,
+        warehouse: &mut Warehouse,
+    ) -> Result<Vec<PickException>, &'static str> {
+        let mut exceptions = Vec::new();
+        for task in list
+            .tasks
+            .iter_mut()
+            .filter(|task| task.status == TaskStatus::Open)
+        {
+            while task.picked < task.quantity {
+                let Ok(mut product) = warehouse.unload_reserved_from(task.bin, &task.sku) else {
+                    break;
+                };
+                product.order = Some(task.order);
+                self.stage(task.order, product);
+                task.picked += 1;
+            }
+            let order = self.order_mut(task.order)?;
+            if let Some(line) = order.lines.iter_mut().find(|line| line.sku == task.sku) {
+                line.picked += task.picked;
+            }
+            if task.picked == task.quantity {
+                task.status = TaskStatus::Confirmed;
+                order.advance_to(OrderStatus::Picked);
+                continue;
+            }
+
+            task.status = TaskStatus::Short;
+            let mut exception = PickException {
+                order: task.order,
+                sku: task.sku.clone(),
+                bin: task.bin,
+                expected: task.quantity,
+                substituted: 0,
+                backordered: 0,
+            };
+            for _ in task.picked..task.quantity {
+                match warehouse.unload_reserved(&task.sku) {
+                    Ok(mut product) => {
+                        product.order = Some(task.order);
+                        self.stage(task.order, product);
+                        exception.substituted += 1;
+                    }
+                    Err(_) => exception.backordered += 1,
+                }
+            }
+            if exception.backordered > 0 {
+                warehouse.release(&task.sku, exception.backordered)?;
+            }
+            let order = self.order_mut(task.order)?;
+            if let Some(line) = order.lines.iter_mut().find(|line| line.sku == task.sku) {
+                line.picked += exception.substituted;
+                line.allocated -= exception.backordered;
+            }
+            if exception.substituted > 0 || task.picked > 0 {
+                order.advance_to(OrderStatus::Picked);
+            }
+            exceptions.push(exception);
+        }
+        Ok(exceptions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{Bin, Zone, ZoneKind};
+    use crate::models::Factory;
+    use crate::network::NodeId;
+    use crate::orders::OrderLine;
+
+    // Болты у входа в проход 1 и в проходе 2, гайки в глубине прохода 1
+    fn warehouse() -> Warehouse {
+        let mut warehouse = Warehouse::from_layout(Layout::new(vec![
+            Zone::new(ZoneKind::Bulk)
+                .with_bin(Bin::new(3, 1, 0))
+                .with_bin(Bin::new(3, 1, 5))
+                .with_bin(Bin::new(3, 2, 4)),
+        ]));
+        let mut factory = Factory::new("PickFactory".to_string());
+        for sku in ["bolt", "bolt", "bolt", "nut", "nut", "nut", "bolt", "bolt"] {
+            warehouse.load(factory.create_sku(sku).unwrap()).unwrap();
+        }
+        warehouse
+    }
+
+    fn bin(index: usize) -> BinId {
+        BinId {
+            zone: 0,
+            bin: index,
+        }
+    }
+
+    fn allocated(
+        book: &mut OrderBook,
+        warehouse: &mut Warehouse,
+        lines: Vec<OrderLine>,
+        due: f64,
+    ) -> u64 {
+        let id = book.place("Acme", lines, due, 0, NodeId(0));
+        book.allocate(id, warehouse).unwrap();
+        id
+    }
+
+    #[test]
+    fn test_batch_grouping_by_units() {
+        let mut book = OrderBook::new();
+        let mut warehouse = warehouse();
+        let first = allocated(
+            &mut book,
+            &mut warehouse,
+            vec![OrderLine::new("bolt", 2)],
+            1.0,
+        );
+        let second = allocated(
+            &mut book,
+            &mut warehouse,
+            vec![OrderLine::new("nut", 2)],
+            2.0,
+        );
+        let third = allocated(
+            &mut book,
+            &mut warehouse,
+            vec![OrderLine::new("bolt", 2)],
+            3.0,
+        );
+
+        let lists = book.pick_lists(&warehouse, Grouping::Batch { max_units: 4 });
+        assert_eq!(lists.len(), 2);
+        assert_eq!(lists[0].orders, vec![first, second]);
+        assert_eq!(lists[1].orders, vec![third]);
+        assert_eq!(lists[0].units(), 4);
+        // Третий заказ берёт болты там, где их не забрал первый
+        assert_eq!(lists[1].tasks[0].bin, bin(0));
+        assert_eq!(lists[1].tasks[1].bin, bin(2));
+    }
+
+    #[test]
+    fn test_wave_grouping_by_due() {
+        let mut book = OrderBook::new();
+        let mut warehouse = warehouse();
+        let morning = allocated(
+            &mut book,
+            &mut warehouse,
+            vec![OrderLine::new("bolt", 1)],
+            2.0,
+        );
+        let evening = allocated(
+            &mut book,
+            &mut warehouse,
+            vec![OrderLine::new("nut", 1)],
+            13.0,
+        );
+        let noon = allocated(
+            &mut book,
+            &mut warehouse,
+            vec![OrderLine::new("bolt", 1)],
+            5.0,
+        );
+
+        let lists = book.pick_lists(&warehouse, Grouping::Wave { hours: 8.0 });
+        assert_eq!(lists.len(), 2);
+        assert_eq!(lists[0].orders, vec![morning, noon]);
+        assert_eq!(lists[1].orders, vec![evening]);
+    }
+
+    #[test]
+    fn test_sequence_follows_serpentine() {
+        let mut book = OrderBook::new();
+        let mut warehouse = warehouse();
+        allocated(
+            &mut book,
+            &mut warehouse,
+            vec![OrderLine::new("nut", 3), OrderLine::new("bolt", 5)],
+            1.0,
+        );
+
+        let list = &book.pick_lists(&warehouse, Grouping::Batch { max_units: 10 })[0];
+        let bins: Vec<BinId> = list.tasks.iter().map(|task| task.bin).collect();
+        // Проход 1 проходим вглубь, проход 2 — обратно к воротам
+        assert_eq!(bins, vec![bin(0), bin(1), bin(2)]);
+        assert!((list.travel_m(warehouse.layout()) - 24.0).abs() < 1e-9);
+
+        let mut unsorted = list.clone();
+        unsorted.tasks.swap(0, 1);
+        assert!(list.travel_m(warehouse.layout()) < unsorted.travel_m(warehouse.layout()));
+    }
+
+    #[test]
+    fn test_execute_confirms_tasks() {
+        let mut book = OrderBook::new();
+        let mut warehouse = warehouse();
+        let id = allocated(
+            &mut book,
+            &mut warehouse,
+            vec![OrderLine::new("bolt", 4)],
+            1.0,
+        );
+
+        let mut list = book
+            .pick_lists(&warehouse, Grouping::Batch { max_units: 10 })
+            .remove(0);
+        assert_eq!(book.execute(&mut list, &mut warehouse), Ok(vec![]));
+        assert!(list.is_done());
+        assert_eq!(list.confirmed(), 2);
+        assert_eq!(book.staged(id), 4);
+        assert_eq!(book.status(id), Some(OrderStatus::Picked));
+        assert_eq!(warehouse.stock("bolt").reserved, 0);
+        assert_eq!(warehouse.handling().picks, 4);
+    }
+
+    #[test]
+    fn test_short_pick_is_substituted() {
+        let mut book = OrderBook::new();
+        let mut warehouse = warehouse();
+        let id = allocated(
+            &mut book,
+            &mut warehouse,
+            vec![OrderLine::new("bolt", 2)],
+            1.0,
+        );
+        let mut list = book
+            .pick_lists(&warehouse, Grouping::Batch { max_units: 10 })
+            .remove(0);
+        assert_eq!(list.tasks[0].bin, bin(0));
+
+        // Пока лист ждал, из ячейки забрали свободные болты
+        for _ in 0..2 {
+            warehouse.unload_sku("bolt").unwrap();
+        }
+
+        let exceptions = book.execute(&mut list, &mut warehouse).unwrap();
+        assert_eq!(exceptions.len(), 1);
+        assert_eq!(exceptions[0].expected, 2);
+        assert_eq!(exceptions[0].substituted, 1);
+        assert_eq!(exceptions[0].backordered, 0);
+        assert_eq!(list.tasks[0].status, TaskStatus::Short);
+        assert_eq!(book.staged(id), 2);
+        assert_eq!(book.order(id).unwrap().lines[0].picked, 2);
+    }
+}
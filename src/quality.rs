@@ -0,0 +1,115 @@
+use crate::models::{Product, Truck, Warehouse};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefectAction {
+    Rework,
+    Scrap,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct LotStats {
+    pub produced: usize,
+    pub passed: usize,
+    pub reworked: usize,
+    pub scrapped: usize,
+}
+
+impl LotStats {
+    pub fn defects(&self) -> usize {
+        self.reworked + self.scrapped
+    }
+
+    pub fn defect_rate(&self) -> f64 {
+        if self.produced == 0 {
+            return 0.0;
+        }
+        self.defects() as f64 / self.produced as f64
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Holder {
+    Warehouse(usize),
+    Truck(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecallHit {
+    pub holder: Holder,
+    pub position: usize,
+    pub sku: String,
+}
+
+fn in_lot(product: &Product, origin: &str, lot: u32) -> bool {
+    product.origin == origin && product.lot == lot
+}
+
+pub fn recall(origin: &str, lot: u32, warehouses: &[&Warehouse], trucks: &[&Truck]) -> Vec<RecallHit> {
+    let mut hits = Vec::new();
+    for (index, warehouse) in warehouses.iter().enumerate() {
+        for (position, product) in warehouse.cargo().enumerate() {
+            if in_lot(product, origin, lot) {
+                hits.push(RecallHit {
+                    holder: Holder::Warehouse(index),
+                    position,
+                    sku: product.sku.clone(),
+                });
+            }
+        }
+    }
+    for (index, truck) in trucks.iter().enumerate() {
+        for (position, product) in truck.cargo().enumerate() {
+            if in_lot(product, origin, lot) {
+                hits.push(RecallHit {
+                    holder: Holder::Truck(index),
+                    position,
+                    sku: product.sku.clone(),
+                });
+            }
+        }
+    }
+    hits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Factory;
+
+    #[test]
+    fn test_lot_stats_defect_rate() {
+        let stats = LotStats {
+            produced: 10,
+            passed: 7,
+            reworked: 2,
+            scrapped: 1,
+        };
+
+        assert_eq!(stats.defects(), 3);
+        assert!((stats.defect_rate() - 0.3).abs() < 1e-9);
+        assert_eq!(LotStats::default().defect_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_recall_finds_lot_everywhere() {
+        let mut factory = Factory::new("RecallFactory".to_string());
+        let mut warehouse = Warehouse::new(5);
+        let mut truck = Truck::new("RecallTruck".to_string(), 5);
+
+        // Лот 1: один продукт на складе, один в грузовике
+        warehouse.load(factory.create().unwrap()).unwrap();
+        truck.load(factory.create().unwrap()).unwrap();
+
+        // Лот 2 не должен попасть в отзыв
+        factory.next_lot();
+        warehouse.load(factory.create().unwrap()).unwrap();
+
+        let hits = recall("RecallFactory", 1, &[&warehouse], &[&truck]);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].holder, Holder::Warehouse(0));
+        assert_eq!(hits[0].position, 0);
+        assert_eq!(hits[1].holder, Holder::Truck(0));
+
+        assert!(recall("OtherFactory", 1, &[&warehouse], &[&truck]).is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+use crate::random::{Distribution, Exponential, Rng};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    Operational,
+    Broken,
+    UnderMaintenance,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct AvailabilityStats {
+    pub operational_hours: f64,
+    pub broken_hours: f64,
+    pub maintenance_hours: f64,
+    pub failures: usize,
+}
+
+impl AvailabilityStats {
+    pub fn total_hours(&self) -> f64 {
+        self.operational_hours + self.broken_hours + self.maintenance_hours
+    }
+
+    pub fn availability(&self) -> f64 {
+        let total = self.total_hours();
+        if total == 0.0 {
+            return 1.0;
+        }
+        self.operational_hours / total
+    }
+}
+
+pub struct Reliability {
+    mtbf: f64,
+    mttr: f64,
+    rng: Rng,
+    condition: Condition,
+    clock: f64,
+    until_failure: f64,
+    until_repaired: f64,
+    maintenance: Vec<(f64, f64)>,
+    stats: AvailabilityStats,
+}
+
+impl Reliability {
+    pub fn new(mtbf: f64, mttr: f64, seed: u64) -> Result<Self, &'static str> {
+        // При нулевой наработке цикл отказ-ремонт не продвигает время
+        if mtbf.is_nan() || mtbf <= 0.0 {
+            return Err("MTBF must be positive");
+        }
+        if mttr.is_nan() || mttr < 0.0 {
+            return Err("MTTR must not be negative");
+        }
+        let mut reliability = Self {
+            mtbf,
+            mttr,
+            rng: Rng::new(seed),
+            condition: Condition::Operational,
+            clock: 0.0,
+            until_failure: f64::INFINITY,
+            until_repaired: 0.0,
+            maintenance: Vec::new(),
+            stats: AvailabilityStats::default(),
+        };
+        reliability.until_failure = reliability.sample(mtbf);
+        Ok(reliability)
+    }
+
+    pub fn never_fails() -> Self {
+        Self::new(f64::INFINITY, 0.0, 0).expect("infinite MTBF is valid")
+    }
+
+    fn sample(&mut self, mean: f64) -> f64 {
+        Exponential { mean }.sample(&mut self.rng)
+    }
+
+    pub fn set_rng(&mut self, rng: Rng) {
+        self.rng = rng;
+        if self.condition == Condition::Operational {
+            self.until_failure = self.sample(self.mtbf);
+        }
+    }
+
+    pub fn condition(&self) -> Condition {
+        self.condition
+    }
+
+    pub fn clock(&self) -> f64 {
+        self.clock
+    }
+
+    pub fn stats(&self) -> AvailabilityStats {
+        self.stats
+    }
+
+    pub fn schedule_maintenance(&mut self, start: f64, duration: f64) {
+        self.maintenance.push((start, start + duration));
+        self.maintenance.sort_by(|a, b| a.0.total_cmp(&b.0));
+    }
+
+    pub fn break_down(&mut self) {
+        self.condition = Condition::Broken;
+        self.stats.failures += 1;
+        self.until_repaired = self.sample(self.mttr);
+    }
+
+    fn active_window(&self) -> Option<f64> {
+        self.maintenance
+            .iter()
+            .find(|(start, end)| *start <= self.clock && self.clock < *end)
+            .map(|(_, end)| *end)
+    }
+
+    fn next_window_start(&self) -> f64 {
+        self.maintenance
+            .iter()
+            .map(|(start, _)| *start)
+            .find(|start| *start > self.clock)
+            .unwrap_or(f64::INFINITY)
+    }
+
+    pub fn advance(&mut self, dt: f64) {
+        let end = self.clock + dt;
+        while self.clock < end {
+            if self.condition != Condition::Broken {
+                if let Some(window_end) = self.active_window() {
+                    self.condition = Condition::UnderMaintenance;
+                    let until = window_end.min(end);
+                    self.stats.maintenance_hours += until - self.clock;
+                    self.clock = until;
+                    if self.clock >= window_end {
+                        // Профилактика обновляет ресурс до отказа
+                        self.condition = Condition::Operational;
+                        self.until_failure = self.sample(self.mtbf);
+                    }
+                    continue;
+                }
+                self.condition = Condition::Operational;
+            }
+            match self.condition {
+                Condition::Operational => {
+                    let failure_at = self.clock + self.until_failure;
+                    let until = end.min(failure_at).min(self.next_window_start());
+                    let elapsed = until - self.clock;
+                    self.stats.operational_hours += elapsed;
+                    self.until_failure -= elapsed;
+                    self.clock = until;
+                    if until >= failure_at {
+                        self.break_down();
+                    }
+                }
+                Condition::Broken => {
+                    let repaired_at = self.clock + self.until_repaired;
+                    let until = end.min(repaired_at);
+                    let elapsed = until - self.clock;
+                    self.stats.broken_hours += elapsed;
+                    self.until_repaired -= elapsed;
+                    self.clock = until;
+                    if until >= repaired_at {
+                        self.condition = Condition::Operational;
+                        self.until_failure = self.sample(self.mtbf);
+                    }
+                }
+                Condition::UnderMaintenance => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_never_fails_is_always_available() {
+        let mut reliability = Reliability::never_fails();
+
+        reliability.advance(1000.0);
+        assert_eq!(reliability.condition(), Condition::Operational);
+        assert_eq!(reliability.stats().failures, 0);
+        assert_eq!(reliability.stats().availability(), 1.0);
+    }
+
+    #[test]
+    fn test_maintenance_window() {
+        let mut reliability = Reliability::never_fails();
+        reliability.schedule_maintenance(10.0, 5.0);
+
+        reliability.advance(12.0);
+        assert_eq!(reliability.condition(), Condition::UnderMaintenance);
+
+        reliability.advance(8.0);
+        assert_eq!(reliability.condition(), Condition::Operational);
+
+        let stats = reliability.stats();
+        assert_eq!(stats.maintenance_hours, 5.0);
+        assert_eq!(stats.operational_hours, 15.0);
+        assert_eq!(stats.availability(), 0.75);
+    }
+
+    #[test]
+    fn test_breakdown_and_repair() {
+        let mut reliability = Reliability::new(f64::INFINITY, 2.0, 3).unwrap();
+
+        reliability.break_down();
+        assert_eq!(reliability.condition(), Condition::Broken);
+
+        // Экспоненциальный ремонт рано или поздно завершается
+        reliability.advance(1000.0);
+        assert_eq!(reliability.condition(), Condition::Operational);
+        assert_eq!(reliability.stats().failures, 1);
+        assert!(reliability.stats().broken_hours > 0.0);
+        assert_eq!(reliability.stats().total_hours(), 1000.0);
+    }
+
+    #[test]
+    fn test_rejects_degenerate_failure_rates() {
+        assert!(Reliability::new(0.0, 0.0, 1).is_err());
+        assert!(Reliability::new(f64::NAN, 1.0, 1).is_err());
+        assert_eq!(
+            Reliability::new(10.0, -1.0, 1).err(),
+            Some("MTTR must not be negative")
+        );
+    }
+
+    #[test]
+    fn test_availability_tracks_mtbf_and_mttr() {
+        let mut reliability = Reliability::new(90.0, 10.0, 11).unwrap();
+
+        reliability.advance(200_000.0);
+        let availability = reliability.stats().availability();
+
+        // Теоретическое значение MTBF / (MTBF + MTTR) = 0.9
+        assert!((availability - 0.9).abs() < 0.02, "{}", availability);
+        assert!(reliability.stats().failures > 100);
+    }
+}
@@ -0,0 +1,438 @@
+use std::collections::VecDeque;
+
+use crate::inventory::StockLevel;
+use crate::models::Warehouse;
+use crate::random::{Distribution, Rng};
+
+// Формула Уилсона: партия, при которой затраты на заказы и хранение равны
+pub fn eoq(demand_per_day: f64, order_cost: f64, holding_cost_per_unit_day: f64) -> f64 {
+    if demand_per_day <= 0.0 || holding_cost_per_unit_day <= 0.0 {
+        return 0.0;
+    }
+    (2.0 * demand_per_day * order_cost / holding_cost_per_unit_day).sqrt()
+}
+
+// Позиция запасов: на руках плюс заказанное и в пути минус зарезервированное
+pub fn position(level: &StockLevel) -> i64 {
+    (level.on_hand + level.on_order + level.in_transit) as i64 - level.reserved as i64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Policy {
+    ReorderPoint {
+        reorder_point: usize,
+        quantity: usize,
+    },
+    PeriodicReview {
+        period_hours: f64,
+        order_up_to: usize,
+    },
+    MinMax {
+        min: usize,
+        max: usize,
+    },
+}
+
+impl Policy {
+    // (s,Q) с партией по EOQ и точкой заказа на спрос за время поставки
+    pub fn economic(
+        demand_per_day: f64,
+        order_cost: f64,
+        holding_cost_per_unit_day: f64,
+        lead_time_days: f64,
+        safety_stock: usize,
+    ) -> Self {
+        let quantity = eoq(demand_per_day, order_cost, holding_cost_per_unit_day)
+            .round()
+            .max(1.0) as usize;
+        let reorder_point = (demand_per_day * lead_time_days).ceil() as usize + safety_stock;
+        Policy::ReorderPoint {
+            reorder_point,
+            quantity,
+        }
+    }
+
+    pub fn is_due(&self, now: f64, last_review: Option<f64>) -> bool {
+        match self {
+            Policy::PeriodicReview { period_hours, .. } => {
+                last_review.is_none_or(|last| now - last + 1e-9 >= *period_hours)
+            }
+            _ => true,
+        }
+    }
+
+    pub fn order_quantity(&self, position: i64) -> usize {
+        match *self {
+            Policy::ReorderPoint {
+                reorder_point,
+                quantity,
+            } => {
+                let reorder_point = reorder_point as i64;
+                if position > reorder_point {
+                    return 0;
+                }
+                // Целое число партий, чтобы позиция поднялась выше точки заказа
+                let quantity = quantity.max(1);
+                let lots = (reorder_point - position) / quantity as i64 + 1;
+                lots as usize * quantity
+            }
+            Policy::PeriodicReview { order_up_to, .. } => {
+                (order_up_to as i64 - position).max(0) as usize
+            }
+            Policy::MinMax { min, max } => {
+                if position < min as i64 {
+                    (max as i64 - position).max(0) as usize
+                } else {
+                    0
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Supplier {
+    Factory(usize),
+    Warehouse(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplenishmentOrder {
+    pub id: u64,
+    pub warehouse: usize,
+    pub sku: String,
+    pub quantity: usize,
+    pub supplier: Supplier,
+    pub placed: f64,
+}
+
+impl ReplenishmentOrder {
+    pub fn is_production(&self) -> bool {
+        matches!(self.supplier, Supplier::Factory(_))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub warehouse: usize,
+    pub sku: String,
+    pub policy: Policy,
+    pub supplier: Supplier,
+    last_review: Option<f64>,
+}
+
+pub struct Replenishment {
+    rules: Vec<Rule>,
+    orders: Vec<ReplenishmentOrder>,
+    next_id: u64,
+    check_every_hours: f64,
+}
+
+impl Replenishment {
+    pub fn new() -> Self {
+        Self {
+            rules: Vec::new(),
+            orders: Vec::new(),
+            next_id: 1,
+            check_every_hours: 1.0,
+        }
+    }
+
+    pub fn with_check_every(mut self, hours: f64) -> Self {
+        self.check_every_hours = hours;
+        self
+    }
+
+    pub fn check_every_hours(&self) -> f64 {
+        self.check_every_hours
+    }
+
+    pub fn add_rule(
+        &mut self,
+        warehouse: usize,
+        sku: &str,
+        policy: Policy,
+        supplier: Supplier,
+    ) -> usize {
+        self.rules.push(Rule {
+            warehouse,
+            sku: sku.to_string(),
+            policy,
+            supplier,
+            last_review: None,
+        });
+        self.rules.len() - 1
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn orders(&self) -> &[ReplenishmentOrder] {
+        &self.orders
+    }
+
+    // Заказанное сразу числится на складе, чтобы не заказать повторно;
+    // в путь оно переходит только при отгрузке
+    pub fn review(
+        &mut self,
+        index: usize,
+        warehouse: &mut Warehouse,
+        now: f64,
+    ) -> Vec<ReplenishmentOrder> {
+        let mut placed = Vec::new();
+        for rule in self.rules.iter_mut().filter(|rule| rule.warehouse == index) {
+            if !rule.policy.is_due(now, rule.last_review) {
+                continue;
+            }
+            rule.last_review = Some(now);
+            let quantity = rule
+                .policy
+                .order_quantity(position(&warehouse.stock(&rule.sku)));
+            if quantity == 0 {
+                continue;
+            }
+            warehouse.order(&rule.sku, quantity);
+            let order = ReplenishmentOrder {
+                id: self.next_id,
+                warehouse: index,
+                sku: rule.sku.clone(),
+                quantity,
+                supplier: rule.supplier,
+                placed: now,
+            };
+            self.next_id += 1;
+            self.orders.push(order.clone());
+            placed.push(order);
+        }
+        placed
+    }
+}
+
+impl Default for Replenishment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InventoryCosts {
+    pub order_cost: f64,
+    pub holding_per_unit_day: f64,
+    pub stockout_per_unit: f64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PolicyOutcome {
+    pub days: usize,
+    pub demand: usize,
+    pub lost: usize,
+    pub stockout_days: usize,
+    pub orders: usize,
+    pub average_on_hand: f64,
+    pub holding_cost: f64,
+    pub ordering_cost: f64,
+    pub stockout_cost: f64,
+}
+
+impl PolicyOutcome {
+    pub fn fill_rate(&self) -> f64 {
+        if self.demand == 0 {
+            return 1.0;
+        }
+        (self.demand - self.lost) as f64 / self.demand as f64
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.holding_cost + self.ordering_cost + self.stockout_cost
+    }
+}
+
+// Дневная модель одного SKU: неудовлетворённый спрос теряется,
+// заказ приходит утром через lead_time_days (не раньше следующего дня)
+pub fn simulate(
+    policy: &Policy,
+    demand: &dyn Distribution,
+    rng: &mut Rng,
+    initial: usize,
+    lead_time_days: usize,
+    days: usize,
+    costs: &InventoryCosts,
+) -> PolicyOutcome {
+    let lead = lead_time_days.max(1);
+    let mut on_hand = initial;
+    let mut pipeline: VecDeque<(usize, usize)> = VecDeque::new();
+    let mut last_review = None;
+    let mut held = 0.0;
+    let mut outcome = PolicyOutcome {
+        days,
+        ..PolicyOutcome::default()
+    };
+
+    for day in 0..days {
+        while pipeline.front().is_some_and(|(arrival, _)| *arrival <= day) {
+            let (_, quantity) = pipeline.pop_front().expect("checked front");
+            on_hand += quantity;
+        }
+
+        let wanted = demand.sample(rng).round().max(0.0) as usize;
+        let served = wanted.min(on_hand);
+        on_hand -= served;
+        outcome.demand += wanted;
+        if served < wanted {
+            outcome.lost += wanted - served;
+            outcome.stockout_days += 1;
+        }
+
+        let now = day as f64 * 24.0;
+        if policy.is_due(now, last_review) {
+            last_review = Some(now);
+            let on_order: usize = pipeline.iter().map(|(_, quantity)| quantity).sum();
+            let quantity = policy.order_quantity((on_hand + on_order) as i64);
+            if quantity > 0 {
+                pipeline.push_back((day + lead, quantity));
+                outcome.orders += 1;
+            }
+        }
+        held += on_hand as f64;
+    }
+
+    if days > 0 {
+        outcome.average_on_hand = held / days as f64;
+    }
+    outcome.holding_cost = held * costs.holding_per_unit_day;
+    outcome.ordering_cost = outcome.orders as f64 * costs.order_cost;
+    outcome.stockout_cost = outcome.lost as f64 * costs.stockout_per_unit;
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Factory;
+    use crate::random::{Constant, Poisson};
+
+    fn costs() -> InventoryCosts {
+        InventoryCosts {
+            order_cost: 50.0,
+            holding_per_unit_day: 0.1,
+            stockout_per_unit: 5.0,
+        }
+    }
+
+    #[test]
+    fn test_eoq_and_economic_policy() {
+        assert_eq!(eoq(10.0, 50.0, 0.1), 100.0);
+        assert_eq!(eoq(0.0, 50.0, 0.1), 0.0);
+        assert_eq!(
+            Policy::economic(10.0, 50.0, 0.1, 3.0, 5),
+            Policy::ReorderPoint {
+                reorder_point: 35,
+                quantity: 100
+            }
+        );
+    }
+
+    #[test]
+    fn test_policies_compute_order_quantity() {
+        let fixed = Policy::ReorderPoint {
+            reorder_point: 20,
+            quantity: 30,
+        };
+        assert_eq!(fixed.order_quantity(21), 0);
+        assert_eq!(fixed.order_quantity(20), 30);
+        // Отставание больше партии — заказываем две
+        assert_eq!(fixed.order_quantity(-15), 60);
+
+        let periodic = Policy::PeriodicReview {
+            period_hours: 24.0,
+            order_up_to: 50,
+        };
+        assert_eq!(periodic.order_quantity(35), 15);
+        assert!(periodic.is_due(0.0, None));
+        assert!(!periodic.is_due(12.0, Some(0.0)));
+        assert!(periodic.is_due(24.0, Some(0.0)));
+
+        let min_max = Policy::MinMax { min: 10, max: 40 };
+        assert_eq!(min_max.order_quantity(10), 0);
+        assert_eq!(min_max.order_quantity(9), 31);
+    }
+
+    #[test]
+    fn test_review_orders_once_for_warehouse_position() {
+        let mut factory = Factory::new("Plant".to_string());
+        let mut warehouse = Warehouse::new(100);
+        for _ in 0..5 {
+            warehouse.load(factory.create().unwrap()).unwrap();
+        }
+        let mut planner = Replenishment::new();
+        planner.add_rule(
+            0,
+            "product",
+            Policy::ReorderPoint {
+                reorder_point: 10,
+                quantity: 20,
+            },
+            Supplier::Factory(0),
+        );
+        planner.add_rule(
+            0,
+            "bolt",
+            Policy::MinMax { min: 1, max: 4 },
+            Supplier::Warehouse(1),
+        );
+
+        let orders = planner.review(0, &mut warehouse, 0.0);
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].quantity, 20);
+        assert!(orders[0].is_production());
+        assert_eq!(orders[1].quantity, 4);
+        assert_eq!(warehouse.stock("product").on_order, 20);
+        assert_eq!(warehouse.stock("product").in_transit, 0);
+
+        // Заказанное уже в позиции запасов, повторного заказа нет
+        assert!(planner.review(0, &mut warehouse, 1.0).is_empty());
+        assert!(planner.review(1, &mut warehouse, 1.0).is_empty());
+        assert_eq!(planner.orders().len(), 2);
+    }
+
+    #[test]
+    fn test_simulation_trades_stockouts_for_holding_cost() {
+        let demand = Poisson { mean: 10.0 };
+        let run =
+            |policy: Policy| simulate(&policy, &demand, &mut Rng::new(7), 60, 3, 365, &costs());
+
+        let lean = run(Policy::ReorderPoint {
+            reorder_point: 15,
+            quantity: 100,
+        });
+        let safe = run(Policy::economic(10.0, 50.0, 0.1, 3.0, 20));
+        let periodic = run(Policy::PeriodicReview {
+            period_hours: 24.0 * 7.0,
+            order_up_to: 110,
+        });
+
+        assert!(lean.lost > safe.lost);
+        assert!(lean.stockout_days > 0);
+        assert!(safe.fill_rate() > 0.99);
+        assert!(safe.holding_cost > lean.holding_cost);
+        assert_eq!(periodic.orders, 53);
+        assert!(safe.total_cost() < lean.total_cost());
+    }
+
+    #[test]
+    fn test_constant_demand_never_runs_out_with_enough_cover() {
+        let outcome = simulate(
+            &Policy::MinMax { min: 30, max: 60 },
+            &Constant(10.0),
+            &mut Rng::new(1),
+            60,
+            2,
+            30,
+            &costs(),
+        );
+        assert_eq!(outcome.lost, 0);
+        assert_eq!(outcome.demand, 300);
+        assert_eq!(outcome.fill_rate(), 1.0);
+    }
+}
@@ -0,0 +1,381 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use crate::models::Truck;
+use crate::network::{NodeId, Road, RoadNetwork};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VehicleProfile {
+    pub height_m: f64,
+    pub gross_weight_t: f64,
+    pub hazmat: bool,
+}
+
+impl Default for VehicleProfile {
+    fn default() -> Self {
+        Self {
+            height_m: 4.0,
+            gross_weight_t: 7.5,
+            hazmat: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Time,
+    Distance,
+}
+
+impl Metric {
+    fn cost(&self, road: &Road) -> f64 {
+        match self {
+            Metric::Time => road.travel_hours,
+            Metric::Distance => road.distance_km,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Leg {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub distance_km: f64,
+    pub travel_hours: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub path: Vec<NodeId>,
+    pub legs: Vec<Leg>,
+    pub distance_km: f64,
+    pub eta_hours: f64,
+}
+
+impl Route {
+    pub fn origin(&self) -> NodeId {
+        self.path[0]
+    }
+
+    pub fn destination(&self) -> NodeId {
+        self.path[self.path.len() - 1]
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+struct Visit {
+    estimate: f64,
+    cost: f64,
+    node: NodeId,
+}
+
+impl Eq for Visit {}
+
+impl Ord for Visit {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // BinaryHeap — max-куча, поэтому сравнение перевёрнуто
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for Visit {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn allowed(road: &Road, profile: Option<&VehicleProfile>) -> bool {
+    profile.is_none_or(|p| road.restrictions.allows(p))
+}
+
+fn straight_line(network: &RoadNetwork, a: NodeId, b: NodeId) -> f64 {
+    match (network.location(a), network.location(b)) {
+        (Some(a), Some(b)) => a.distance_to(b),
+        _ => 0.0,
+    }
+}
+
+// Нижняя оценка стоимости на единицу прямого расстояния, чтобы эвристика A* была допустимой
+fn heuristic_scale(network: &RoadNetwork, metric: Metric) -> f64 {
+    let mut scale = f64::INFINITY;
+    for (node, _) in network.nodes() {
+        for road in network.roads_from(node) {
+            let straight = straight_line(network, road.from, road.to);
+            if straight > 0.0 {
+                scale = scale.min(metric.cost(road) / straight);
+            }
+        }
+    }
+    if scale.is_finite() { scale } else { 0.0 }
+}
+
+type Labels<'a> = Vec<Option<(f64, Option<&'a Road>)>>;
+
+fn search<'a>(
+    network: &'a RoadNetwork,
+    from: NodeId,
+    to: Option<NodeId>,
+    metric: Metric,
+    profile: Option<&VehicleProfile>,
+) -> Labels<'a> {
+    let scale = match to {
+        Some(_) => heuristic_scale(network, metric),
+        None => 0.0,
+    };
+    let estimate = |node| match to {
+        Some(target) => straight_line(network, node, target) * scale,
+        None => 0.0,
+    };
+
+    // Для каждой вершины: лучшая стоимость и дорога, по которой пришли
+    let mut best: Labels<'a> = vec![None; network.len()];
+    let mut done = vec![false; network.len()];
+    let mut heap = BinaryHeap::new();
+    if from.0 >= network.len() {
+        return best;
+    }
+    best[from.0] = Some((0.0, None));
+    heap.push(Visit {
+        estimate: estimate(from),
+        cost: 0.0,
+        node: from,
+    });
+
+    while let Some(Visit { cost, node, .. }) = heap.pop() {
+        if done[node.0] {
+            continue;
+        }
+        done[node.0] = true;
+        if Some(node) == to {
+            break;
+        }
+        for road in network.roads_from(node) {
+            if !allowed(road, profile) {
+                continue;
+            }
+            let next = cost + metric.cost(road);
+            let improves = match best[road.to.0] {
+                Some((current, _)) => next < current,
+                None => true,
+            };
+            if improves {
+                best[road.to.0] = Some((next, Some(road)));
+                heap.push(Visit {
+                    estimate: next + estimate(road.to),
+                    cost: next,
+                    node: road.to,
+                });
+            }
+        }
+    }
+    best
+}
+
+fn rebuild(from: NodeId, to: NodeId, best: &Labels) -> Option<Route> {
+    let mut roads = Vec::new();
+    let mut node = to;
+    while node != from {
+        let (_, via) = best.get(node.0).copied().flatten()?;
+        let road = via?;
+        node = road.from;
+        roads.push(road);
+    }
+    roads.reverse();
+
+    let mut path = vec![from];
+    path.extend(roads.iter().map(|road| road.to));
+    Some(Route {
+        path,
+        legs: roads
+            .iter()
+            .map(|road| Leg {
+                from: road.from,
+                to: road.to,
+                distance_km: road.distance_km,
+                travel_hours: road.travel_hours,
+            })
+            .collect(),
+        distance_km: roads.iter().map(|road| road.distance_km).sum(),
+        eta_hours: roads.iter().map(|road| road.travel_hours).sum(),
+    })
+}
+
+impl RoadNetwork {
+    pub fn route(&self, from: NodeId, to: NodeId) -> Option<Route> {
+        self.route_with(from, to, Metric::Time, None)
+    }
+
+    pub fn route_for(&self, truck: &Truck, from: NodeId, to: NodeId) -> Option<Route> {
+        self.route_with(from, to, Metric::Time, Some(truck.profile()))
+    }
+
+    pub fn route_with(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        metric: Metric,
+        profile: Option<&VehicleProfile>,
+    ) -> Option<Route> {
+        if from.0 >= self.len() || to.0 >= self.len() {
+            return None;
+        }
+        let best = search(self, from, Some(to), metric, profile);
+        rebuild(from, to, &best)
+    }
+}
+
+pub struct DistanceMatrix {
+    size: usize,
+    cells: Vec<Option<(f64, f64)>>,
+}
+
+impl DistanceMatrix {
+    pub fn build(network: &RoadNetwork, profile: Option<&VehicleProfile>) -> Self {
+        let size = network.len();
+        let mut cells = vec![None; size * size];
+        for origin in 0..size {
+            let best = search(network, NodeId(origin), None, Metric::Time, profile);
+            for target in 0..size {
+                if let Some(route) = rebuild(NodeId(origin), NodeId(target), &best) {
+                    cells[origin * size + target] = Some((route.distance_km, route.eta_hours));
+                }
+            }
+        }
+        Self { size, cells }
+    }
+
+    fn cell(&self, from: NodeId, to: NodeId) -> Option<(f64, f64)> {
+        if from.0 >= self.size || to.0 >= self.size {
+            return None;
+        }
+        self.cells[from.0 * self.size + to.0]
+    }
+
+    pub fn distance_km(&self, from: NodeId, to: NodeId) -> Option<f64> {
+        self.cell(from, to).map(|(km, _)| km)
+    }
+
+    pub fn hours(&self, from: NodeId, to: NodeId) -> Option<f64> {
+        self.cell(from, to).map(|(_, hours)| hours)
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Location, Restrictions, SiteKind};
+
+    // plant -- a -- dc: короткий, но медленный путь через a, и быстрый объезд через b (мост с ограничениями)
+    fn sample_network() -> (RoadNetwork, NodeId, NodeId, NodeId, NodeId) {
+        let mut network = RoadNetwork::new();
+        let plant = network.add_location(Location::new("plant", SiteKind::Factory, 0.0, 0.0));
+        let a = network.add_location(Location::new("village", SiteKind::Junction, 50.0, 10.0));
+        let b = network.add_location(Location::new("bridge", SiteKind::Junction, 50.0, -20.0));
+        let dc = network.add_location(Location::new("dc", SiteKind::Warehouse, 100.0, 0.0));
+        network.add_two_way_road(plant, a, 51.0, 1.2).unwrap();
+        network.add_two_way_road(a, dc, 51.0, 1.2).unwrap();
+        network.add_two_way_road(plant, b, 54.0, 0.6).unwrap();
+        network.add_two_way_road(b, dc, 54.0, 0.6).unwrap();
+        (network, plant, a, b, dc)
+    }
+
+    #[test]
+    fn test_fastest_route() {
+        let (network, plant, _, b, dc) = sample_network();
+
+        let route = network.route(plant, dc).unwrap();
+        assert_eq!(route.path, vec![plant, b, dc]);
+        assert_eq!(route.distance_km, 108.0);
+        assert!((route.eta_hours - 1.2).abs() < 1e-9);
+        assert_eq!(route.legs.len(), 2);
+        assert_eq!(route.legs[1].from, b);
+        assert_eq!(route.origin(), plant);
+        assert_eq!(route.destination(), dc);
+    }
+
+    #[test]
+    fn test_shortest_by_distance() {
+        let (network, plant, a, _, dc) = sample_network();
+
+        let route = network.route_with(plant, dc, Metric::Distance, None).unwrap();
+        assert_eq!(route.path, vec![plant, a, dc]);
+        assert_eq!(route.distance_km, 102.0);
+    }
+
+    #[test]
+    fn test_route_to_self_and_unreachable() {
+        let (mut network, plant, _, _, _) = sample_network();
+        let island = network.add_location(Location::new("island", SiteKind::Market, 0.0, 500.0));
+
+        let route = network.route(plant, plant).unwrap();
+        assert_eq!(route.path, vec![plant]);
+        assert_eq!(route.eta_hours, 0.0);
+
+        assert!(network.route(plant, island).is_none());
+        assert!(network.route(plant, NodeId(99)).is_none());
+    }
+
+    #[test]
+    fn test_restrictions_depend_on_truck() {
+        let (mut network, plant, a, b, dc) = sample_network();
+        let low_bridge = Restrictions {
+            max_height_m: Some(3.5),
+            ..Restrictions::default()
+        };
+        network.restrict_road(plant, b, low_bridge).unwrap();
+
+        let van = Truck::new("Van".to_string(), 2).with_profile(VehicleProfile {
+            height_m: 2.5,
+            ..VehicleProfile::default()
+        });
+        let lorry = Truck::new("Lorry".to_string(), 10);
+
+        assert_eq!(network.route_for(&van, plant, dc).unwrap().path, vec![plant, b, dc]);
+        assert_eq!(network.route_for(&lorry, plant, dc).unwrap().path, vec![plant, a, dc]);
+    }
+
+    #[test]
+    fn test_hazmat_and_weight_restrictions() {
+        let (mut network, plant, a, b, dc) = sample_network();
+        network
+            .restrict_road(plant, b, Restrictions { hazmat_banned: true, ..Restrictions::default() })
+            .unwrap();
+        network
+            .restrict_road(plant, a, Restrictions { max_weight_t: Some(5.0), ..Restrictions::default() })
+            .unwrap();
+
+        let tanker = VehicleProfile {
+            hazmat: true,
+            ..VehicleProfile::default()
+        };
+        assert!(network.route_with(plant, dc, Metric::Time, Some(&tanker)).is_none());
+        assert_eq!(network.route_with(plant, dc, Metric::Time, None).unwrap().path, vec![plant, b, dc]);
+    }
+
+    #[test]
+    fn test_distance_matrix_is_cached() {
+        let (mut network, plant, a, _, dc) = sample_network();
+
+        let matrix = network.distance_matrix(None);
+        assert_eq!(matrix.size(), 4);
+        assert_eq!(matrix.distance_km(plant, dc), Some(108.0));
+        assert_eq!(matrix.hours(dc, dc), Some(0.0));
+        assert_eq!(matrix.distance_km(a, plant), Some(51.0));
+
+        let again = network.distance_matrix(None);
+        assert!(std::rc::Rc::ptr_eq(&matrix, &again));
+        assert_eq!(network.cached_matrices(), 1);
+
+        // Изменение сети сбрасывает кэш
+        network.add_location(Location::new("new", SiteKind::Junction, 0.0, 0.0));
+        assert_eq!(network.cached_matrices(), 0);
+        assert_eq!(network.distance_matrix(None).distance_km(plant, NodeId(4)), None);
+    }
+}
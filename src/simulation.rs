@@ -0,0 +1,1457 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::crossdock::CrossDock;
+use crate::docks::{Appointment, DockStatus, Docks};
+use crate::emissions::Emissions;
+use crate::ledger::{Account, Ledger, ProfitAndLoss};
+use crate::market::Market;
+use crate::models::{Factory, Product, Truck, Warehouse};
+use crate::network::{NodeId, RoadNetwork};
+use crate::random::{Distribution, Rng};
+use crate::replenishment::{Replenishment, ReplenishmentOrder, Supplier};
+use crate::trip::TruckState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum EntityId {
+    Factory(usize),
+    Warehouse(usize),
+    Truck(usize),
+    Market(usize),
+    Docks(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    ProductionComplete,
+    TruckArrives,
+    DockFree,
+    DemandTick,
+    ReviewStock,
+    TransferArrives,
+    StagingExpires,
+    Custom(&'static str),
+}
+
+struct Scheduled {
+    time: f64,
+    seq: u64,
+    target: EntityId,
+    event: Event,
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Scheduled {}
+
+impl Ord for Scheduled {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Раньше по времени, при равенстве — в порядке планирования
+        other
+            .time
+            .total_cmp(&self.time)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+pub struct Scheduler {
+    now: f64,
+    seq: u64,
+    queue: BinaryHeap<Scheduled>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self {
+            now: 0.0,
+            seq: 0,
+            queue: BinaryHeap::new(),
+        }
+    }
+
+    pub fn now(&self) -> f64 {
+        self.now
+    }
+
+    pub fn schedule_at(&mut self, time: f64, target: EntityId, event: Event) {
+        self.seq += 1;
+        self.queue.push(Scheduled {
+            time: time.max(self.now),
+            seq: self.seq,
+            target,
+            event,
+        });
+    }
+
+    pub fn schedule(&mut self, delay: f64, target: EntityId, event: Event) {
+        self.schedule_at(self.now + delay, target, event);
+    }
+
+    pub fn pending(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn next_time(&self) -> Option<f64> {
+        self.queue.peek().map(|scheduled| scheduled.time)
+    }
+
+    fn pop_until(&mut self, until: f64) -> Option<(EntityId, Event)> {
+        if self.next_time()? > until {
+            return None;
+        }
+        let scheduled = self.queue.pop()?;
+        self.now = scheduled.time;
+        Some((scheduled.target, scheduled.event))
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub struct Context<'a> {
+    pub me: EntityId,
+    pub network: &'a RoadNetwork,
+    scheduler: &'a mut Scheduler,
+}
+
+impl Context<'_> {
+    pub fn now(&self) -> f64 {
+        self.scheduler.now()
+    }
+
+    pub fn schedule(&mut self, delay: f64, target: EntityId, event: Event) {
+        self.scheduler.schedule(delay, target, event);
+    }
+}
+
+pub trait Handler {
+    fn handle(&mut self, event: &Event, ctx: &mut Context);
+}
+
+impl Handler for Factory {
+    fn handle(&mut self, event: &Event, ctx: &mut Context) {
+        self.advance(ctx.now() - self.reliability().clock());
+        if *event == Event::ProductionComplete {
+            // Неудачный цикл (поломка, полный буфер) просто теряется
+            let _ = self.step();
+            let next = self.next_cycle_time();
+            ctx.schedule(next, ctx.me, Event::ProductionComplete);
+        }
+    }
+}
+
+impl Handler for Truck {
+    fn handle(&mut self, event: &Event, ctx: &mut Context) {
+        self.advance(ctx.now() - self.reliability().clock());
+        if *event != Event::TruckArrives {
+            return;
+        }
+        match self.trip() {
+            None => {
+                let _ = self.dock(ctx.network);
+            }
+            // Простой в пути сдвигает прибытие на остаток рейса
+            Some(trip) if self.state() == TruckState::EnRoute => {
+                ctx.schedule(trip.remaining_hours(), ctx.me, Event::TruckArrives);
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+impl Handler for Warehouse {
+    fn handle(&mut self, _event: &Event, _ctx: &mut Context) {}
+}
+
+impl Handler for Market {
+    fn handle(&mut self, event: &Event, ctx: &mut Context) {
+        if *event == Event::DemandTick {
+            self.generate_demand(ctx.now());
+            ctx.schedule(self.period(), ctx.me, Event::DemandTick);
+        }
+    }
+}
+
+pub struct Simulation {
+    scheduler: Scheduler,
+    network: RoadNetwork,
+    factories: Vec<Factory>,
+    warehouses: Vec<Warehouse>,
+    trucks: Vec<Truck>,
+    markets: Vec<Market>,
+    docks: Vec<(NodeId, Docks)>,
+    cross_docks: Vec<(usize, CrossDock)>,
+    supply: Vec<(usize, usize)>,
+    ledger: Ledger,
+    emissions: Emissions,
+    replenishment: Replenishment,
+    transfers: Vec<(f64, usize, Vec<Product>)>,
+    log: Vec<(f64, EntityId, Event)>,
+    seed: Option<u64>,
+    travel_noise: Option<Box<dyn Distribution>>,
+    noise_rngs: Vec<Rng>,
+}
+
+impl Simulation {
+    pub fn new(network: RoadNetwork) -> Self {
+        Self {
+            scheduler: Scheduler::new(),
+            network,
+            factories: Vec::new(),
+            warehouses: Vec::new(),
+            trucks: Vec::new(),
+            markets: Vec::new(),
+            docks: Vec::new(),
+            cross_docks: Vec::new(),
+            supply: Vec::new(),
+            ledger: Ledger::new(),
+            emissions: Emissions::new(),
+            replenishment: Replenishment::new(),
+            transfers: Vec::new(),
+            log: Vec::new(),
+            seed: None,
+            travel_noise: None,
+            noise_rngs: Vec::new(),
+        }
+    }
+
+    // Каждая сущность получает свой подпоток по имени, так что добавление новых не меняет старые
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn with_replenishment(mut self, replenishment: Replenishment) -> Self {
+        self.replenishment = replenishment;
+        self
+    }
+
+    pub fn with_travel_noise(mut self, factor: Box<dyn Distribution>) -> Self {
+        self.travel_noise = Some(factor);
+        self
+    }
+
+    fn stream(&self, key: &str) -> Option<Rng> {
+        self.seed.map(|seed| Rng::stream(seed, key))
+    }
+
+    pub fn now(&self) -> f64 {
+        self.scheduler.now()
+    }
+
+    pub fn network(&self) -> &RoadNetwork {
+        &self.network
+    }
+
+    pub fn add_factory(&mut self, mut factory: Factory) -> EntityId {
+        let key = format!("factory/{}", factory.name());
+        if let Some(rng) = self.stream(&key) {
+            factory.set_rng(rng);
+        }
+        if let Some(rng) = self.stream(&format!("{}/reliability", key)) {
+            factory.reliability_mut().set_rng(rng);
+        }
+        self.factories.push(factory);
+        EntityId::Factory(self.factories.len() - 1)
+    }
+
+    pub fn add_warehouse(&mut self, warehouse: Warehouse) -> EntityId {
+        self.warehouses.push(warehouse);
+        EntityId::Warehouse(self.warehouses.len() - 1)
+    }
+
+    pub fn add_truck(&mut self, mut truck: Truck) -> EntityId {
+        let key = format!("truck/{}", truck.name());
+        if let Some(rng) = self.stream(&format!("{}/reliability", key)) {
+            truck.reliability_mut().set_rng(rng);
+        }
+        let noise = self
+            .stream(&format!("{}/travel", key))
+            .unwrap_or_else(|| Rng::new(self.trucks.len() as u64));
+        self.noise_rngs.push(noise);
+        self.trucks.push(truck);
+        EntityId::Truck(self.trucks.len() - 1)
+    }
+
+    pub fn add_market(&mut self, mut market: Market) -> EntityId {
+        if let Some(rng) = self.stream(&format!("market/{}", market.name())) {
+            market.set_rng(rng);
+        }
+        self.markets.push(market);
+        EntityId::Market(self.markets.len() - 1)
+    }
+
+    // Без ворот на площадке грузовик обслуживается сразу по прибытии
+    pub fn add_docks(&mut self, node: NodeId, docks: Docks) -> usize {
+        self.docks.push((node, docks));
+        self.docks.len() - 1
+    }
+
+    pub fn docks(&self, index: usize) -> &Docks {
+        &self.docks[index].1
+    }
+
+    pub fn add_cross_dock(&mut self, warehouse: usize, cross_dock: CrossDock) -> usize {
+        self.cross_docks.push((warehouse, cross_dock));
+        self.cross_docks.len() - 1
+    }
+
+    pub fn cross_dock(&self, index: usize) -> &CrossDock {
+        &self.cross_docks[index].1
+    }
+
+    pub fn cross_dock_mut(&mut self, index: usize) -> &mut CrossDock {
+        &mut self.cross_docks[index].1
+    }
+
+    pub fn book(
+        &mut self,
+        truck: usize,
+        node: NodeId,
+        earliest: f64,
+        hours: f64,
+    ) -> Result<Appointment, &'static str> {
+        let index = self
+            .docks
+            .iter()
+            .position(|(at, _)| *at == node)
+            .ok_or("No dock doors")?;
+        let appointment = self.docks[index].1.book(truck, earliest, hours)?;
+        // Если записанный грузовик не приедет, по окончании слота ворота получит двор
+        let delay = (appointment.end - self.now()).max(0.0);
+        self.scheduler
+            .schedule(delay, EntityId::Docks(index), Event::DockFree);
+        Ok(appointment)
+    }
+
+    pub fn factory(&self, index: usize) -> &Factory {
+        &self.factories[index]
+    }
+
+    pub fn warehouse(&self, index: usize) -> &Warehouse {
+        &self.warehouses[index]
+    }
+
+    pub fn truck(&self, index: usize) -> &Truck {
+        &self.trucks[index]
+    }
+
+    pub fn truck_mut(&mut self, index: usize) -> &mut Truck {
+        &mut self.trucks[index]
+    }
+
+    pub fn market(&self, index: usize) -> &Market {
+        &self.markets[index]
+    }
+
+    pub fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    pub fn profit_and_loss(&self) -> ProfitAndLoss {
+        let delivered = self.markets.iter().map(Market::received).sum();
+        self.ledger.profit_and_loss(delivered)
+    }
+
+    pub fn emissions(&self) -> &Emissions {
+        &self.emissions
+    }
+
+    pub fn replenishment(&self) -> &Replenishment {
+        &self.replenishment
+    }
+
+    pub fn replenishment_mut(&mut self) -> &mut Replenishment {
+        &mut self.replenishment
+    }
+
+    fn charge(&mut self, account: Account, amount: f64, memo: &str) {
+        if amount > 0.0 {
+            self.ledger
+                .expense(self.now(), account, amount, memo)
+                .expect("expense account");
+        }
+    }
+
+    pub fn log(&self) -> &[(f64, EntityId, Event)] {
+        &self.log
+    }
+
+    pub fn connect(&mut self, factory: usize, warehouse: usize) {
+        self.supply.push((factory, warehouse));
+    }
+
+    pub fn start_production(&mut self, factory: usize) {
+        let cycle = self.factories[factory].cycle_time();
+        self.scheduler
+            .schedule(cycle, EntityId::Factory(factory), Event::ProductionComplete);
+    }
+
+    pub fn start_demand(&mut self, market: usize) {
+        let period = self.markets[market].period();
+        self.scheduler
+            .schedule(period, EntityId::Market(market), Event::DemandTick);
+    }
+
+    pub fn start_replenishment(&mut self, warehouse: usize) {
+        self.scheduler
+            .schedule(0.0, EntityId::Warehouse(warehouse), Event::ReviewStock);
+    }
+
+    pub fn schedule(&mut self, delay: f64, target: EntityId, event: Event) {
+        self.scheduler.schedule(delay, target, event);
+    }
+
+    pub fn dispatch(&mut self, truck: usize, to: NodeId) -> Result<f64, &'static str> {
+        if self.docks.iter().any(|(_, docks)| docks.holds(truck)) {
+            return Err("Truck is at the dock");
+        }
+        let from = self.trucks[truck].location().ok_or("Truck has no location")?;
+        let mut route = self
+            .network
+            .route_for(&self.trucks[truck], from, to)
+            .ok_or("No route")?;
+        if let Some(noise) = &self.travel_noise {
+            for leg in route.legs.iter_mut() {
+                leg.travel_hours *= noise.sample(&mut self.noise_rngs[truck]).max(0.0);
+            }
+            route.eta_hours = route.legs.iter().map(|leg| leg.travel_hours).sum();
+        }
+        let eta = route.eta_hours;
+        let cost = self.trucks[truck].cost().trip(route.distance_km, eta);
+        let footprint = route.clone();
+        // Простой до отправления не должен засчитываться как езда
+        let idle = self.now() - self.trucks[truck].reliability().clock();
+        if idle > 0.0 {
+            self.trucks[truck].reliability_mut().advance(idle);
+        }
+        self.trucks[truck].dispatch(route)?;
+        self.emissions
+            .record_trip(&mut self.trucks[truck], &footprint);
+        let memo = format!("trip of {}", self.trucks[truck].name());
+        self.charge(Account::TransportCost, cost, &memo);
+        // Склад назначения видит груз как товар в пути, а выпуск под чужой заказ
+        // этому заказу уже не достанется
+        let destination = self.warehouses.iter().position(|w| w.location() == Some(to));
+        for product in self.trucks[truck].cargo() {
+            if let Some(ordered) = product.destination.filter(|d| Some(*d) != destination) {
+                self.warehouses[ordered].cancel_order(&product.sku, 1);
+            }
+            if let Some(warehouse) = destination {
+                self.warehouses[warehouse].expect(&product.sku, 1);
+            }
+        }
+        self.scheduler
+            .schedule(eta, EntityId::Truck(truck), Event::TruckArrives);
+        Ok(self.now() + eta)
+    }
+
+    pub fn run_until(&mut self, until: f64) -> usize {
+        let mut processed = 0;
+        // Хранение начисляется по единице-часам между событиями
+        let mut unit_hours = vec![0.0; self.warehouses.len()];
+        let mut last = self.now();
+        while let Some((target, event)) = self.scheduler.pop_until(until) {
+            self.accrue_storage(&mut unit_hours, self.now() - last);
+            last = self.now();
+            self.deliver(target, &event);
+            self.log.push((self.now(), target, event));
+            processed += 1;
+        }
+        self.scheduler.now = self.scheduler.now.max(until);
+        self.accrue_storage(&mut unit_hours, self.now() - last);
+        for (i, held) in unit_hours.into_iter().enumerate() {
+            let cost = held / 24.0 * self.warehouses[i].storage_cost_per_unit_day();
+            self.charge(Account::StorageCost, cost, &format!("storage at warehouse {}", i));
+        }
+        processed
+    }
+
+    fn accrue_storage(&self, unit_hours: &mut [f64], hours: f64) {
+        for (held, warehouse) in unit_hours.iter_mut().zip(&self.warehouses) {
+            *held += warehouse.loaded() as f64 * hours;
+        }
+    }
+
+    fn deliver(&mut self, target: EntityId, event: &Event) {
+        let mut ctx = Context {
+            me: target,
+            network: &self.network,
+            scheduler: &mut self.scheduler,
+        };
+        match target {
+            EntityId::Factory(i) => {
+                let before = self.factories[i].produced();
+                self.factories[i].handle(event, &mut ctx);
+                let made = self.factories[i].produced() - before;
+                let cost = made as f64 * self.factories[i].unit_cost();
+                let memo = format!("{} units at {}", made, self.factories[i].name());
+                self.charge(Account::ProductionCost, cost, &memo);
+                let factory = &self.factories[i];
+                self.emissions
+                    .record_production(factory.sku(), made, factory.unit_emissions());
+                for &(factory, warehouse) in &self.supply {
+                    if factory == i {
+                        self.factories[i].ship_to(&mut self.warehouses[warehouse], warehouse);
+                    }
+                }
+            }
+            EntityId::Warehouse(i) => {
+                self.warehouses[i].handle(event, &mut ctx);
+                match event {
+                    Event::ReviewStock => self.review_stock(i),
+                    Event::TransferArrives => self.receive_transfers(i),
+                    Event::StagingExpires => self.expire_staging(i),
+                    _ => {}
+                }
+            }
+            EntityId::Market(i) => {
+                let before = self.markets[i].sales().len();
+                self.markets[i].handle(event, &mut ctx);
+                let now = self.now();
+                let market = &self.markets[i];
+                for sale in &market.sales()[before..] {
+                    if sale.revenue <= 0.0 {
+                        continue;
+                    }
+                    let memo = format!("{} x {} at {}", sale.quantity, sale.sku, market.name());
+                    self.ledger
+                        .sale(now, sale.revenue, &memo)
+                        .expect("sale has positive revenue");
+                }
+            }
+            EntityId::Docks(i) => {
+                if *event == Event::DockFree {
+                    self.admit_from_yard(i);
+                }
+            }
+            EntityId::Truck(i) => {
+                self.trucks[i].handle(event, &mut ctx);
+                match event {
+                    Event::TruckArrives if self.trucks[i].is_docked() => self.arrive_at_docks(i),
+                    Event::DockFree => self.leave_door(i),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // Производственный заказ уходит на завод, а перемещение сразу снимает товар с другого склада
+    fn review_stock(&mut self, warehouse: usize) {
+        self.receive_transfers(warehouse);
+        let now = self.now();
+        let orders = self
+            .replenishment
+            .review(warehouse, &mut self.warehouses[warehouse], now);
+        for order in orders {
+            match order.supplier {
+                Supplier::Factory(f) if self.can_supply(f, warehouse) => {
+                    self.factories[f].order_production(&order.sku, order.quantity, Some(warehouse))
+                }
+                // Выпуск не попадёт на склад ни напрямую, ни грузовиком
+                Supplier::Factory(_) => {
+                    self.warehouses[warehouse].cancel_order(&order.sku, order.quantity)
+                }
+                Supplier::Warehouse(source) => self.transfer(source, &order),
+            }
+        }
+        let every = self.replenishment.check_every_hours();
+        self.scheduler
+            .schedule(every, EntityId::Warehouse(warehouse), Event::ReviewStock);
+    }
+
+    fn can_supply(&self, factory: usize, warehouse: usize) -> bool {
+        if self.supply.contains(&(factory, warehouse)) {
+            return true;
+        }
+        match (self.factories[factory].location(), self.warehouses[warehouse].location()) {
+            (Some(from), Some(to)) => self.network.route(from, to).is_some(),
+            _ => false,
+        }
+    }
+
+    // Перемещение идёт собственным транспортом складов вне модели парка,
+    // поэтому затраты и выбросы рейса здесь не учитываются
+    fn transfer(&mut self, source: usize, order: &ReplenishmentOrder) {
+        let from = self.warehouses[source].location();
+        let to = self.warehouses[order.warehouse].location();
+        let route = match (from, to) {
+            (Some(from), Some(to)) => self.network.route(from, to),
+            _ => None,
+        };
+        let Some(route) = route else {
+            self.warehouses[order.warehouse].cancel_order(&order.sku, order.quantity);
+            return;
+        };
+        let mut products = Vec::new();
+        while products.len() < order.quantity {
+            match self.warehouses[source].unload_sku(&order.sku) {
+                Some(product) => products.push(product),
+                None => break,
+            }
+        }
+        let destination = &mut self.warehouses[order.warehouse];
+        destination.expect(&order.sku, products.len());
+        destination.cancel_order(&order.sku, order.quantity - products.len());
+        if products.is_empty() {
+            return;
+        }
+        let hours = route.eta_hours;
+        self.transfers.push((self.now() + hours, order.warehouse, products));
+        self.scheduler
+            .schedule(hours, EntityId::Warehouse(order.warehouse), Event::TransferArrives);
+    }
+
+    fn expire_staging(&mut self, warehouse: usize) {
+        let now = self.now();
+        if let Some((_, cross_dock)) = self.cross_docks.iter_mut().find(|(at, _)| *at == warehouse) {
+            cross_dock.expire(now, &mut self.warehouses[warehouse]);
+        }
+    }
+
+    // Не поместившееся остаётся в пути и разгружается при следующей проверке склада
+    fn receive_transfers(&mut self, warehouse: usize) {
+        let now = self.now();
+        let (arrived, pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.transfers)
+            .into_iter()
+            .partition(|(at, to, _)| *to == warehouse && *at <= now + 1e-9);
+        self.transfers = pending;
+        let mut waiting = Vec::new();
+        for (_, _, products) in arrived {
+            for product in products {
+                if self.warehouses[warehouse].can_accept(&product) {
+                    self.warehouses[warehouse].receive(product).expect("warehouse has room");
+                } else {
+                    waiting.push(product);
+                }
+            }
+        }
+        if !waiting.is_empty() {
+            self.transfers.push((now, warehouse, waiting));
+        }
+    }
+
+    fn arrive_at_docks(&mut self, truck: usize) {
+        let node = self.trucks[truck].location();
+        let now = self.now();
+        let Some(index) = self.docks.iter().position(|(at, _)| Some(*at) == node) else {
+            self.exchange_cargo(truck);
+            return;
+        };
+        // Если двор переполнен, грузовик остаётся без обслуживания
+        if let Ok(DockStatus::AtDoor(door)) = self.docks[index].1.arrive(truck, now) {
+            self.serve_at_door(index, truck, door);
+        }
+    }
+
+    fn serve_at_door(&mut self, index: usize, truck: usize, door: usize) {
+        let (unloaded, loaded) = self.exchange_cargo(truck);
+        let docks = &mut self.docks[index].1;
+        let hours = docks.service_hours(unloaded, loaded);
+        docks.occupy(door, hours);
+        self.scheduler
+            .schedule(hours, EntityId::Truck(truck), Event::DockFree);
+    }
+
+    fn admit_from_yard(&mut self, index: usize) {
+        let now = self.now();
+        for (truck, door) in self.docks[index].1.admit(now) {
+            self.serve_at_door(index, truck, door);
+        }
+    }
+
+    fn leave_door(&mut self, truck: usize) {
+        let now = self.now();
+        let at_door = self.docks.iter().position(|(_, docks)| docks.door_of(truck).is_some());
+        let Some(index) = at_door else { return };
+        if let Some((next, door)) = self.docks[index].1.release(truck, now) {
+            self.serve_at_door(index, next, door);
+        }
+    }
+
+    // Грузовик у склада и рынка разгружается, у завода — забирает выпуск
+    fn exchange_cargo(&mut self, index: usize) -> (usize, usize) {
+        let node = self.trucks[index].location();
+        let now = self.now();
+        let truck = &mut self.trucks[index];
+        let arrived_with = truck.loaded();
+        for market in self.markets.iter_mut() {
+            if market.location() == node {
+                let _ = market.deliver_from(truck);
+            }
+        }
+        let mut cross_loaded = 0;
+        for (w, warehouse) in self.warehouses.iter_mut().enumerate() {
+            if warehouse.location() != node {
+                continue;
+            }
+            // Весь груз машины числился в пути: что склад не принял, оттуда снимается
+            let mut arriving: HashMap<String, usize> = HashMap::new();
+            for product in self.trucks[index].cargo() {
+                *arriving.entry(product.sku.clone()).or_default() += 1;
+            }
+            if let Some((_, cross_dock)) = self.cross_docks.iter_mut().find(|(at, _)| *at == w) {
+                cross_loaded += cross_dock_at(cross_dock, warehouse, &mut self.trucks, index, now);
+                // Срок ожидания проверяется и без следующего грузовика
+                if cross_dock.staged() > 0 {
+                    self.scheduler.schedule(
+                        cross_dock.max_dwell_hours(),
+                        EntityId::Warehouse(w),
+                        Event::StagingExpires,
+                    );
+                }
+            } else {
+                let truck = &mut self.trucks[index];
+                while truck.cargo().last().is_some_and(|product| warehouse.can_accept(product)) {
+                    match truck.unload() {
+                        Ok(product) => {
+                            if let Some(count) = arriving.get_mut(&product.sku) {
+                                *count -= 1;
+                            }
+                            warehouse.receive(product).expect("warehouse has room");
+                        }
+                        Err(_) => break,
+                    }
+                }
+            }
+            for (sku, count) in arriving {
+                warehouse.cancel_expected(&sku, count);
+            }
+            self.scheduler
+                .schedule(0.0, EntityId::Warehouse(w), Event::DockFree);
+        }
+        let truck = &mut self.trucks[index];
+        let kept = truck.loaded() - cross_loaded;
+        for factory in self.factories.iter_mut() {
+            if factory.location() != node {
+                continue;
+            }
+            // Выпуск снимается с завода, только если машина его точно примет
+            while factory
+                .peek_output()
+                .is_some_and(|product| truck.check_load(product).is_ok())
+            {
+                let product = factory.take_output().expect("output was peeked");
+                truck.load(product).expect("load was checked");
+            }
+        }
+        (arrived_with - kept, truck.loaded() - kept)
+    }
+}
+
+// Входящий грузовик сдаёт товар в зону кросс-докинга, исходящие забирают своё.
+// Возвращает, сколько единиц погружено в прибывший грузовик
+fn cross_dock_at(
+    cross_dock: &mut CrossDock,
+    warehouse: &mut Warehouse,
+    trucks: &mut [Truck],
+    arrived: usize,
+    now: f64,
+) -> usize {
+    cross_dock.expire(now, warehouse);
+    if cross_dock.is_outbound(arrived) {
+        return cross_dock.load_outbound(arrived, &mut trucks[arrived], now);
+    }
+    cross_dock.unload_inbound(&mut trucks[arrived], now, warehouse);
+    let node = trucks[arrived].location();
+    for (index, truck) in trucks.iter_mut().enumerate() {
+        if index != arrived && truck.location() == node && truck.is_docked() {
+            cross_dock.load_outbound(index, truck, now);
+        }
+    }
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crossdock::MatchRule;
+    use crate::fleet::OperatingCost;
+    use crate::network::{Location, SiteKind};
+    use crate::random::{Constant, Exponential, Triangular};
+    use crate::reliability::Reliability;
+    use crate::replenishment::Policy;
+
+    fn sample_simulation() -> (Simulation, NodeId, NodeId) {
+        let mut network = RoadNetwork::new();
+        let plant = network.add_location(Location::new("plant", SiteKind::Factory, 0.0, 0.0));
+        let dc = network.add_location(Location::new("dc", SiteKind::Warehouse, 50.0, 0.0));
+        network.add_two_way_road(plant, dc, 50.0, 1.0).unwrap();
+        (Simulation::new(network), plant, dc)
+    }
+
+    #[test]
+    fn test_events_run_in_time_then_schedule_order() {
+        let mut scheduler = Scheduler::new();
+        scheduler.schedule_at(2.0, EntityId::Truck(0), Event::Custom("late"));
+        scheduler.schedule_at(1.0, EntityId::Truck(0), Event::Custom("first"));
+        scheduler.schedule_at(1.0, EntityId::Truck(1), Event::Custom("second"));
+
+        assert_eq!(scheduler.pending(), 3);
+        assert_eq!(scheduler.pop_until(10.0), Some((EntityId::Truck(0), Event::Custom("first"))));
+        assert_eq!(scheduler.pop_until(10.0), Some((EntityId::Truck(1), Event::Custom("second"))));
+        assert_eq!(scheduler.now(), 1.0);
+        assert_eq!(scheduler.pop_until(1.5), None);
+        assert_eq!(scheduler.pop_until(2.0), Some((EntityId::Truck(0), Event::Custom("late"))));
+    }
+
+    #[test]
+    fn test_run_until_advances_clock() {
+        let (mut sim, _, _) = sample_simulation();
+
+        assert_eq!(sim.run_until(5.0), 0);
+        assert_eq!(sim.now(), 5.0);
+    }
+
+    #[test]
+    fn test_factory_produces_into_warehouse() {
+        let (mut sim, plant, dc) = sample_simulation();
+        sim.add_factory(Factory::new("SimFactory".to_string()).with_location(plant));
+        sim.add_warehouse(Warehouse::new(100).with_location(dc));
+        sim.connect(0, 0);
+        sim.start_production(0);
+
+        // Цикл 1 час: события в 1, 2, ..., 24
+        assert_eq!(sim.run_until(24.0), 24);
+        assert_eq!(sim.warehouse(0).loaded(), 24);
+        assert_eq!(sim.factory(0).buffered(), 0);
+    }
+
+    #[test]
+    fn test_truck_shuttles_output_to_warehouse() {
+        let (mut sim, plant, dc) = sample_simulation();
+        sim.add_factory(
+            Factory::new("ShuttleFactory".to_string())
+                .with_location(plant)
+                .with_cycle_time(0.5),
+        );
+        sim.add_warehouse(Warehouse::new(100).with_location(dc));
+        sim.add_truck(Truck::new("Shuttle".to_string(), 10).with_location(dc));
+        sim.start_production(0);
+
+        // Едем пустыми на завод; прибытие в 4.0 запланировано раньше, чем выпуск в 4.0
+        sim.run_until(3.0);
+        assert_eq!(sim.dispatch(0, plant), Ok(4.0));
+        sim.run_until(4.0);
+        assert_eq!(sim.truck(0).loaded(), 7);
+        assert_eq!(sim.factory(0).buffered(), 1);
+
+        sim.dispatch(0, dc).unwrap();
+        assert_eq!(sim.warehouse(0).stock("product").in_transit, 7);
+        sim.run_until(5.0);
+        assert_eq!(sim.warehouse(0).stock("product").in_transit, 0);
+        assert_eq!(sim.truck(0).location(), Some(dc));
+        assert_eq!(sim.truck(0).loaded(), 0);
+        assert_eq!(sim.warehouse(0).loaded(), 7);
+        assert!(sim.log().iter().any(|(_, target, event)| {
+            *target == EntityId::Warehouse(0) && *event == Event::DockFree
+        }));
+    }
+
+    #[test]
+    fn test_payload_limit_leaves_output_at_factory() {
+        let (mut sim, plant, dc) = sample_simulation();
+        sim.add_factory(
+            Factory::new("HeavyFactory".to_string())
+                .with_location(plant)
+                .with_cycle_time(0.5),
+        );
+        sim.add_truck(
+            Truck::new("Light".to_string(), 10)
+                .with_location(dc)
+                .with_max_payload(3.0),
+        );
+        sim.start_production(0);
+
+        // Машина берёт по массе только три единицы, остальное ждёт на заводе
+        sim.run_until(3.0);
+        sim.dispatch(0, plant).unwrap();
+        sim.run_until(4.0);
+        assert_eq!(sim.truck(0).loaded(), 3);
+        assert_eq!(sim.factory(0).buffered(), 5);
+    }
+
+    #[test]
+    fn test_units_left_on_truck_are_no_longer_in_transit() {
+        let (mut sim, plant, dc) = sample_simulation();
+        sim.add_factory(
+            Factory::new("ShuttleFactory".to_string())
+                .with_location(plant)
+                .with_cycle_time(0.5),
+        );
+        sim.add_warehouse(Warehouse::new(2).with_location(dc));
+        sim.add_truck(Truck::new("Shuttle".to_string(), 10).with_location(dc));
+        sim.start_production(0);
+
+        sim.run_until(3.0);
+        sim.dispatch(0, plant).unwrap();
+        sim.run_until(4.0);
+        sim.dispatch(0, dc).unwrap();
+        assert_eq!(sim.warehouse(0).stock("product").in_transit, 7);
+
+        // Склад принял только две единицы, остальные остались в машине
+        sim.run_until(5.0);
+        assert_eq!(sim.warehouse(0).loaded(), 2);
+        assert_eq!(sim.truck(0).loaded(), 5);
+        assert_eq!(sim.warehouse(0).stock("product").in_transit, 0);
+    }
+
+    #[test]
+    fn test_truck_arrives_after_maintenance_on_the_road() {
+        let (mut sim, plant, dc) = sample_simulation();
+        let mut truck = Truck::new("Serviced".to_string(), 10).with_location(plant);
+        truck.reliability_mut().schedule_maintenance(0.9, 0.2);
+        sim.add_truck(truck);
+
+        // Час пути плюс 0.2 часа стоянки
+        sim.dispatch(0, dc).unwrap();
+        sim.run_until(1.0);
+        assert_eq!(sim.truck(0).state(), TruckState::EnRoute);
+        sim.run_until(100.0);
+        assert_eq!(sim.truck(0).location(), Some(dc));
+        assert!(sim.truck(0).trip().is_none());
+        let arrivals: Vec<f64> = sim
+            .log()
+            .iter()
+            .filter(|(_, _, event)| *event == Event::TruckArrives)
+            .map(|(time, _, _)| *time)
+            .collect();
+        assert!((arrivals[arrivals.len() - 1] - 1.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_maintenance_delays_trip_dispatched_later() {
+        let (mut sim, plant, dc) = sample_simulation();
+        let mut truck = Truck::new("Serviced".to_string(), 10).with_location(plant);
+        truck.reliability_mut().schedule_maintenance(3.0, 0.5);
+        sim.add_truck(truck);
+
+        // Три часа простоя на заводе не сокращают путь
+        sim.run_until(3.0);
+        sim.dispatch(0, dc).unwrap();
+        sim.run_until(100.0);
+        assert_eq!(sim.truck(0).location(), Some(dc));
+        let arrivals: Vec<f64> = sim
+            .log()
+            .iter()
+            .filter(|(_, _, event)| *event == Event::TruckArrives)
+            .map(|(time, _, _)| *time)
+            .collect();
+        assert!((arrivals[arrivals.len() - 1] - 4.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_runs_are_deterministic() {
+        let run = || {
+            let (mut sim, plant, dc) = sample_simulation();
+            sim.add_factory(Factory::new("A".to_string()).with_location(plant));
+            sim.add_factory(Factory::new("B".to_string()).with_location(plant));
+            sim.add_warehouse(Warehouse::new(2000).with_location(dc));
+            sim.connect(0, 0);
+            sim.connect(1, 0);
+            sim.start_production(0);
+            sim.start_production(1);
+            sim.run_until(24.0 * 30.0);
+            sim.warehouse(0).cargo().map(|p| p.origin.clone()).collect::<Vec<_>>()
+        };
+
+        let first = run();
+        assert_eq!(first.len(), 720 * 2);
+        assert_eq!(first, run());
+        assert_eq!(first[0], "A");
+        assert_eq!(first[1], "B");
+    }
+
+    #[test]
+    fn test_market_sells_truck_deliveries() {
+        let (mut sim, plant, dc) = sample_simulation();
+        let mut market = Market::new("Town".to_string()).with_location(dc);
+        market.set_price("product", 3.0);
+        market.set_demand("product", Box::new(Constant(2.0)));
+        sim.add_market(market);
+        sim.add_factory(Factory::new("F".to_string()).with_location(plant));
+        sim.add_truck(Truck::new("T".to_string(), 10).with_location(dc));
+        sim.start_production(0);
+        sim.start_demand(0);
+
+        sim.run_until(2.0);
+        sim.dispatch(0, plant).unwrap();
+        sim.run_until(3.0);
+        assert_eq!(sim.truck(0).loaded(), 3);
+
+        // Пока грузовик в пути, спрос не удовлетворяется
+        sim.dispatch(0, dc).unwrap();
+        sim.run_until(5.0);
+        assert_eq!(sim.truck(0).loaded(), 0);
+        assert_eq!(sim.market(0).units_lost("product"), 8);
+        assert_eq!(sim.market(0).units_sold("product"), 2);
+        assert_eq!(sim.market(0).stock("product"), 1);
+        assert_eq!(sim.market(0).revenue(), 6.0);
+    }
+
+    #[test]
+    fn test_ledger_reports_profit_and_loss() {
+        let (mut sim, plant, dc) = sample_simulation();
+        let mut market = Market::new("Town".to_string()).with_location(dc);
+        market.set_price("product", 3.0);
+        market.set_demand("product", Box::new(Constant(2.0)));
+        sim.add_market(market);
+        sim.add_factory(
+            Factory::new("F".to_string())
+                .with_location(plant)
+                .with_unit_cost(1.0),
+        );
+        sim.add_truck(
+            Truck::new("T".to_string(), 10)
+                .with_location(dc)
+                .with_cost(OperatingCost::new(0.5, 10.0, 0.0)),
+        );
+        sim.start_production(0);
+        sim.start_demand(0);
+
+        sim.run_until(2.0);
+        sim.dispatch(0, plant).unwrap();
+        sim.run_until(3.0);
+        sim.dispatch(0, dc).unwrap();
+        sim.run_until(5.0);
+
+        // Пять единиц по 1, два рейса по 50 км и часу, продано две по 3
+        let report = sim.profit_and_loss();
+        assert_eq!(report.production, 5.0);
+        assert_eq!(report.transport, 70.0);
+        assert_eq!(report.revenue, 6.0);
+        assert_eq!(report.units_delivered, 3);
+        assert_eq!(report.profit(), -69.0);
+        assert_eq!(report.cost_per_unit_delivered(), Some(25.0));
+        assert!(sim.ledger().is_balanced());
+        assert_eq!(sim.ledger().balance(Account::Cash), -69.0);
+    }
+
+    #[test]
+    fn test_emissions_follow_products_to_warehouse() {
+        let (mut sim, plant, dc) = sample_simulation();
+        sim.add_factory(
+            Factory::new("GreenFactory".to_string())
+                .with_location(plant)
+                .with_energy_use(2.0, 0.5),
+        );
+        sim.add_warehouse(Warehouse::new(100).with_location(dc));
+        sim.add_truck(Truck::new("Shuttle".to_string(), 10).with_location(dc));
+        sim.start_production(0);
+
+        sim.run_until(3.0);
+        sim.dispatch(0, plant).unwrap();
+        sim.run_until(4.0);
+        sim.dispatch(0, dc).unwrap();
+        sim.run_until(5.0);
+
+        // Порожний пробег к заводу остаётся на плече, груженый — на товарах
+        let emissions = sim.emissions();
+        assert_eq!(emissions.by_lane().len(), 2);
+        assert_eq!(emissions.by_sku()[0].0, "product");
+        let delivered = sim.warehouse(0).loaded() as f64;
+        let loaded = emissions.trips()[1].co2e_kg;
+        let footprint: f64 = sim.warehouse(0).cargo().map(|p| p.co2e_kg).sum();
+        assert!((footprint - delivered - loaded).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_reorder_point_drives_make_to_order_factory() {
+        let (mut sim, plant, dc) = sample_simulation();
+        sim.add_factory(
+            Factory::new("SimFactory".to_string())
+                .with_location(plant)
+                .with_make_to_order(),
+        );
+        sim.add_warehouse(Warehouse::new(100).with_location(dc));
+        sim.connect(0, 0);
+        sim.replenishment_mut().add_rule(
+            0,
+            "product",
+            Policy::ReorderPoint {
+                reorder_point: 5,
+                quantity: 10,
+            },
+            Supplier::Factory(0),
+        );
+        sim.start_production(0);
+        sim.start_replenishment(0);
+
+        sim.run_until(0.5);
+        assert_eq!(sim.warehouse(0).stock("product").on_order, 10);
+        assert_eq!(sim.warehouse(0).stock("product").in_transit, 0);
+        assert_eq!(sim.factory(0).production_backlog(), 10);
+
+        // Партия выпущена и на складе, выше точки заказа завод простаивает
+        sim.run_until(24.0);
+        assert_eq!(sim.warehouse(0).loaded(), 10);
+        assert_eq!(sim.warehouse(0).stock("product").on_order, 0);
+        assert_eq!(sim.factory(0).produced(), 10);
+        assert_eq!(sim.replenishment().orders().len(), 1);
+    }
+
+    #[test]
+    fn test_production_order_delivered_by_truck() {
+        let (mut sim, plant, dc) = sample_simulation();
+        sim.add_factory(
+            Factory::new("OrderFactory".to_string())
+                .with_location(plant)
+                .with_cycle_time(0.5)
+                .with_make_to_order(),
+        );
+        sim.add_warehouse(Warehouse::new(100).with_location(dc));
+        sim.add_truck(Truck::new("Shuttle".to_string(), 10).with_location(dc));
+        sim.replenishment_mut().add_rule(
+            0,
+            "product",
+            Policy::ReorderPoint {
+                reorder_point: 5,
+                quantity: 6,
+            },
+            Supplier::Factory(0),
+        );
+        sim.start_production(0);
+        sim.start_replenishment(0);
+
+        sim.run_until(4.0);
+        sim.dispatch(0, plant).unwrap();
+        sim.run_until(5.0);
+        assert_eq!(sim.truck(0).loaded(), 6);
+        assert_eq!(sim.warehouse(0).stock("product").on_order, 6);
+
+        // Отгрузка переводит заказ в путь, а не добавляет к нему
+        sim.dispatch(0, dc).unwrap();
+        let level = sim.warehouse(0).stock("product");
+        assert_eq!((level.on_order, level.in_transit), (0, 6));
+
+        sim.run_until(8.0);
+        let level = sim.warehouse(0).stock("product");
+        assert_eq!((level.on_hand, level.on_order, level.in_transit), (6, 0, 0));
+        assert_eq!(sim.replenishment().orders().len(), 1);
+    }
+
+    #[test]
+    fn test_transfer_moves_stock_between_warehouses() {
+        let (mut sim, plant, dc) = sample_simulation();
+        let mut factory = Factory::new("Plant".to_string());
+        let mut central = Warehouse::new(100).with_location(plant);
+        for _ in 0..5 {
+            central.load(factory.create().unwrap()).unwrap();
+        }
+        sim.add_warehouse(central);
+        sim.add_warehouse(Warehouse::new(100).with_location(dc));
+        sim.replenishment_mut().add_rule(
+            1,
+            "product",
+            Policy::MinMax { min: 2, max: 8 },
+            Supplier::Warehouse(0),
+        );
+        sim.start_replenishment(1);
+
+        // На центральном складе только 5 из 8, недостающее не числится в пути
+        sim.run_until(0.5);
+        assert_eq!(sim.warehouse(0).loaded(), 0);
+        assert_eq!(sim.warehouse(1).stock("product").in_transit, 5);
+        assert_eq!(sim.warehouse(1).stock("product").on_order, 0);
+
+        // Дорога занимает час
+        sim.run_until(3.0);
+        assert_eq!(sim.warehouse(1).loaded(), 5);
+        assert_eq!(sim.warehouse(1).stock("product").in_transit, 0);
+        assert_eq!(sim.replenishment().orders().len(), 1);
+    }
+
+    #[test]
+    fn test_production_order_goes_to_ordering_warehouse() {
+        let (mut sim, plant, dc) = sample_simulation();
+        sim.add_factory(
+            Factory::new("OrderFactory".to_string())
+                .with_location(plant)
+                .with_make_to_order(),
+        );
+        sim.add_warehouse(Warehouse::new(100).with_location(plant));
+        sim.add_warehouse(Warehouse::new(100).with_location(dc));
+        sim.connect(0, 0);
+        sim.connect(0, 1);
+        sim.replenishment_mut().add_rule(
+            1,
+            "product",
+            Policy::ReorderPoint {
+                reorder_point: 5,
+                quantity: 10,
+            },
+            Supplier::Factory(0),
+        );
+        sim.start_production(0);
+        sim.start_replenishment(1);
+
+        // Первый по списку склад заказ не делал и ничего не получает
+        sim.run_until(24.0);
+        assert_eq!(sim.warehouse(0).loaded(), 0);
+        assert_eq!(sim.warehouse(1).loaded(), 10);
+        assert_eq!(sim.warehouse(1).stock("product").on_order, 0);
+    }
+
+    #[test]
+    fn test_unreachable_warehouse_does_not_order_from_factory() {
+        let (mut sim, plant, _) = sample_simulation();
+        sim.add_factory(
+            Factory::new("OrderFactory".to_string())
+                .with_location(plant)
+                .with_make_to_order(),
+        );
+        sim.add_warehouse(Warehouse::new(100));
+        sim.replenishment_mut().add_rule(
+            0,
+            "product",
+            Policy::ReorderPoint {
+                reorder_point: 5,
+                quantity: 10,
+            },
+            Supplier::Factory(0),
+        );
+        sim.start_production(0);
+        sim.start_replenishment(0);
+
+        sim.run_until(0.5);
+        assert_eq!(sim.warehouse(0).stock("product").on_order, 0);
+        assert_eq!(sim.factory(0).production_backlog(), 0);
+    }
+
+    #[test]
+    fn test_transfer_without_route_is_refused() {
+        let (mut sim, plant, _) = sample_simulation();
+        let mut factory = Factory::new("Plant".to_string());
+        let mut central = Warehouse::new(100).with_location(plant);
+        for _ in 0..5 {
+            central.load(factory.create().unwrap()).unwrap();
+        }
+        sim.add_warehouse(central);
+        sim.add_warehouse(Warehouse::new(100));
+        sim.replenishment_mut().add_rule(
+            1,
+            "product",
+            Policy::MinMax { min: 2, max: 8 },
+            Supplier::Warehouse(0),
+        );
+        sim.start_replenishment(1);
+
+        // Без дороги товар не может появиться на складе мгновенно
+        sim.run_until(3.0);
+        assert_eq!(sim.warehouse(0).loaded(), 5);
+        assert_eq!(sim.warehouse(1).loaded(), 0);
+        let level = sim.warehouse(1).stock("product");
+        assert_eq!((level.on_order, level.in_transit), (0, 0));
+    }
+
+    #[test]
+    fn test_storage_accrues_per_unit_day() {
+        let (mut sim, plant, dc) = sample_simulation();
+        sim.add_factory(Factory::new("SimFactory".to_string()).with_location(plant));
+        sim.add_warehouse(
+            Warehouse::new(100)
+                .with_location(dc)
+                .with_storage_cost(2.4),
+        );
+        sim.connect(0, 0);
+        sim.start_production(0);
+
+        // После k-го часа на складе k единиц: 1 + 2 + ... + 23 = 276 единице-часов
+        sim.run_until(24.0);
+        let storage = sim.ledger().balance(Account::StorageCost);
+        assert!((storage - 27.6).abs() < 1e-9);
+        assert_eq!(sim.profit_and_loss().production, 0.0);
+    }
+
+    #[test]
+    fn test_trucks_wait_in_yard_for_dock_door() {
+        let (mut sim, plant, dc) = sample_simulation();
+        sim.add_factory(
+            Factory::new("DockFactory".to_string())
+                .with_location(plant)
+                .with_cycle_time(0.125),
+        );
+        sim.add_truck(Truck::new("First".to_string(), 4).with_location(dc));
+        sim.add_truck(Truck::new("Second".to_string(), 4).with_location(dc));
+        let docks = sim.add_docks(plant, Docks::new(1, 3).with_rates(4.0, 4.0));
+        sim.start_production(0);
+        sim.dispatch(0, plant).unwrap();
+        sim.dispatch(1, plant).unwrap();
+
+        // Ворота одни: второй грузовик ждёт во дворе, пока грузят первый
+        sim.run_until(1.5);
+        assert_eq!(sim.truck(0).loaded(), 4);
+        assert_eq!(sim.truck(1).loaded(), 0);
+        assert_eq!(sim.docks(docks).yard().collect::<Vec<_>>(), vec![1]);
+        assert_eq!(sim.dispatch(1, dc), Err("Truck is at the dock"));
+
+        sim.run_until(3.0);
+        assert_eq!(sim.truck(1).loaded(), 4);
+        let stats = sim.docks(docks).stats();
+        assert_eq!(stats.served, 2);
+        assert_eq!(stats.max_wait_hours, 1.0);
+        assert!((sim.docks(docks).utilization(3.0) - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(sim.dispatch(0, dc), Ok(4.0));
+    }
+
+    #[test]
+    fn test_no_show_booking_frees_door_for_yard() {
+        let (mut sim, plant, dc) = sample_simulation();
+        sim.add_factory(Factory::new("DockFactory".to_string()).with_location(plant));
+        sim.add_truck(Truck::new("Waiting".to_string(), 4).with_location(dc));
+        let docks = sim.add_docks(plant, Docks::new(1, 3).with_rates(4.0, 4.0));
+        sim.start_production(0);
+        sim.book(7, plant, 0.0, 2.0).unwrap();
+        sim.dispatch(0, plant).unwrap();
+
+        // Грузовик 7 так и не приехал: в 2.0 ворота отдаются двору
+        sim.run_until(1.5);
+        assert_eq!(sim.docks(docks).yard().collect::<Vec<_>>(), vec![0]);
+        sim.run_until(50.0);
+        assert_eq!(sim.docks(docks).yard().count(), 0);
+        assert!(sim.truck(0).loaded() > 0);
+        assert_eq!(sim.docks(docks).stats().max_wait_hours, 1.0);
+        assert!(sim.dispatch(0, dc).is_ok());
+    }
+
+    #[test]
+    fn test_booking_requires_docks() {
+        let (mut sim, plant, dc) = sample_simulation();
+        sim.add_docks(plant, Docks::new(2, 1));
+
+        assert_eq!(sim.book(0, plant, 4.0, 1.0).unwrap().start, 4.0);
+        assert_eq!(sim.book(0, dc, 4.0, 1.0), Err("No dock doors"));
+    }
+
+    #[test]
+    fn test_staged_cargo_expires_without_another_truck() {
+        let (mut sim, plant, dc) = sample_simulation();
+        sim.add_factory(Factory::new("CrossFactory".to_string()).with_location(plant));
+        sim.add_warehouse(Warehouse::new(100).with_location(dc));
+        sim.add_truck(Truck::new("Inbound".to_string(), 10).with_location(dc));
+        let cross_dock = sim.add_cross_dock(0, CrossDock::new(10, 2.0));
+        sim.cross_dock_mut(cross_dock).open_load(1, MatchRule::Any);
+        sim.start_production(0);
+
+        sim.run_until(2.0);
+        sim.dispatch(0, plant).unwrap();
+        sim.run_until(3.0);
+        sim.dispatch(0, dc).unwrap();
+        sim.run_until(5.5);
+        assert_eq!(sim.cross_dock(cross_dock).staged(), 3);
+
+        // Исходящий грузовик так и не пришёл: в 6.0 истёк срок ожидания
+        sim.run_until(6.0);
+        assert_eq!(sim.cross_dock(cross_dock).staged(), 0);
+        assert_eq!(sim.cross_dock(cross_dock).stats().expired, 3);
+        assert_eq!(sim.warehouse(0).loaded(), 3);
+    }
+
+    #[test]
+    fn test_cross_dock_hands_cargo_to_waiting_truck() {
+        let (mut sim, plant, dc) = sample_simulation();
+        sim.add_factory(Factory::new("CrossFactory".to_string()).with_location(plant));
+        sim.add_warehouse(Warehouse::new(100).with_location(dc));
+        sim.add_truck(Truck::new("Inbound".to_string(), 10).with_location(dc));
+        sim.add_truck(Truck::new("Outbound".to_string(), 3).with_location(dc));
+        let cross_dock = sim.add_cross_dock(0, CrossDock::new(10, 2.0));
+        sim.cross_dock_mut(cross_dock).open_load(1, MatchRule::Any);
+        sim.start_production(0);
+
+        sim.run_until(4.0);
+        sim.dispatch(0, plant).unwrap();
+        sim.run_until(5.0);
+        assert_eq!(sim.truck(0).loaded(), 5);
+        sim.dispatch(0, dc).unwrap();
+        sim.run_until(7.0);
+
+        // Три единицы сразу ушли в ожидающий исходящий грузовик, две ждут в зоне комплектации
+        assert_eq!(sim.truck(1).loaded(), 3);
+        assert_eq!(sim.cross_dock(cross_dock).staged(), 2);
+        assert_eq!(sim.warehouse(0).loaded(), 0);
+
+        sim.dispatch(0, plant).unwrap();
+        sim.run_until(8.0);
+        sim.dispatch(0, dc).unwrap();
+        sim.run_until(9.0);
+
+        // Залежавшиеся единицы ушли на хранение по сроку, не дожидаясь прибытия
+        let stats = sim.cross_dock(cross_dock).stats();
+        assert_eq!(stats.expired, 2);
+        assert_eq!(stats.cross_docked, 3);
+        assert_eq!(stats.received, 8);
+        assert_eq!(sim.warehouse(0).loaded(), 2);
+        assert_eq!(sim.cross_dock(cross_dock).staged(), 3);
+    }
+
+    fn seeded_run(seed: u64, extra_factory: bool) -> (Vec<f64>, Vec<bool>) {
+        let (sim, plant, dc) = sample_simulation();
+        let mut sim = sim
+            .with_seed(seed)
+            .with_travel_noise(Box::new(Triangular { min: 0.9, mode: 1.0, max: 1.5 }));
+        let mut factory = Factory::new("A".to_string())
+            .with_location(plant)
+            .with_production_time(Box::new(Exponential { mean: 1.0 }))
+            .with_reliability(Reliability::new(50.0, 4.0, 0).unwrap());
+        factory.set_defect_rate("product", 0.2);
+        sim.add_factory(factory);
+        if extra_factory {
+            let mut other = Factory::new("B".to_string()).with_location(plant);
+            other.set_defect_rate("product", 0.5);
+            sim.add_factory(other);
+        }
+        sim.add_warehouse(Warehouse::new(10_000).with_location(dc));
+        sim.add_truck(Truck::new("T".to_string(), 10).with_location(dc));
+        sim.connect(0, 0);
+        sim.start_production(0);
+        if extra_factory {
+            sim.start_production(1);
+        }
+        sim.dispatch(0, plant).unwrap();
+        sim.run_until(500.0);
+
+        let times = sim
+            .log()
+            .iter()
+            .filter(|(_, target, _)| *target != EntityId::Factory(1))
+            .map(|(time, _, _)| *time)
+            .collect();
+        let defects = sim
+            .warehouse(0)
+            .cargo()
+            .filter(|p| p.origin == "A")
+            .map(|p| p.defective)
+            .collect();
+        (times, defects)
+    }
+
+    #[test]
+    fn test_scenario_seed_reproduces_run() {
+        let first = seeded_run(2024, false);
+        assert_eq!(first, seeded_run(2024, false));
+        assert_ne!(first.0, seeded_run(2025, false).0);
+    }
+
+    #[test]
+    fn test_substreams_stable_when_entities_added() {
+        let alone = seeded_run(7, false);
+        let crowded = seeded_run(7, true);
+
+        // События завода A и грузовика, как и их случайность, не зависят от завода B
+        assert_eq!(alone.0, crowded.0);
+        assert_eq!(alone.1, crowded.1);
+    }
+}
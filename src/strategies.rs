@@ -0,0 +1,439 @@
+use std::collections::HashMap;
+
+use crate::layout::{BinId, Layout};
+use crate::models::Product;
+
+pub trait PutAwayStrategy {
+    fn name(&self) -> &'static str;
+    fn choose_bin(&self, layout: &Layout, product: &Product) -> Result<BinId, &'static str>;
+}
+
+// Ячейки хранения, в которые товар можно положить по политике категорий
+fn storage_bins<'a>(layout: &'a Layout, product: &'a Product) -> impl Iterator<Item = BinId> + 'a {
+    layout
+        .bins()
+        .filter(move |(id, bin)| {
+            layout.zone_kind(*id).is_some_and(|kind| kind.is_storage())
+                && bin.allows(product.category)
+        })
+        .map(|(id, _)| id)
+}
+
+fn distance(layout: &Layout, id: BinId) -> f64 {
+    layout.bin(id).map_or(f64::INFINITY, |bin| bin.distance_m())
+}
+
+fn nearest_with_room<I: Iterator<Item = BinId>>(layout: &Layout, bins: I) -> Option<BinId> {
+    bins.filter(|id| layout.bin(*id).is_some_and(|bin| bin.has_room()))
+        .min_by(|a, b| {
+            distance(layout, *a)
+                .total_cmp(&distance(layout, *b))
+                .then(a.cmp(b))
+        })
+}
+
+fn no_bin(layout: &Layout, product: &Product) -> &'static str {
+    if storage_bins(layout, product).next().is_none() {
+        "No bin allows this category"
+    } else {
+        "Queue overflow"
+    }
+}
+
+pub struct CategoryZones;
+
+impl PutAwayStrategy for CategoryZones {
+    fn name(&self) -> &'static str {
+        "category zones"
+    }
+
+    fn choose_bin(&self, layout: &Layout, product: &Product) -> Result<BinId, &'static str> {
+        layout.put_away(product)
+    }
+}
+
+pub struct ClosestEmptyBin;
+
+impl PutAwayStrategy for ClosestEmptyBin {
+    fn name(&self) -> &'static str {
+        "closest empty bin"
+    }
+
+    fn choose_bin(&self, layout: &Layout, product: &Product) -> Result<BinId, &'static str> {
+        let empty = storage_bins(layout, product)
+            .filter(|id| layout.bin(*id).is_some_and(|bin| bin.is_empty()));
+        nearest_with_room(layout, empty)
+            .or_else(|| nearest_with_room(layout, storage_bins(layout, product)))
+            .ok_or_else(|| no_bin(layout, product))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AbcClass {
+    A,
+    B,
+    C,
+}
+
+// Быстрые SKU (A) ближе к воротам, медленные (C) — дальше
+pub struct AbcSlotting {
+    classes: HashMap<String, AbcClass>,
+}
+
+impl AbcSlotting {
+    pub fn new() -> Self {
+        Self {
+            classes: HashMap::new(),
+        }
+    }
+
+    pub fn classify(mut self, sku: &str, class: AbcClass) -> Self {
+        self.classes.insert(sku.to_string(), class);
+        self
+    }
+
+    pub fn from_velocity(velocity: &HashMap<String, f64>) -> Self {
+        let mut skus: Vec<(&String, &f64)> = velocity.iter().collect();
+        skus.sort_by(|a, b| b.1.total_cmp(a.1).then(a.0.cmp(b.0)));
+        let total: f64 = skus.iter().map(|(_, v)| **v).sum();
+        let mut running = 0.0;
+        let mut slotting = Self::new();
+        for (sku, v) in skus {
+            let share = if total > 0.0 { running / total } else { 1.0 };
+            let class = if share < 0.8 {
+                AbcClass::A
+            } else if share < 0.95 {
+                AbcClass::B
+            } else {
+                AbcClass::C
+            };
+            running += v;
+            slotting = slotting.classify(sku, class);
+        }
+        slotting
+    }
+
+    pub fn class(&self, sku: &str) -> AbcClass {
+        self.classes.get(sku).copied().unwrap_or(AbcClass::C)
+    }
+}
+
+impl Default for AbcSlotting {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PutAwayStrategy for AbcSlotting {
+    fn name(&self) -> &'static str {
+        "ABC velocity slotting"
+    }
+
+    fn choose_bin(&self, layout: &Layout, product: &Product) -> Result<BinId, &'static str> {
+        let mut bins: Vec<BinId> = storage_bins(layout, product).collect();
+        bins.sort_by(|a, b| {
+            distance(layout, *a)
+                .total_cmp(&distance(layout, *b))
+                .then(a.cmp(b))
+        });
+        // Делим ячейки по удалённости на три равные полосы
+        let band = bins.len().div_ceil(3).max(1);
+        let start = match self.class(&product.sku) {
+            AbcClass::A => 0,
+            AbcClass::B => band,
+            AbcClass::C => 2 * band,
+        }
+        .min(bins.len());
+        let has_room = |id: &&BinId| layout.bin(**id).is_some_and(|bin| bin.has_room());
+        bins[start..]
+            .iter()
+            .find(has_room)
+            .or_else(|| bins[..start].iter().rev().find(has_room))
+            .copied()
+            .ok_or_else(|| no_bin(layout, product))
+    }
+}
+
+pub struct ConsolidateSku;
+
+impl PutAwayStrategy for ConsolidateSku {
+    fn name(&self) -> &'static str {
+        "consolidate same SKU"
+    }
+
+    fn choose_bin(&self, layout: &Layout, product: &Product) -> Result<BinId, &'static str> {
+        let same_sku = storage_bins(layout, product).filter(|id| {
+            layout.bin(*id).is_some_and(|bin| {
+                bin.contents()
+                    .any(|stored| stored.product.sku == product.sku)
+            })
+        });
+        nearest_with_room(layout, same_sku)
+            .or_else(|| nearest_with_room(layout, storage_bins(layout, product)))
+            .ok_or_else(|| no_bin(layout, product))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PickCandidate {
+    pub bin: BinId,
+    pub received: u64,
+    pub expires_at: Option<f64>,
+    pub distance_m: f64,
+}
+
+pub trait PickingStrategy {
+    fn name(&self) -> &'static str;
+    fn choose(&self, candidates: &[PickCandidate]) -> Option<PickCandidate>;
+}
+
+pub struct Fifo;
+
+impl PickingStrategy for Fifo {
+    fn name(&self) -> &'static str {
+        "FIFO"
+    }
+
+    fn choose(&self, candidates: &[PickCandidate]) -> Option<PickCandidate> {
+        candidates.iter().min_by_key(|c| c.received).copied()
+    }
+}
+
+pub struct Lifo;
+
+impl PickingStrategy for Lifo {
+    fn name(&self) -> &'static str {
+        "LIFO"
+    }
+
+    fn choose(&self, candidates: &[PickCandidate]) -> Option<PickCandidate> {
+        candidates.iter().max_by_key(|c| c.received).copied()
+    }
+}
+
+pub struct Fefo;
+
+impl PickingStrategy for Fefo {
+    fn name(&self) -> &'static str {
+        "FEFO"
+    }
+
+    fn choose(&self, candidates: &[PickCandidate]) -> Option<PickCandidate> {
+        candidates
+            .iter()
+            .min_by(|a, b| {
+                let a_expiry = a.expires_at.unwrap_or(f64::INFINITY);
+                let b_expiry = b.expires_at.unwrap_or(f64::INFINITY);
+                a_expiry
+                    .total_cmp(&b_expiry)
+                    .then(a.received.cmp(&b.received))
+            })
+            .copied()
+    }
+}
+
+pub struct LeastTravel;
+
+impl PickingStrategy for LeastTravel {
+    fn name(&self) -> &'static str {
+        "least travel"
+    }
+
+    fn choose(&self, candidates: &[PickCandidate]) -> Option<PickCandidate> {
+        candidates
+            .iter()
+            .min_by(|a, b| {
+                a.distance_m
+                    .total_cmp(&b.distance_m)
+                    .then(a.received.cmp(&b.received))
+            })
+            .copied()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct HandlingStats {
+    pub put_aways: usize,
+    pub picks: usize,
+    pub travel_m: f64,
+}
+
+impl HandlingStats {
+    pub fn record(&mut self, distance_m: f64, pick: bool) {
+        if pick {
+            self.picks += 1;
+        } else {
+            self.put_aways += 1;
+        }
+        // Путь от ворот до ячейки и обратно
+        self.travel_m += 2.0 * distance_m;
+    }
+
+    pub fn working_hours(&self, walk_m_per_hour: f64, hours_per_move: f64) -> f64 {
+        self.travel_m / walk_m_per_hour + (self.picks + self.put_aways) as f64 * hours_per_move
+    }
+
+    pub fn picks_per_hour(&self, walk_m_per_hour: f64, hours_per_move: f64) -> f64 {
+        let hours = self.working_hours(walk_m_per_hour, hours_per_move);
+        if hours == 0.0 {
+            return 0.0;
+        }
+        self.picks as f64 / hours
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::{Bin, Stored, Zone, ZoneKind};
+    use crate::models::{Category, Factory};
+
+    fn item(sku: &str) -> Product {
+        Factory::new("StrategyFactory".to_string())
+            .create_sku(sku)
+            .unwrap()
+    }
+
+    fn aisle_layout() -> Layout {
+        Layout::new(vec![
+            Zone::new(ZoneKind::Bulk)
+                .with_bin(Bin::new(2, 1, 0))
+                .with_bin(Bin::new(2, 2, 0))
+                .with_bin(Bin::new(2, 3, 0))
+                .with_bin(Bin::new(2, 4, 0))
+                .with_bin(Bin::new(2, 5, 0))
+                .with_bin(Bin::new(2, 6, 0)),
+        ])
+    }
+
+    fn bin(index: usize) -> BinId {
+        BinId {
+            zone: 0,
+            bin: index,
+        }
+    }
+
+    #[test]
+    fn test_closest_empty_bin() {
+        let mut layout = aisle_layout();
+        layout
+            .bin_mut(bin(0))
+            .unwrap()
+            .store(Stored {
+                received: 1,
+                product: item("a"),
+            })
+            .unwrap();
+
+        assert_eq!(ClosestEmptyBin.choose_bin(&layout, &item("a")), Ok(bin(1)));
+    }
+
+    #[test]
+    fn test_consolidate_same_sku() {
+        let mut layout = aisle_layout();
+        layout
+            .bin_mut(bin(3))
+            .unwrap()
+            .store(Stored {
+                received: 1,
+                product: item("a"),
+            })
+            .unwrap();
+
+        assert_eq!(ConsolidateSku.choose_bin(&layout, &item("a")), Ok(bin(3)));
+        assert_eq!(ConsolidateSku.choose_bin(&layout, &item("b")), Ok(bin(0)));
+    }
+
+    #[test]
+    fn test_abc_slotting_bands() {
+        let layout = aisle_layout();
+        let slotting = AbcSlotting::new()
+            .classify("fast", AbcClass::A)
+            .classify("medium", AbcClass::B);
+
+        assert_eq!(slotting.choose_bin(&layout, &item("fast")), Ok(bin(0)));
+        assert_eq!(slotting.choose_bin(&layout, &item("medium")), Ok(bin(2)));
+        assert_eq!(slotting.choose_bin(&layout, &item("slow")), Ok(bin(4)));
+    }
+
+    #[test]
+    fn test_abc_from_velocity() {
+        let velocity = HashMap::from([
+            ("fast".to_string(), 80.0),
+            ("medium".to_string(), 15.0),
+            ("slow".to_string(), 5.0),
+        ]);
+        let slotting = AbcSlotting::from_velocity(&velocity);
+
+        assert_eq!(slotting.class("fast"), AbcClass::A);
+        assert_eq!(slotting.class("medium"), AbcClass::B);
+        assert_eq!(slotting.class("slow"), AbcClass::C);
+    }
+
+    #[test]
+    fn test_strategies_respect_category_policy() {
+        let layout = Layout::new(vec![
+            Zone::new(ZoneKind::Bulk).with_bin(Bin::new(1, 1, 0).allow(&[Category::General])),
+        ]);
+        let mut factory = Factory::new("PolicyFactory".to_string());
+        factory.set_category("acid", Category::Hazardous);
+        let acid = factory.create_sku("acid").unwrap();
+
+        assert_eq!(
+            ClosestEmptyBin.choose_bin(&layout, &acid),
+            Err("No bin allows this category")
+        );
+        assert_eq!(
+            ConsolidateSku.choose_bin(&layout, &acid),
+            Err("No bin allows this category")
+        );
+        assert_eq!(
+            AbcSlotting::new().choose_bin(&layout, &acid),
+            Err("No bin allows this category")
+        );
+    }
+
+    #[test]
+    fn test_picking_strategies() {
+        let candidates = [
+            PickCandidate {
+                bin: bin(0),
+                received: 2,
+                expires_at: Some(50.0),
+                distance_m: 9.0,
+            },
+            PickCandidate {
+                bin: bin(1),
+                received: 1,
+                expires_at: None,
+                distance_m: 6.0,
+            },
+            PickCandidate {
+                bin: bin(2),
+                received: 3,
+                expires_at: Some(20.0),
+                distance_m: 3.0,
+            },
+        ];
+
+        assert_eq!(Fifo.choose(&candidates).unwrap().received, 1);
+        assert_eq!(Lifo.choose(&candidates).unwrap().received, 3);
+        assert_eq!(Fefo.choose(&candidates).unwrap().expires_at, Some(20.0));
+        assert_eq!(LeastTravel.choose(&candidates).unwrap().bin, bin(2));
+        assert!(Fifo.choose(&[]).is_none());
+    }
+
+    #[test]
+    fn test_handling_stats() {
+        let mut stats = HandlingStats::default();
+        stats.record(10.0, false);
+        stats.record(15.0, true);
+        stats.record(5.0, true);
+
+        assert_eq!(stats.travel_m, 60.0);
+        assert_eq!(stats.picks, 2);
+        assert_eq!(stats.working_hours(60.0, 0.0), 1.0);
+        assert_eq!(stats.picks_per_hour(60.0, 0.0), 2.0);
+        assert_eq!(HandlingStats::default().picks_per_hour(60.0, 0.1), 0.0);
+    }
+}
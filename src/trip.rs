@@ -0,0 +1,164 @@
+use crate::network::NodeId;
+use crate::routing::{Leg, Route};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TruckState {
+    Idle,
+    Loading,
+    EnRoute,
+    Unloading,
+    Refuelling,
+    Stranded,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trip {
+    route: Route,
+    leg: usize,
+    elapsed: f64,
+}
+
+impl Trip {
+    pub fn new(route: Route) -> Self {
+        Self {
+            route,
+            leg: 0,
+            elapsed: 0.0,
+        }
+    }
+
+    pub fn route(&self) -> &Route {
+        &self.route
+    }
+
+    pub fn current_leg(&self) -> Option<&Leg> {
+        self.route.legs.get(self.leg)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.leg >= self.route.legs.len()
+    }
+
+    pub fn leg_progress(&self) -> f64 {
+        match self.current_leg() {
+            Some(leg) if leg.travel_hours > 0.0 => self.elapsed / leg.travel_hours,
+            _ => 0.0,
+        }
+    }
+
+    pub fn remaining_hours(&self) -> f64 {
+        self.route.legs[self.leg.min(self.route.legs.len())..]
+            .iter()
+            .map(|leg| leg.travel_hours)
+            .sum::<f64>()
+            - self.elapsed
+    }
+
+    // Сколько часов нужно, чтобы проехать km от текущей точки;
+    // если km хватает до конца рейса, ограничения нет
+    pub fn hours_for_km(&self, km: f64) -> f64 {
+        let mut left = km;
+        let mut hours = 0.0;
+        let mut elapsed = self.elapsed;
+        for leg in &self.route.legs[self.leg.min(self.route.legs.len())..] {
+            let leg_hours = leg.travel_hours - elapsed;
+            let leg_km = if leg.travel_hours > 0.0 {
+                leg.distance_km * leg_hours / leg.travel_hours
+            } else {
+                0.0
+            };
+            if left < leg_km {
+                return hours + leg_hours * left / leg_km;
+            }
+            left -= leg_km;
+            hours += leg_hours;
+            elapsed = 0.0;
+        }
+        f64::INFINITY
+    }
+
+    // Возвращает последнюю пройденную вершину и пройденные километры
+    pub fn advance(&mut self, hours: f64) -> (Option<NodeId>, f64) {
+        let mut left = hours;
+        let mut reached = None;
+        let mut km = 0.0;
+        while let Some(leg) = self.route.legs.get(self.leg).copied() {
+            let remaining = leg.travel_hours - self.elapsed;
+            if left < remaining {
+                self.elapsed += left;
+                if leg.travel_hours > 0.0 {
+                    km += leg.distance_km * left / leg.travel_hours;
+                }
+                break;
+            }
+            left -= remaining;
+            km += leg.distance_km * (1.0 - self.leg_progress());
+            self.leg += 1;
+            self.elapsed = 0.0;
+            reached = Some(leg.to);
+        }
+        (reached, km)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_route() -> Route {
+        Route {
+            path: vec![NodeId(0), NodeId(1), NodeId(2)],
+            legs: vec![
+                Leg { from: NodeId(0), to: NodeId(1), distance_km: 60.0, travel_hours: 1.0 },
+                Leg { from: NodeId(1), to: NodeId(2), distance_km: 40.0, travel_hours: 0.5 },
+            ],
+            distance_km: 100.0,
+            eta_hours: 1.5,
+        }
+    }
+
+    #[test]
+    fn test_trip_advances_through_legs() {
+        let mut trip = Trip::new(sample_route());
+
+        let (reached, km) = trip.advance(0.5);
+        assert_eq!(reached, None);
+        assert_eq!(km, 30.0);
+        assert_eq!(trip.leg_progress(), 0.5);
+        assert_eq!(trip.remaining_hours(), 1.0);
+
+        let (reached, km) = trip.advance(0.75);
+        assert_eq!(reached, Some(NodeId(1)));
+        assert_eq!(km, 50.0);
+        assert_eq!(trip.current_leg().unwrap().to, NodeId(2));
+
+        let (reached, _) = trip.advance(10.0);
+        assert_eq!(reached, Some(NodeId(2)));
+        assert!(trip.is_finished());
+        assert_eq!(trip.remaining_hours(), 0.0);
+    }
+
+    #[test]
+    fn test_hours_for_km_from_current_position() {
+        let mut trip = Trip::new(sample_route());
+        trip.advance(0.5);
+
+        assert_eq!(trip.hours_for_km(15.0), 0.25);
+        assert_eq!(trip.hours_for_km(50.0), 0.75);
+        // Хватает до конца рейса — ограничения нет
+        assert_eq!(trip.hours_for_km(70.0), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_empty_trip_is_finished() {
+        let trip = Trip::new(Route {
+            path: vec![NodeId(3)],
+            legs: Vec::new(),
+            distance_km: 0.0,
+            eta_hours: 0.0,
+        });
+
+        assert!(trip.is_finished());
+        assert_eq!(trip.route().destination(), NodeId(3));
+    }
+}
@@ -0,0 +1,627 @@
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::models::Truck;
+use crate::network::{NodeId, RoadNetwork};
+use crate::routing::{DistanceMatrix, Metric, Route, VehicleProfile};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Customer {
+    pub node: NodeId,
+    pub demand: usize,
+    pub open: f64,
+    pub close: f64,
+    pub service_hours: f64,
+}
+
+impl Customer {
+    pub fn new(node: NodeId, demand: usize) -> Self {
+        Self {
+            node,
+            demand,
+            open: 0.0,
+            close: f64::INFINITY,
+            service_hours: 0.0,
+        }
+    }
+
+    pub fn with_window(mut self, open: f64, close: f64) -> Self {
+        self.open = open;
+        self.close = close;
+        self
+    }
+
+    pub fn with_service(mut self, hours: f64) -> Self {
+        self.service_hours = hours;
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VehicleRoute {
+    pub truck: usize,
+    // Индексы клиентов в порядке объезда
+    pub stops: Vec<usize>,
+    pub load: usize,
+    pub distance_km: f64,
+    pub hours: f64,
+    pub route: Route,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VrpSolution {
+    pub routes: Vec<VehicleRoute>,
+    pub unassigned: Vec<usize>,
+    pub cost: f64,
+}
+
+impl VrpSolution {
+    pub fn distance_km(&self) -> f64 {
+        self.routes.iter().map(|route| route.distance_km).sum()
+    }
+}
+
+pub struct VrpProblem<'a> {
+    network: &'a RoadNetwork,
+    depot: NodeId,
+    customers: Vec<Customer>,
+    capacities: Vec<usize>,
+    profiles: Vec<VehicleProfile>,
+    metric: Metric,
+    start: f64,
+    budget: Duration,
+}
+
+struct Evaluator<'a> {
+    // Без ограничений — для рейсов, ещё не закреплённых за машиной
+    open: Rc<DistanceMatrix>,
+    // Вместимость и матрица по профилю каждой машины
+    vehicles: Vec<(usize, Rc<DistanceMatrix>)>,
+    depot: NodeId,
+    customers: &'a [Customer],
+    metric: Metric,
+    start: f64,
+}
+
+impl Evaluator<'_> {
+    fn matrix(&self, vehicle: Option<usize>) -> &DistanceMatrix {
+        match vehicle {
+            Some(vehicle) => &self.vehicles[vehicle].1,
+            None => &self.open,
+        }
+    }
+
+    fn leg(matrix: &DistanceMatrix, from: NodeId, to: NodeId) -> Option<(f64, f64)> {
+        Some((matrix.distance_km(from, to)?, matrix.hours(from, to)?))
+    }
+
+    fn weight(&self, from: NodeId, to: NodeId) -> f64 {
+        match (Self::leg(&self.open, from, to), self.metric) {
+            (Some((km, _)), Metric::Distance) => km,
+            (Some((_, hours)), Metric::Time) => hours,
+            (None, _) => f64::INFINITY,
+        }
+    }
+
+    // Пробег и длительность рейса с ожиданием открытия окон; None, если окно пропущено
+    fn evaluate(&self, stops: &[usize], vehicle: Option<usize>) -> Option<(f64, f64)> {
+        let matrix = self.matrix(vehicle);
+        let mut at = self.depot;
+        let mut clock = self.start;
+        let mut km = 0.0;
+        for &stop in stops {
+            let customer = &self.customers[stop];
+            let (distance, hours) = Self::leg(matrix, at, customer.node)?;
+            km += distance;
+            clock = (clock + hours).max(customer.open);
+            if clock > customer.close {
+                return None;
+            }
+            clock += customer.service_hours;
+            at = customer.node;
+        }
+        let (distance, hours) = Self::leg(matrix, at, self.depot)?;
+        Some((km + distance, clock + hours - self.start))
+    }
+
+    fn load(&self, stops: &[usize]) -> usize {
+        stops.iter().map(|&stop| self.customers[stop].demand).sum()
+    }
+
+    fn cost(&self, stops: &[usize], vehicle: usize) -> Option<f64> {
+        self.cost_with(stops, self.vehicles[vehicle].0, Some(vehicle))
+    }
+
+    fn cost_or_infinity(&self, stops: &[usize], vehicle: usize) -> f64 {
+        self.cost(stops, vehicle).unwrap_or(f64::INFINITY)
+    }
+
+    fn cost_with(&self, stops: &[usize], capacity: usize, vehicle: Option<usize>) -> Option<f64> {
+        if stops.is_empty() {
+            return Some(0.0);
+        }
+        if self.load(stops) > capacity {
+            return None;
+        }
+        let (km, hours) = self.evaluate(stops, vehicle)?;
+        Some(match self.metric {
+            Metric::Distance => km,
+            Metric::Time => hours,
+        })
+    }
+
+    fn total(&self, routes: &[Vec<usize>]) -> f64 {
+        routes
+            .iter()
+            .enumerate()
+            .map(|(vehicle, stops)| self.cost_or_infinity(stops, vehicle))
+            .sum()
+    }
+}
+
+impl<'a> VrpProblem<'a> {
+    pub fn new(network: &'a RoadNetwork, depot: NodeId) -> Self {
+        Self {
+            network,
+            depot,
+            customers: Vec::new(),
+            capacities: Vec::new(),
+            profiles: Vec::new(),
+            metric: Metric::Distance,
+            start: 0.0,
+            budget: Duration::from_millis(100),
+        }
+    }
+
+    pub fn with_customer(mut self, customer: Customer) -> Self {
+        self.customers.push(customer);
+        self
+    }
+
+    // Номер рейса в решении совпадает с индексом грузовика в этом срезе
+    pub fn with_fleet(mut self, trucks: &[Truck]) -> Self {
+        self.capacities = trucks.iter().map(|truck| truck.capacity()).collect();
+        self.profiles = trucks.iter().map(|truck| *truck.profile()).collect();
+        self
+    }
+
+    pub fn with_metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    pub fn with_start(mut self, hours: f64) -> Self {
+        self.start = hours;
+        self
+    }
+
+    pub fn with_time_budget(mut self, budget: Duration) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    pub fn solve(&self) -> VrpSolution {
+        let deadline = Instant::now() + self.budget;
+        let vehicles = self
+            .capacities
+            .iter()
+            .zip(&self.profiles)
+            .map(|(&capacity, profile)| (capacity, self.network.distance_matrix(Some(profile))))
+            .collect();
+        let evaluator = Evaluator {
+            open: self.network.distance_matrix(None),
+            vehicles,
+            depot: self.depot,
+            customers: &self.customers,
+            metric: self.metric,
+            start: self.start,
+        };
+        let (mut routes, mut unassigned) = self.savings(&evaluator);
+        self.insert_unassigned(&evaluator, &mut routes, &mut unassigned);
+        improve(&evaluator, &mut routes, deadline);
+        self.insert_unassigned(&evaluator, &mut routes, &mut unassigned);
+        unassigned.sort();
+
+        let cost = evaluator.total(&routes);
+        let routes = routes
+            .into_iter()
+            .enumerate()
+            .filter(|(_, stops)| !stops.is_empty())
+            .filter_map(|(truck, stops)| {
+                let (distance_km, hours) = evaluator.evaluate(&stops, Some(truck))?;
+                Some(VehicleRoute {
+                    truck,
+                    load: evaluator.load(&stops),
+                    route: self.route_through(&stops, &self.profiles[truck])?,
+                    stops,
+                    distance_km,
+                    hours,
+                })
+            })
+            .collect();
+        VrpSolution {
+            routes,
+            unassigned,
+            cost,
+        }
+    }
+
+    // Метод сбережений Кларка — Райта, затем крупные рейсы достаются крупным машинам
+    fn savings(&self, evaluator: &Evaluator) -> (Vec<Vec<usize>>, Vec<usize>) {
+        let largest = self.capacities.iter().copied().max().unwrap_or(0);
+        let mut unassigned = Vec::new();
+        let mut routes: Vec<Vec<usize>> = Vec::new();
+        let mut route_of: Vec<Option<usize>> = vec![None; self.customers.len()];
+        for (index, customer) in self.customers.iter().enumerate() {
+            if customer.demand <= largest && evaluator.evaluate(&[index], None).is_some() {
+                route_of[index] = Some(routes.len());
+                routes.push(vec![index]);
+            } else {
+                unassigned.push(index);
+            }
+        }
+
+        let depot = self.depot;
+        let mut savings: Vec<(f64, usize, usize)> = Vec::new();
+        for i in 0..self.customers.len() {
+            for j in 0..self.customers.len() {
+                if i == j || route_of[i].is_none() || route_of[j].is_none() {
+                    continue;
+                }
+                let (a, b) = (self.customers[i].node, self.customers[j].node);
+                let saving = evaluator.weight(a, depot) + evaluator.weight(depot, b)
+                    - evaluator.weight(a, b);
+                savings.push((saving, i, j));
+            }
+        }
+        savings.sort_by(|x, y| y.0.total_cmp(&x.0).then(x.1.cmp(&y.1)).then(x.2.cmp(&y.2)));
+
+        for (saving, i, j) in savings {
+            if saving <= 0.0 {
+                break;
+            }
+            let (Some(ri), Some(rj)) = (route_of[i], route_of[j]) else {
+                continue;
+            };
+            if ri == rj || routes[ri].last() != Some(&i) || routes[rj].first() != Some(&j) {
+                continue;
+            }
+            let merged: Vec<usize> = routes[ri]
+                .iter()
+                .chain(routes[rj].iter())
+                .copied()
+                .collect();
+            if evaluator.cost_with(&merged, largest, None).is_none() {
+                continue;
+            }
+            for &stop in &routes[rj] {
+                route_of[stop] = Some(ri);
+            }
+            routes[rj].clear();
+            routes[ri] = merged;
+        }
+
+        routes.retain(|stops| !stops.is_empty());
+        routes.sort_by_key(|stops| std::cmp::Reverse(evaluator.load(stops)));
+        let mut trucks: Vec<usize> = (0..self.capacities.len()).collect();
+        trucks.sort_by_key(|&truck| (std::cmp::Reverse(self.capacities[truck]), truck));
+        let mut assigned = vec![Vec::new(); self.capacities.len()];
+        // Рейс должен пройти по дорогам, открытым для своей машины
+        for (stops, truck) in routes.iter().zip(trucks.iter()) {
+            if evaluator.cost(stops, *truck).is_some() {
+                assigned[*truck] = stops.clone();
+            } else {
+                unassigned.extend(stops);
+            }
+        }
+        for stops in routes.iter().skip(trucks.len()) {
+            unassigned.extend(stops);
+        }
+        (assigned, unassigned)
+    }
+
+    // Клиентов без рейса пробуем вставить в самое дешёвое допустимое место
+    fn insert_unassigned(
+        &self,
+        evaluator: &Evaluator,
+        routes: &mut [Vec<usize>],
+        unassigned: &mut Vec<usize>,
+    ) {
+        let mut left = Vec::new();
+        for customer in unassigned.drain(..) {
+            let mut best: Option<(f64, usize, usize)> = None;
+            for (r, stops) in routes.iter().enumerate() {
+                let Some(before) = evaluator.cost(stops, r) else {
+                    continue;
+                };
+                for position in 0..=stops.len() {
+                    let mut candidate = stops.clone();
+                    candidate.insert(position, customer);
+                    let Some(after) = evaluator.cost(&candidate, r) else {
+                        continue;
+                    };
+                    if best.is_none_or(|(delta, _, _)| after - before < delta) {
+                        best = Some((after - before, r, position));
+                    }
+                }
+            }
+            match best {
+                Some((_, r, position)) => routes[r].insert(position, customer),
+                None => left.push(customer),
+            }
+        }
+        *unassigned = left;
+    }
+
+    fn route_through(&self, stops: &[usize], profile: &VehicleProfile) -> Option<Route> {
+        let mut nodes = vec![self.depot];
+        nodes.extend(stops.iter().map(|&stop| self.customers[stop].node));
+        nodes.push(self.depot);
+        let mut route = Route {
+            path: vec![self.depot],
+            legs: Vec::new(),
+            distance_km: 0.0,
+            eta_hours: 0.0,
+        };
+        for pair in nodes.windows(2) {
+            let part = self
+                .network
+                .route_with(pair[0], pair[1], Metric::Time, Some(profile))?;
+            route.path.extend(part.path.iter().skip(1));
+            route.legs.extend(part.legs);
+            route.distance_km += part.distance_km;
+            route.eta_hours += part.eta_hours;
+        }
+        Some(route)
+    }
+}
+
+fn accept(
+    evaluator: &Evaluator,
+    candidate: &[usize],
+    vehicle: usize,
+    current: f64,
+) -> Option<f64> {
+    evaluator
+        .cost(candidate, vehicle)
+        .filter(|cost| *cost < current - 1e-9)
+}
+
+// Локальный поиск: 2-opt внутри рейса, перенос и обмен клиентов между рейсами.
+// Принимаем первое улучшение, пока они есть и не истёк бюджет времени
+fn improve(
+    evaluator: &Evaluator,
+    routes: &mut [Vec<usize>],
+    deadline: Instant,
+) {
+    let mut improved = true;
+    while improved && Instant::now() < deadline {
+        improved = false;
+
+        for (r, route) in routes.iter_mut().enumerate() {
+            let current = evaluator.cost_or_infinity(route, r);
+            'two_opt: for i in 0..route.len() {
+                for j in i + 1..route.len() {
+                    let mut candidate = route.clone();
+                    candidate[i..=j].reverse();
+                    if accept(evaluator, &candidate, r, current).is_some() {
+                        *route = candidate;
+                        improved = true;
+                        break 'two_opt;
+                    }
+                }
+            }
+        }
+
+        'relocate: for from in 0..routes.len() {
+            for i in 0..routes[from].len() {
+                for to in 0..routes.len() {
+                    let positions = if from == to {
+                        routes[to].len()
+                    } else {
+                        routes[to].len() + 1
+                    };
+                    for position in 0..positions {
+                        let mut source = routes[from].clone();
+                        let customer = source.remove(i);
+                        let mut target = if from == to {
+                            source.clone()
+                        } else {
+                            routes[to].clone()
+                        };
+                        target.insert(position, customer);
+                        let (before, after) = if from == to {
+                            (
+                                evaluator.cost_or_infinity(&routes[from], from),
+                                evaluator.cost(&target, to),
+                            )
+                        } else {
+                            (
+                                evaluator.cost_or_infinity(&routes[from], from)
+                                    + evaluator.cost_or_infinity(&routes[to], to),
+                                evaluator
+                                    .cost(&source, from)
+                                    .zip(evaluator.cost(&target, to))
+                                    .map(|(a, b)| a + b),
+                            )
+                        };
+                        if after.is_some_and(|after| after < before - 1e-9) {
+                            if from != to {
+                                routes[from] = source;
+                            }
+                            routes[to] = target;
+                            improved = true;
+                            break 'relocate;
+                        }
+                    }
+                }
+            }
+        }
+
+        'swap: for a in 0..routes.len() {
+            for b in a + 1..routes.len() {
+                let before =
+                    evaluator.cost_or_infinity(&routes[a], a) + evaluator.cost_or_infinity(&routes[b], b);
+                for i in 0..routes[a].len() {
+                    for j in 0..routes[b].len() {
+                        let mut left = routes[a].clone();
+                        let mut right = routes[b].clone();
+                        std::mem::swap(&mut left[i], &mut right[j]);
+                        let after = evaluator
+                            .cost(&left, a)
+                            .zip(evaluator.cost(&right, b))
+                            .map(|(x, y)| x + y);
+                        if after.is_some_and(|after| after < before - 1e-9) {
+                            routes[a] = left;
+                            routes[b] = right;
+                            improved = true;
+                            break 'swap;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::{Location, Restrictions, SiteKind};
+
+    // Склад в центре, клиенты по сторонам света и ещё один рядом с востоком
+    fn star() -> (RoadNetwork, NodeId, Vec<NodeId>) {
+        let mut network = RoadNetwork::new();
+        let depot = network.add_location(Location::new("depot", SiteKind::Warehouse, 0.0, 0.0));
+        let points = [
+            (10.0, 0.0),
+            (0.0, 10.0),
+            (-10.0, 0.0),
+            (0.0, -10.0),
+            (12.0, 2.0),
+        ];
+        let nodes: Vec<NodeId> = points
+            .iter()
+            .enumerate()
+            .map(|(i, (x, y))| {
+                network.add_location(Location::new(&format!("c{}", i), SiteKind::Market, *x, *y))
+            })
+            .collect();
+        for (i, &a) in nodes.iter().enumerate() {
+            network.add_two_way_road(depot, a, 10.0, 0.2).unwrap();
+            let b = nodes[(i + 1) % nodes.len()];
+            network.add_two_way_road(a, b, 14.0, 0.28).unwrap();
+        }
+        network
+            .add_two_way_road(nodes[0], nodes[4], 3.0, 0.06)
+            .unwrap();
+        (network, depot, nodes)
+    }
+
+    fn fleet(capacities: &[usize]) -> Vec<Truck> {
+        capacities
+            .iter()
+            .enumerate()
+            .map(|(i, &capacity)| Truck::new(format!("T{}", i), capacity))
+            .collect()
+    }
+
+    #[test]
+    fn test_savings_merge_neighbours() {
+        let (network, depot, nodes) = star();
+        let problem = VrpProblem::new(&network, depot)
+            .with_customer(Customer::new(nodes[0], 2))
+            .with_customer(Customer::new(nodes[4], 2))
+            .with_fleet(&fleet(&[10, 10]));
+
+        let solution = problem.solve();
+        assert_eq!(solution.routes.len(), 1);
+        assert_eq!(solution.routes[0].load, 4);
+        assert_eq!(solution.distance_km(), 23.0);
+        assert!(solution.unassigned.is_empty());
+    }
+
+    #[test]
+    fn test_capacity_splits_routes() {
+        let (network, depot, nodes) = star();
+        let mut problem = VrpProblem::new(&network, depot).with_fleet(&fleet(&[4, 4, 4]));
+        for &node in &nodes[..4] {
+            problem = problem.with_customer(Customer::new(node, 2));
+        }
+
+        let solution = problem.solve();
+        assert!(solution.unassigned.is_empty());
+        assert!(solution.routes.iter().all(|route| route.load <= 4));
+        assert_eq!(
+            solution
+                .routes
+                .iter()
+                .map(|route| route.stops.len())
+                .sum::<usize>(),
+            4
+        );
+        // Соседние клиенты объединяются: 10 + 14 + 10 на пару лучше, чем 20 + 20
+        assert_eq!(solution.distance_km(), 68.0);
+    }
+
+    #[test]
+    fn test_time_windows_and_unserved_customers() {
+        let (network, depot, nodes) = star();
+        let problem = VrpProblem::new(&network, depot)
+            .with_metric(Metric::Time)
+            .with_customer(Customer::new(nodes[0], 1).with_window(0.0, 0.1))
+            .with_customer(
+                Customer::new(nodes[1], 1)
+                    .with_window(1.0, 2.0)
+                    .with_service(0.5),
+            )
+            .with_customer(Customer::new(nodes[2], 20))
+            .with_fleet(&fleet(&[5]));
+
+        let solution = problem.solve();
+        // Первое окно закрывается раньше, чем до клиента можно доехать, третьему мало места
+        assert_eq!(solution.unassigned, vec![0, 2]);
+        assert_eq!(solution.routes[0].stops, vec![1]);
+        // Ждём открытия окна в 1.0, обслуживаем полчаса и возвращаемся
+        assert!((solution.routes[0].hours - 1.7).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_routes_are_dispatchable() {
+        let (network, depot, nodes) = star();
+        let trucks = fleet(&[10]);
+        let problem = VrpProblem::new(&network, depot)
+            .with_customer(Customer::new(nodes[1], 1))
+            .with_customer(Customer::new(nodes[3], 1))
+            .with_fleet(&trucks);
+
+        let solution = problem.solve();
+        let plan = &solution.routes[0];
+        assert_eq!(plan.route.origin(), depot);
+        assert_eq!(plan.route.destination(), depot);
+        assert_eq!(plan.route.distance_km, plan.distance_km);
+
+        let mut truck = Truck::new("T0".to_string(), 10).with_location(depot);
+        assert_eq!(truck.dispatch(plan.route.clone()), Ok(()));
+    }
+
+    #[test]
+    fn test_routes_follow_truck_profile() {
+        let (mut network, depot, nodes) = star();
+        let low_bridge = Restrictions {
+            max_height_m: Some(3.0),
+            ..Restrictions::default()
+        };
+        network.restrict_road(depot, nodes[0], low_bridge).unwrap();
+        network.restrict_road(nodes[0], depot, low_bridge).unwrap();
+        let problem = VrpProblem::new(&network, depot)
+            .with_customer(Customer::new(nodes[0], 1))
+            .with_fleet(&fleet(&[10]));
+
+        let solution = problem.solve();
+        let plan = &solution.routes[0];
+        // Под мостом не пройти — объезд через соседа: 10 + 3 туда и обратно
+        assert_eq!(plan.distance_km, 26.0);
+        assert_eq!(plan.route.path, vec![depot, nodes[4], nodes[0], nodes[4], depot]);
+    }
+}